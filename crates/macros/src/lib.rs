@@ -8,11 +8,26 @@ pub fn derive_parsable_enum(input: TokenStream) -> TokenStream {
     let name = input.ident;
 
     TokenStream::from(quote! {
-        impl crate::parse::Parsable for #name {
+        impl<'a> crate::parse::Parsable<'a> for #name {
             #[inline(always)]
-            fn parse(parser: &mut impl crate::parse::Parser, _ctx: impl crate::parse::ParseContext) -> Result<Self, crate::parse::ParseError> {
+            fn parse(parser: &mut impl crate::parse::Parser<'a>, _ctx: impl crate::parse::ParseContext) -> Result<Self, crate::parse::ParseError> {
                 parser.next_enum()
             }
         }
     })
 }
+
+#[proc_macro_derive(EncodableEnum)]
+pub fn derive_encodable_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+
+    TokenStream::from(quote! {
+        impl crate::encode::Encodable for #name {
+            #[inline(always)]
+            fn encode(&self, writer: &mut impl crate::encode::Writer, _ctx: impl crate::parse::ParseContext) {
+                writer.write_enum(*self)
+            }
+        }
+    })
+}
@@ -0,0 +1,463 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use pag_parser::{
+    visit::{image_reference, Composition, CompositionGraph},
+    PagRef, TagBody,
+};
+use serde::Serialize;
+
+/// Name of the manifest written next to the extracted files
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// A layer referring to an extracted asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LayerRef {
+    /// ID of the composition containing the layer
+    pub composition: u32,
+    /// ID of the layer
+    pub layer: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageEntry {
+    pub file: String,
+    pub id: u32,
+    pub scale_factor: f32,
+    pub layers: Vec<LayerRef>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioEntry {
+    pub file: String,
+    pub composition: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VideoEntry {
+    /// Annex-B H.264 stream
+    pub file: String,
+    /// The same frames muxed into an MP4 file
+    pub mp4: String,
+    pub composition: u32,
+    pub width: i32,
+    pub height: i32,
+    pub frame_rate: f32,
+    pub frame_count: usize,
+    /// Frames reusing the first frame of a static time range, not stored in the video
+    pub static_frames: u64,
+    pub has_alpha: bool,
+    pub layers: Vec<LayerRef>,
+}
+
+/// An image of a changed region of a bitmap sequence frame
+#[derive(Debug, Serialize)]
+pub struct BitmapRectEntry {
+    pub file: String,
+    pub frame: usize,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BitmapEntry {
+    pub composition: u32,
+    pub width: i32,
+    pub height: i32,
+    pub frame_rate: f32,
+    pub frame_count: usize,
+    /// Frames storing the full picture, other frames only store the changed regions
+    pub key_frames: Vec<usize>,
+    pub rects: Vec<BitmapRectEntry>,
+    pub layers: Vec<LayerRef>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FontEntry {
+    pub family: String,
+    pub style: String,
+}
+
+/// Extracted files and the compositions/layers referring to them.
+/// Fonts are only referenced by name in PAG files, so no font file is written.
+#[derive(Debug, Default, Serialize)]
+pub struct Manifest {
+    pub images: Vec<ImageEntry>,
+    pub audios: Vec<AudioEntry>,
+    pub videos: Vec<VideoEntry>,
+    pub bitmaps: Vec<BitmapEntry>,
+    pub fonts: Vec<FontEntry>,
+}
+
+impl Manifest {
+    /// Names of all written files
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        let images = self.images.iter().map(|entry| entry.file.as_str());
+        let audios = self.audios.iter().map(|entry| entry.file.as_str());
+        let videos = self
+            .videos
+            .iter()
+            .flat_map(|entry| [entry.file.as_str(), entry.mp4.as_str()]);
+        let bitmaps = self
+            .bitmaps
+            .iter()
+            .flat_map(|entry| entry.rects.iter().map(|rect| rect.file.as_str()));
+        images.chain(audios).chain(videos).chain(bitmaps)
+    }
+}
+
+/// File extension of an encoded image detected by its magic bytes
+fn image_extension(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xff, 0xd8, 0xff, ..] => "jpg",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+        _ => "bin",
+    }
+}
+
+/// Layers of all compositions referring to each image ID
+fn image_layers(graph: &CompositionGraph) -> BTreeMap<u32, Vec<LayerRef>> {
+    let mut layers = BTreeMap::<u32, Vec<LayerRef>>::new();
+    for composition in graph.compositions() {
+        for layer in composition.layers() {
+            if let Some(reference) = image_reference(layer) {
+                layers
+                    .entry(reference.id.to_u32())
+                    .or_default()
+                    .push(LayerRef {
+                        composition: composition.id(),
+                        layer: layer.id.to_u32(),
+                    });
+            }
+        }
+    }
+    layers
+}
+
+/// PreCompose layers referring to a composition
+fn precompose_layers(graph: &CompositionGraph, id: u32) -> Vec<LayerRef> {
+    graph
+        .compositions()
+        .flat_map(|composition| {
+            graph
+                .precomposes(composition.id())
+                .iter()
+                .filter(|precompose| precompose.reference.id.to_u32() == id)
+                .map(move |precompose| LayerRef {
+                    composition: composition.id(),
+                    layer: precompose.layer.id.to_u32(),
+                })
+        })
+        .collect()
+}
+
+/// Write every embedded image, audio, video and bitmap frame of the file to `dir`, return the manifest
+pub fn extract(pag: &PagRef, dir: &Path) -> io::Result<Manifest> {
+    fs::create_dir_all(dir)?;
+    let mut manifest = Manifest::default();
+    let graph = pag.composition_graph();
+
+    let index = pag.image_index();
+    let mut images = index.iter().collect::<Vec<_>>();
+    images.sort_by_key(|image| image.id);
+    let mut image_layers = image_layers(&graph);
+    for image in images {
+        let file = format!("image_{}.{}", image.id, image_extension(image.file_bytes));
+        fs::write(dir.join(&file), image.file_bytes)?;
+        manifest.images.push(ImageEntry {
+            file,
+            id: image.id,
+            scale_factor: image.scale_factor,
+            layers: image_layers.remove(&image.id).unwrap_or_default(),
+        });
+    }
+
+    for composition in graph.compositions() {
+        if let Some(bytes) = composition.audio_bytes() {
+            let file = match manifest.audios.is_empty() {
+                true => "audio.mp4".to_string(),
+                false => format!("audio_{}.mp4", composition.id()),
+            };
+            fs::write(dir.join(&file), bytes)?;
+            manifest.audios.push(AudioEntry {
+                file,
+                composition: composition.id(),
+            });
+        }
+
+        let layers = precompose_layers(&graph, composition.id());
+        for (index, sequence) in composition.bitmap_sequences().enumerate() {
+            let (width, height) = (sequence.width.to_i32(), sequence.height.to_i32());
+            let mut name = format!("bitmap_{}_{}x{}", composition.id(), width, height);
+            // Sequences of the same size in one composition are told apart by their index
+            let taken = manifest.bitmaps.iter().any(|bitmap| {
+                (bitmap.composition, bitmap.width, bitmap.height)
+                    == (composition.id(), width, height)
+            });
+            if taken {
+                name = format!("{}_{}", name, index);
+            }
+            let mut rects = vec![];
+            for (frame, bitmap_frame) in sequence.bitmap_frames.iter().enumerate() {
+                for (i, rect) in bitmap_frame.bitmaps.iter().enumerate() {
+                    let file = format!(
+                        "{}_{}_{}.{}",
+                        name,
+                        frame,
+                        i,
+                        image_extension(&rect.file_bytes.data)
+                    );
+                    fs::write(dir.join(&file), &rect.file_bytes.data)?;
+                    rects.push(BitmapRectEntry {
+                        file,
+                        frame,
+                        x: rect.x.to_i32(),
+                        y: rect.y.to_i32(),
+                    });
+                }
+            }
+            manifest.bitmaps.push(BitmapEntry {
+                composition: composition.id(),
+                width,
+                height,
+                frame_rate: sequence.frame_rate,
+                frame_count: sequence.bitmap_frames.len(),
+                key_frames: sequence
+                    .bitmap_frames
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, frame)| frame.is_key_frame)
+                    .map(|(i, _)| i)
+                    .collect(),
+                rects,
+                layers: layers.clone(),
+            });
+        }
+
+        let Composition::Video(block) = composition else {
+            continue;
+        };
+        for (index, sequence) in composition.video_sequences().enumerate() {
+            let mut name = format!(
+                "video_{}_{}x{}",
+                composition.id(),
+                sequence.width,
+                sequence.height
+            );
+            // Likewise for video sequences of the same size
+            let taken = manifest
+                .videos
+                .iter()
+                .any(|video| video.file == format!("{}.h264", name));
+            if taken {
+                name = format!("{}_{}", name, index);
+            }
+            let file = format!("{}.h264", name);
+            let mut writer = BufWriter::new(File::create(dir.join(&file))?);
+            sequence.write_annexb(&mut writer)?;
+            writer.flush()?;
+            let mp4 = format!("{}.mp4", name);
+            fs::write(dir.join(&mp4), sequence.to_mp4(block.mp4_header(sequence)))?;
+            manifest.videos.push(VideoEntry {
+                file,
+                mp4,
+                composition: composition.id(),
+                width: sequence.width.to_i32(),
+                height: sequence.height.to_i32(),
+                frame_rate: sequence.frame_rate,
+                frame_count: sequence.video_frames.len(),
+                static_frames: sequence.static_frame_stats().static_frames,
+                has_alpha: block.has_alpha,
+                layers: layers.clone(),
+            });
+        }
+    }
+
+    for tag in &pag.tag_block.tags {
+        if let TagBody::FontTables(tables) = &tag.body {
+            manifest
+                .fonts
+                .extend(tables.font_datas.iter().map(|font| FontEntry {
+                    family: font.font_family.clone(),
+                    style: font.font_style.clone(),
+                }));
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&manifest).map_err(io::Error::other)?;
+    fs::write(dir.join(MANIFEST_NAME), json)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use pag_parser::{
+        encode::PagWriter,
+        parse::{EncodedInt32, EncodedUint32, PagParser, Time},
+        BitmapCompositionBlock, BitmapFrame, BitmapRect, BitmapSequence, ByteData, FileHeader,
+        ImageBytes, ImageReference, LayerBlock, LayerType, Pag, Tag, TagBlock, TagBody, TagCode,
+        VectorCompositionBlock, VideoCompositionBlock, VideoFrame, VideoSequence,
+    };
+
+    use super::{extract, MANIFEST_NAME};
+
+    #[test]
+    fn test_extract() {
+        let png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let layer = LayerBlock {
+            r#type: LayerType::Image,
+            id: EncodedUint32::from(3),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::ImageReference,
+                    TagBody::ImageReference(ImageReference {
+                        id: EncodedUint32::from(9),
+                    }),
+                )],
+            },
+        };
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![
+                    Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer)),
+                    Tag::new(
+                        TagCode::AudioBytes,
+                        TagBody::AudioBytes(ByteData::from_vec(b"ftyp".to_vec())),
+                    ),
+                ],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::ImageBytes,
+            TagBody::ImageBytes(ImageBytes {
+                id: EncodedUint32::from(9),
+                file_bytes: ByteData::from_vec(png.clone()),
+            }),
+        ));
+        pag.push_tag(Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        let input = PagWriter::write_all(&pag);
+        let pag = PagParser::parse_ref(&input).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("pag-extract-{}", std::process::id()));
+        let manifest = extract(&pag, &dir).unwrap();
+        assert_eq!(
+            manifest.files().collect::<Vec<_>>(),
+            ["image_9.png", "audio.mp4"]
+        );
+        assert_eq!(fs::read(dir.join("image_9.png")).unwrap(), png);
+        assert_eq!(fs::read(dir.join("audio.mp4")).unwrap(), b"ftyp");
+
+        let json = fs::read_to_string(dir.join(MANIFEST_NAME)).unwrap();
+        assert!(json.contains(
+            r#""layers": [
+        {
+          "composition": 1,
+          "layer": 3
+        }
+      ]"#
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn new_video_sequence() -> Tag<'static> {
+        let sequence = VideoSequence {
+            width: EncodedInt32::from(32),
+            height: EncodedInt32::from(16),
+            frame_rate: 24.,
+            alpha_start_x: None,
+            alpha_start_y: None,
+            sps_data: ByteData::from_vec(vec![0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e]),
+            pps_data: ByteData::from_vec(vec![0, 0, 0, 1, 0x68, 0xce]),
+            frame_count: EncodedUint32::from(1),
+            is_key_frame_flag: vec![true],
+            video_frames: vec![VideoFrame {
+                is_key_frame: true,
+                frame: Time::from(0),
+                file_bytes: ByteData::from_vec(vec![0, 0, 0, 1, 0x65, 0x88]),
+            }],
+            static_time_ranges: vec![],
+        };
+        Tag::new(TagCode::VideoSequence, TagBody::VideoSequence(sequence))
+    }
+
+    fn new_bitmap_sequence() -> Tag<'static> {
+        let sequence = BitmapSequence {
+            width: EncodedInt32::from(32),
+            height: EncodedInt32::from(16),
+            frame_rate: 24.,
+            frame_count: EncodedUint32::from(1),
+            is_key_frame_flag: vec![true],
+            bitmap_frames: vec![BitmapFrame {
+                is_key_frame: true,
+                bitmaps: vec![BitmapRect {
+                    x: EncodedInt32::from(0),
+                    y: EncodedInt32::from(0),
+                    file_bytes: ByteData::from_vec(b"\x89PNG\r\n\x1a\n".to_vec()),
+                }],
+            }],
+        };
+        Tag::new(TagCode::BitmapSequence, TagBody::BitmapSequence(sequence))
+    }
+
+    #[test]
+    fn test_extract_same_size_sequences() {
+        let video = VideoCompositionBlock {
+            id: EncodedUint32::from(1),
+            has_alpha: false,
+            tag_block: TagBlock {
+                tags: vec![new_video_sequence(), new_video_sequence()],
+            },
+        };
+        let bitmap = BitmapCompositionBlock {
+            id: EncodedUint32::from(2),
+            tag_block: TagBlock {
+                tags: vec![new_bitmap_sequence(), new_bitmap_sequence()],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VideoCompositionBlock,
+            TagBody::VideoCompositionBlock(video),
+        ));
+        pag.push_tag(Tag::new(
+            TagCode::BitmapCompositionBlock,
+            TagBody::BitmapCompositionBlock(bitmap),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        let input = PagWriter::write_all(&pag);
+        let pag = PagParser::parse_ref(&input).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("pag-extract-same-{}", std::process::id()));
+        let manifest = extract(&pag, &dir).unwrap();
+        let mut files = manifest.files().collect::<Vec<_>>();
+        files.sort_unstable();
+        assert_eq!(
+            files,
+            [
+                "bitmap_2_32x16_0_0.png",
+                "bitmap_2_32x16_1_0_0.png",
+                "video_1_32x16.h264",
+                "video_1_32x16.mp4",
+                "video_1_32x16_1.h264",
+                "video_1_32x16_1.mp4",
+            ]
+        );
+        for file in files {
+            assert!(dir.join(file).is_file());
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cell::RefCell, io};
+
+use pag_parser::{
+    visit::{Composition, Traversable},
+    PagRef, TagBlock, TagBody,
+};
+
+/// Print the file header, file attributes and compositions
+pub fn info(pag: &PagRef, out: &mut impl io::Write) -> io::Result<()> {
+    writeln!(out, "version: {}", pag.header.version)?;
+    writeln!(out, "length: {} bytes", pag.header.length)?;
+    writeln!(out, "compress method: {}", pag.header.compress_method)?;
+
+    let attributes = pag.tag_block.tags.iter().find_map(|tag| match &tag.body {
+        TagBody::FileAttributes(attributes) => Some(attributes),
+        _ => None,
+    });
+    if let Some(attributes) = attributes {
+        writeln!(out, "timestamp: {}", attributes.timestamp)?;
+        writeln!(out, "author: {}", attributes.author)?;
+        writeln!(out, "scene: {}", attributes.scene)?;
+        writeln!(out, "AE version: {}", attributes.ae_version)?;
+        writeln!(out, "plugin version: {}", attributes.plugin_version)?;
+        writeln!(out, "system version: {}", attributes.system_version)?;
+        for warning in &attributes.warnings {
+            writeln!(out, "warning: {}", warning)?;
+        }
+    }
+
+    let graph = pag.composition_graph();
+    let main = graph.main().map(|composition| composition.id());
+    writeln!(out, "compositions:")?;
+    for composition in graph.compositions() {
+        let kind = match composition {
+            Composition::Vector(_) => "vector",
+            Composition::Video(_) => "video",
+            Composition::Bitmap(_) => "bitmap",
+        };
+        write!(out, "  #{} {}", composition.id(), kind)?;
+        if let Some(attributes) = composition.attributes() {
+            write!(
+                out,
+                " {}x{}, {} frames @ {} fps",
+                attributes.width, attributes.height, attributes.duration, attributes.frame_rate
+            )?;
+            if attributes.frame_rate > 0. {
+                let seconds = attributes.duration.to_u64() as f32 / attributes.frame_rate;
+                write!(out, " ({:.2}s)", seconds)?;
+            }
+        }
+        if Some(composition.id()) == main {
+            write!(out, " [main]")?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Print the tag tree with tag codes and body lengths
+pub fn tree(pag: &PagRef, out: &mut impl io::Write) -> io::Result<()> {
+    write_tags(&pag.tag_block, 0, out)
+}
+
+fn write_tags(tag_block: &TagBlock, depth: usize, out: &mut impl io::Write) -> io::Result<()> {
+    for tag in tag_block.tags.iter().filter(|tag| !tag.is_empty()) {
+        write!(
+            out,
+            "{:indent$}{:?} [{}] {} bytes",
+            "",
+            tag.header.code,
+            u8::from(tag.header.code),
+            tag.header.length,
+            indent = depth * 2
+        )?;
+        if let TagBody::Invalid { error, .. } = &tag.body {
+            write!(out, " (invalid: {})", error)?;
+        }
+        writeln!(out)?;
+        if let Some(tag_block) = tag.body.tag_block() {
+            write_tags(tag_block, depth + 1, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// List layers with their ID, type, time span and name
+pub fn layers(pag: &PagRef, out: &mut impl io::Write) -> io::Result<()> {
+    let rows = RefCell::new(vec![]);
+    pag.traverse_layer(|layer| {
+        let span = match layer.get_layer_time_span() {
+            Some(span) => format!("{}..{}", span.start, span.end),
+            None => "-".to_string(),
+        };
+        rows.borrow_mut().push(format!(
+            "{:>6}  {:<10}  {:<12}  {}",
+            layer.get_layer_id(),
+            format!("{:?}", layer.get_layer_type()),
+            span,
+            layer.get_layer_name().unwrap_or_default()
+        ));
+    });
+
+    writeln!(out, "{:>6}  {:<10}  {:<12}  name", "id", "type", "frames")?;
+    for row in rows.into_inner() {
+        writeln!(out, "{}", row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pag_parser::{
+        encode::PagWriter,
+        parse::{EncodedUint32, PagParser, Time},
+        BlendMode, FileHeader, LayerAttributes, LayerBlock, LayerType, Pag, Property, Ratio, Tag,
+        TagBlock, TagBody, TagCode, TrackMatteType, VectorCompositionBlock,
+    };
+
+    fn new_pag() -> Vec<u8> {
+        let attributes = LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(0),
+            stretch: Ratio::one(),
+            start_time: Time::from(5),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::Value(0.),
+            duration: Time::from(20),
+            name: "Title".to_string(),
+        };
+        let layer = LayerBlock {
+            r#type: LayerType::Text,
+            id: EncodedUint32::from(7),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::LayerAttributesV2,
+                    TagBody::LayerAttributesV2(attributes),
+                )],
+            },
+        };
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer))],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        PagWriter::write_all(&pag)
+    }
+
+    fn output(f: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+        let mut out = vec![];
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_tree() {
+        let input = new_pag();
+        let pag = PagParser::parse_ref(&input).unwrap();
+        let tree = output(|out| super::tree(&pag, out));
+        let lines = tree.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("VectorCompositionBlock [2] "));
+        assert!(lines[1].starts_with("  LayerBlock [5] "));
+        assert!(lines[2].starts_with("    LayerAttributesV2 [52] "));
+    }
+
+    #[test]
+    fn test_layers() {
+        let input = new_pag();
+        let pag = PagParser::parse_ref(&input).unwrap();
+        let layers = output(|out| super::layers(&pag, out));
+        assert_eq!(
+            layers.lines().nth(1),
+            Some("     7  Text        5..25         Title")
+        );
+    }
+}
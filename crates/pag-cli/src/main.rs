@@ -0,0 +1,87 @@
+//! Command-line tool to inspect PAG files
+
+mod extract;
+mod inspect;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use pag_parser::parse::{PagParser, ParseError};
+
+#[derive(Debug, Parser)]
+#[command(name = "pag", version, about = "Inspect PAG files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the file header, file attributes and compositions
+    Info { file: PathBuf },
+    /// Print the tag tree with tag codes and body lengths
+    Tree { file: PathBuf },
+    /// List layers with their ID, type, time span and name
+    Layers { file: PathBuf },
+    /// Write embedded images, audio, video and bitmap frames to a directory, with a JSON manifest
+    Extract {
+        file: PathBuf,
+        /// Output directory, defaults to `<file name>_assets` in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Command {
+    fn file(&self) -> &Path {
+        match self {
+            Command::Info { file }
+            | Command::Tree { file }
+            | Command::Layers { file }
+            | Command::Extract { file, .. } => file,
+        }
+    }
+}
+
+fn run(command: &Command) -> Result<(), ParseError> {
+    let input = fs::read(command.file())?;
+    let pag = PagParser::parse_ref(&input)?;
+
+    let mut out = io::stdout().lock();
+    match command {
+        Command::Info { .. } => inspect::info(&pag, &mut out)?,
+        Command::Tree { .. } => inspect::tree(&pag, &mut out)?,
+        Command::Layers { .. } => inspect::layers(&pag, &mut out)?,
+        Command::Extract { file, output } => {
+            let dir = match output {
+                Some(output) => output.clone(),
+                None => {
+                    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+                    PathBuf::from(format!("{}_assets", stem))
+                }
+            };
+            let manifest = extract::extract(&pag, &dir)?;
+            for file in manifest.files().chain([extract::MANIFEST_NAME]) {
+                writeln!(out, "{}", dir.join(file).display())?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}: {}", cli.command.file().display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
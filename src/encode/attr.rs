@@ -0,0 +1,226 @@
+use crate::{
+    format::{Keyframe, KeyframeInterpolationType, Point, Property},
+    parse::{AttributeConfig, AttributeType, Parsable, BEZIER_PRECISION, SPATIAL_PRECISION},
+};
+
+use super::{
+    encodable::Encodable,
+    writer::{StreamWriter, Writer},
+};
+
+/// AttributeBlock 的编码器，标志位区域与内容区域分开写入，结束时依次合并到输出流
+#[derive(Debug, Default)]
+pub struct AttributeBlockWriter {
+    flags: StreamWriter,
+    content: StreamWriter,
+}
+
+impl AttributeBlockWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在标志位区域中写入自定义的 UB[num_bits] 数据
+    pub fn write_ubits(&mut self, value: u32, num_bits: u8) {
+        self.flags.write_ubits(value, num_bits);
+    }
+
+    /// 写入 Value 属性；时间轴属性按不含关键帧的静态值写入
+    pub fn write<T>(&mut self, config: AttributeConfig<T>, value: &T)
+    where
+        T: Encodable + PartialEq,
+    {
+        self.write_value(AttributeType::from(&config), config.default_value(), value);
+    }
+
+    fn write_value<T>(&mut self, r#type: AttributeType, default_value: &T, value: &T)
+    where
+        T: Encodable + PartialEq,
+    {
+        match r#type {
+            AttributeType::NotExisted => {}
+            AttributeType::FixedValue => value.encode(&mut self.content, ()),
+            AttributeType::BitFlag => {
+                let flag = value.as_bool().unwrap_or_default();
+                self.flags.write_bit(flag);
+            }
+            _ => {
+                let exist = value != default_value;
+                self.flags.write_bit(exist);
+                if !exist {
+                    return;
+                }
+                if !matches!(r#type, AttributeType::Value | AttributeType::Custom) {
+                    // animatable
+                    self.flags.write_bit(false);
+                }
+                value.encode(&mut self.content, ());
+            }
+        }
+    }
+
+    /// 写入 Property 属性，包含完整的关键帧信息
+    pub fn write_property<T>(&mut self, config: AttributeConfig<T>, property: &Property<T>)
+    where
+        T: Encodable + for<'b> Parsable<'b> + PartialEq,
+    {
+        let r#type = AttributeType::from(&config);
+        // 关键帧列表为空时按默认值写出
+        let value = property.value().unwrap_or(config.default_value());
+        let keyframes = match property {
+            Property::Animated(keyframes) if !keyframes.is_empty() => keyframes,
+            _ => return self.write_value(r#type, config.default_value(), value),
+        };
+        match r#type {
+            AttributeType::SimpleProperty
+            | AttributeType::DiscreteProperty
+            | AttributeType::MultiDimensionProperty
+            | AttributeType::SpatialProperty => {}
+            _ => return self.write_value(r#type, config.default_value(), value),
+        }
+
+        let has_spatial = r#type == AttributeType::SpatialProperty
+            && keyframes.iter().any(|keyframe| {
+                keyframe.spatial_in != Point::zero() || keyframe.spatial_out != Point::zero()
+            });
+        // exist + animatable
+        self.flags.write_bit(true);
+        self.flags.write_bit(true);
+        if r#type == AttributeType::SpatialProperty {
+            self.flags.write_bit(has_spatial);
+        }
+        write_keyframes(&mut self.content, r#type, keyframes, has_spatial);
+    }
+
+    /// 标志位区域按字节对齐，之后紧跟内容区域
+    pub fn finish(self, writer: &mut impl Writer) {
+        writer.write_bytes(self.flags.buffer());
+        writer.write_bytes(self.content.buffer());
+    }
+}
+
+/// 写入 Property 的关键帧列表
+fn write_keyframes<T>(
+    writer: &mut impl Writer,
+    r#type: AttributeType,
+    keyframes: &[Keyframe<T>],
+    has_spatial: bool,
+) where
+    T: Encodable + for<'b> Parsable<'b>,
+{
+    writer.write_encoded_u32((keyframes.len() as u32).into());
+
+    if r#type != AttributeType::DiscreteProperty {
+        for keyframe in keyframes {
+            writer.write_ubits(u8::from(keyframe.interpolation_type) as u32, 2);
+        }
+    }
+
+    for keyframe in keyframes {
+        writer.write_time(keyframe.start_time);
+    }
+    writer.write_time(keyframes[keyframes.len() - 1].end_time);
+
+    let mut values = keyframes
+        .iter()
+        .map(|keyframe| &keyframe.start_value)
+        .collect::<Vec<_>>();
+    values.push(&keyframes[keyframes.len() - 1].end_value);
+    // 按比特位存储的属性值与之后的缓动参数之间不进行字节对齐
+    if !T::encode_value_bits(&values, writer, r#type) {
+        for value in values {
+            value.encode(writer, ());
+        }
+    }
+
+    let dimensionality = match r#type {
+        AttributeType::MultiDimensionProperty => T::dimensionality(),
+        _ => 1,
+    };
+    write_eases(writer, keyframes, dimensionality, has_spatial);
+    writer.align();
+}
+
+/// 写入时间缓动参数，以及空间缓动参数（若存在）
+fn write_eases<T>(
+    writer: &mut impl Writer,
+    keyframes: &[Keyframe<T>],
+    dimensionality: usize,
+    has_spatial: bool,
+) {
+    let mut bezier_list = vec![];
+    for keyframe in keyframes {
+        if keyframe.interpolation_type != KeyframeInterpolationType::Bezier {
+            continue;
+        }
+        for i in 0..dimensionality {
+            let bezier_out = keyframe.bezier_out.get(i).copied().unwrap_or(Point::zero());
+            let bezier_in = keyframe.bezier_in.get(i).copied().unwrap_or(Point::zero());
+            bezier_list.extend([bezier_out.x, bezier_out.y, bezier_in.x, bezier_in.y]);
+        }
+    }
+    writer.write_float_list(&bezier_list, BEZIER_PRECISION);
+
+    if has_spatial {
+        let mut spatial_list = vec![];
+        for keyframe in keyframes {
+            for point in [keyframe.spatial_in, keyframe.spatial_out] {
+                let exist = point != Point::zero();
+                writer.write_bit(exist);
+                if exist {
+                    spatial_list.extend([point.x, point.y]);
+                }
+            }
+        }
+        writer.write_float_list(&spatial_list, SPATIAL_PRECISION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::{Keyframe, KeyframeInterpolationType, Point, Property},
+        parse::{AttributeBlock, AttributeConfig, AttributeType, Time},
+    };
+
+    use super::AttributeBlockWriter;
+    use crate::encode::StreamWriter;
+
+    #[test]
+    fn test_write_spatial_property_keyframes() {
+        let property = Property::Animated(vec![Keyframe {
+            start_value: Point::zero(),
+            end_value: Point::new(10., 5.),
+            start_time: Time::from(0),
+            end_time: Time::from(20),
+            interpolation_type: KeyframeInterpolationType::Bezier,
+            bezier_out: vec![Point::new(0.5, 0.)],
+            bezier_in: vec![Point::new(0.5, 1.)],
+            spatial_out: Point::zero(),
+            spatial_in: Point::new(1., -2.),
+        }]);
+
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(0xffu8), &0x80);
+        block.write_property(AttributeConfig::SpatialProperty(Point::zero()), &property);
+        let mut writer = StreamWriter::new();
+        block.finish(&mut writer);
+        let output = writer.into_bytes();
+
+        let mut block = AttributeBlock::new(&output);
+        let value = block.flag(AttributeType::Value);
+        let spatial = block.flag(AttributeType::SpatialProperty);
+        assert_eq!(block.read::<u8>("value", value).unwrap(), Some(0x80));
+        let Ok(Some(Property::Animated(keyframes))) =
+            block.read_property::<Point>("position", spatial)
+        else {
+            panic!("expect keyframes");
+        };
+        assert_eq!(keyframes.len(), 1);
+        assert_eq!(keyframes[0].end_time, 20);
+        assert_eq!(keyframes[0].end_value, Point::new(10., 5.));
+        assert_eq!(keyframes[0].bezier_in, vec![Point::new(0.5, 1.)]);
+        assert_eq!(keyframes[0].spatial_in, Point::new(1., -2.));
+        assert_eq!(block.byte_length().unwrap(), output.len());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::parse::{
+    AttributeType, EncodedInt32, EncodedInt64, EncodedUint32, EncodedUint64, ParseContext,
+};
+
+use super::writer::Writer;
+
+/// 可编码对象
+pub trait Encodable {
+    /// 编码
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext);
+
+    /// 作为 BitFlag 属性时，直接编码为布尔值
+    fn as_bool(&self) -> Option<bool> {
+        None
+    }
+
+    /// 关键帧的属性值列表按比特位存储时，直接写入比特流并返回 true；否则返回 false，按字节依次编码
+    fn encode_value_bits(_values: &[&Self], _writer: &mut impl Writer, _type: AttributeType) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+}
+
+impl Encodable for f32 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_f32(*self)
+    }
+}
+
+impl Encodable for u8 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_u8(*self)
+    }
+
+    fn encode_value_bits(values: &[&Self], writer: &mut impl Writer, _type: AttributeType) -> bool {
+        let list = values
+            .iter()
+            .map(|value| **value as u32)
+            .collect::<Vec<_>>();
+        writer.write_uint32_list(&list);
+        true
+    }
+}
+
+impl Encodable for bool {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_bool(*self)
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        Some(*self)
+    }
+
+    fn encode_value_bits(values: &[&Self], writer: &mut impl Writer, _type: AttributeType) -> bool {
+        for value in values {
+            writer.write_bit(**value);
+        }
+        true
+    }
+}
+
+impl Encodable for String {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_string(self)
+    }
+}
+
+impl Encodable for EncodedUint32 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_u32(*self)
+    }
+}
+
+impl Encodable for EncodedInt32 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_i32(*self)
+    }
+}
+
+impl Encodable for EncodedUint64 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_u64(*self)
+    }
+}
+
+impl Encodable for EncodedInt64 {
+    #[inline(always)]
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_i64(*self)
+    }
+}
@@ -0,0 +1,197 @@
+mod attr;
+mod encodable;
+mod verify;
+mod writer;
+
+pub use attr::AttributeBlockWriter;
+pub use encodable::Encodable;
+pub use verify::{check_round_trip, RoundTripMismatch};
+pub use writer::{StreamWriter, Writer};
+
+use crate::{FileHeader, PagRef, Tag};
+
+/// PAG File Writer
+#[derive(Debug)]
+pub struct PagWriter {
+    /// The header of file
+    header: FileHeader,
+    /// The internal bytes based writer
+    inner: StreamWriter,
+}
+
+impl PagWriter {
+    /// Length of the file header: "PAG" + version + length + compress method
+    const FILE_HEADER_LENGTH: u32 = 9;
+
+    /// Create new instance
+    /// - header: the header of PAG file, the length will be updated when finished
+    pub fn new(header: FileHeader) -> Self {
+        Self {
+            header,
+            inner: StreamWriter::new(),
+        }
+    }
+
+    /// Enable lossless mode, which keeps the original encodings from parsing
+    /// (e.g. long-form tag headers on short tags) so that round-trips stay byte-exact.
+    /// Tags parsed with `ParseOptions::lossless` and left unmodified are written as their
+    /// original bytes, modified tags are re-encoded.
+    pub fn with_lossless(self, lossless: bool) -> Self {
+        Self {
+            inner: StreamWriter::with_lossless(lossless),
+            ..self
+        }
+    }
+
+    /// Write next tag section
+    pub fn write_tag(&mut self, tag: &Tag<'_>) {
+        tag.encode(&mut self.inner, ());
+    }
+
+    /// Finish writing and return the content of PAG file
+    pub fn finish(self) -> Vec<u8> {
+        let body = self.inner.into_bytes();
+        let header = FileHeader {
+            length: Self::FILE_HEADER_LENGTH + body.len() as u32,
+            ..self.header
+        };
+
+        let mut writer = StreamWriter::new();
+        header.encode(&mut writer, ());
+        writer.write_bytes(&body);
+        writer.into_bytes()
+    }
+}
+
+impl PagWriter {
+    /// Write whole Pag object to bytes
+    pub fn write_all(pag: &PagRef<'_>) -> Vec<u8> {
+        Self::write_all_with(pag, false)
+    }
+
+    /// Write whole Pag object to bytes, keeping the original encodings
+    pub fn write_all_lossless(pag: &PagRef<'_>) -> Vec<u8> {
+        Self::write_all_with(pag, true)
+    }
+
+    fn write_all_with(pag: &PagRef<'_>, lossless: bool) -> Vec<u8> {
+        let mut writer = Self::new(pag.header.clone()).with_lossless(lossless);
+        for tag in &pag.tag_block.tags {
+            writer.write_tag(tag);
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32, EncodedUint64, PagParser, ParseOptions, Time},
+        BlendMode, ByteData, Color, CompositionAttributes, FileHeader, LayerAttributes, LayerBlock,
+        LayerType, Pag, Point, Property, Ratio, Rectangle, ShapeGroup, Tag, TagBlock, TagBody,
+        TagCode, TrackMatteType, VectorCompositionBlock,
+    };
+
+    use super::PagWriter;
+
+    #[test]
+    fn test_write_all_pag() {
+        let rectangle = Rectangle {
+            reversed: false,
+            size: Property::Value(Point::new(40., 30.)),
+            position: Property::Value(Point::zero()),
+            roundness: Property::Value(4.),
+        };
+        let group = ShapeGroup {
+            blend_mode: BlendMode::Multiply,
+            anchor_point: Property::Value(Point::zero()),
+            position: Property::Value(Point::new(10., 20.)),
+            scale: Property::Value(Point::one()),
+            skew: Property::Value(0.),
+            skew_axis: Property::Value(0.),
+            rotation: Property::Value(45.),
+            opacity: Property::Value(0x80),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(TagCode::Rectangle, TagBody::Rectangle(rectangle))],
+            },
+        };
+        let attributes = LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(0),
+            stretch: Ratio::one(),
+            start_time: Time::from(0),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::Value(0.),
+            duration: Time::from(30),
+            name: "shape".to_string(),
+        };
+        let layer = LayerBlock {
+            r#type: LayerType::Shape,
+            id: EncodedUint32::from(2),
+            tag_block: TagBlock {
+                tags: vec![
+                    Tag::new(
+                        TagCode::LayerAttributesV2,
+                        TagBody::LayerAttributesV2(attributes),
+                    ),
+                    Tag::new(TagCode::ShapeGroup, TagBody::ShapeGroup(group)),
+                ],
+            },
+        };
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![
+                    Tag::new(
+                        TagCode::CompositionAttributes,
+                        TagBody::CompositionAttributes(CompositionAttributes {
+                            width: EncodedInt32::from(100),
+                            height: EncodedInt32::from(100),
+                            duration: EncodedUint64::from(30),
+                            frame_rate: 30.,
+                            background_color: Color::WHITE,
+                        }),
+                    ),
+                    Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer)),
+                ],
+            },
+        };
+
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        ));
+        // 未解析的标签按原样写出，内容超过 62 字节时使用 long 型 TagHeader
+        pag.push_tag(Tag::new(
+            TagCode::CachePolicy,
+            TagBody::Raw(ByteData::from(&[0x5a; 64])),
+        ));
+        pag.push_tag(Tag::EMPTY);
+
+        let output = PagWriter::write_all(&pag);
+        assert_eq!(&output[..3], b"PAG");
+        assert_eq!(
+            u32::from_le_bytes(output[4..8].try_into().unwrap()) as usize,
+            output.len()
+        );
+
+        let parsed = PagParser::parse_all(&output).unwrap();
+        assert_eq!(parsed.tag_block.tags.len(), 3);
+        let TagBody::Raw(raw) = &parsed.tag_block.tags[1].body else {
+            panic!("expect Raw, got {:?}", parsed.tag_block.tags[1].body);
+        };
+        assert_eq!(*raw.data, [0x5a; 64]);
+        assert_eq!(PagWriter::write_all(&parsed), output);
+
+        // 所有标签的内容都应被完整读取
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(PagParser::parse_all_with(&output, options).is_ok());
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt;
+
+use crate::{
+    parse::{PagParser, Parsable, ParseContext, ParseError, ParseOptions, StreamParser},
+    PagRef, Tag, TagBody, TagCode, TagHeader,
+};
+
+use super::{encodable::Encodable, PagWriter, StreamWriter};
+
+/// 文件头长度："PAG" + version + length + compress method
+const FILE_HEADER_LENGTH: usize = 9;
+
+/// 往返编码（解析后再写出）与原始数据不一致的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripMismatch {
+    /// 第一个不一致字节的偏移量
+    pub offset: usize,
+    /// 不一致字节所在的标签路径，如 `VectorCompositionBlock[0]/LayerBlock[3]/Transform2D`
+    pub tag_path: String,
+    /// 原始数据长度
+    pub expected_length: usize,
+    /// 写出数据长度
+    pub actual_length: usize,
+}
+
+impl fmt::Display for RoundTripMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round-trip mismatch at offset {:#x} in {} (expected {} bytes, got {} bytes)",
+            self.offset, self.tag_path, self.expected_length, self.actual_length
+        )
+    }
+}
+
+/// 以无损模式解析 PAG 文件后重新写出，返回与原始数据的第一个不一致位置
+///
+/// 内容长度变化时，外层的文件头及 TagHeader 中的长度字段也会不一致，
+/// 因此按标签逐层比较，定位到最内层不一致的标签
+pub fn check_round_trip(input: &[u8]) -> Result<Option<RoundTripMismatch>, ParseError> {
+    let options = ParseOptions {
+        lossless: true,
+        ..Default::default()
+    };
+    let pag = PagParser::parse_ref_with(input, options)?;
+    Ok(find_mismatch(&pag, input))
+}
+
+/// 以无损模式写出 pag，返回与原始数据 input 的第一个不一致位置
+fn find_mismatch(pag: &PagRef<'_>, input: &[u8]) -> Option<RoundTripMismatch> {
+    let output = PagWriter::write_all_lossless(pag);
+    if input == output.as_slice() {
+        return None;
+    }
+
+    let mut path = vec![];
+    let offset = match locate_tags(
+        &pag.tag_block.tags,
+        input,
+        FILE_HEADER_LENGTH,
+        (),
+        &mut path,
+    ) {
+        Err(offset) => offset,
+        // 所有标签一致，但 End 标签之后仍有多余数据
+        Ok(end) if end < input.len() => {
+            path.push("(trailing data)".to_string());
+            end
+        }
+        // 所有标签一致，不一致位置在文件头中
+        Ok(_) => {
+            path = vec!["FileHeader".to_string()];
+            first_difference(input, &output)
+        }
+    };
+
+    Some(RoundTripMismatch {
+        offset,
+        tag_path: path.join("/"),
+        expected_length: input.len(),
+        actual_length: output.len(),
+    })
+}
+
+/// 比较从 input[start] 开始的原始标签与写出的标签，并将不一致标签的路径追加到 path
+///
+/// ctx 为标签列表所在的上下文，与写出时的上下文一致
+/// - 全部一致时返回标签列表在 input 中的结束位置
+/// - 否则返回第一个不一致字节在 input 中的偏移量
+fn locate_tags(
+    tags: &[Tag],
+    input: &[u8],
+    start: usize,
+    ctx: impl ParseContext,
+    path: &mut Vec<String>,
+) -> Result<usize, usize> {
+    let mut position = start;
+    for (i, tag) in tags.iter().enumerate() {
+        let code = tag.header.code;
+        let encoded = encode_lossless(tag, ctx.clone());
+        let original = original_tag(&input[position.min(input.len())..]);
+        if original == Some(encoded.as_slice()) {
+            position += encoded.len();
+            continue;
+        }
+
+        let tag_block = tag.body.tag_block();
+        if tag_block.is_some() {
+            let index = tags[..i]
+                .iter()
+                .filter(|tag| tag.header.code == code)
+                .count();
+            path.push(format!("{:?}[{}]", code, index));
+        } else {
+            path.push(format!("{:?}", code));
+        }
+        let Some(original) = original else {
+            return Err(position);
+        };
+
+        let original_header_length = tag_header_length(original);
+        let tag_ctx = ctx.with_tag_code(code);
+        let header_length = encoded.len() - encode_lossless(&tag.body, tag_ctx.clone()).len();
+        let (original_body, encoded_body) = (
+            &original[original_header_length..],
+            &encoded[header_length..],
+        );
+        let body_start = position + original_header_length;
+        match tag_block {
+            None if original_body != encoded_body => {
+                return Err(body_start + first_difference(original_body, encoded_body));
+            }
+            None => {}
+            Some(tag_block) => {
+                // 子标签的编码依赖图层类型及视频是否包含透明通道
+                let block_ctx = match &tag.body {
+                    TagBody::LayerBlock(block) => tag_ctx.with_layer_type(block.r#type),
+                    TagBody::VideoCompositionBlock(block) => tag_ctx.with_alpha(block.has_alpha),
+                    _ => tag_ctx,
+                };
+                // 子标签列表总是位于标签内容的末尾，之前为标签自身的字段
+                let fields_length =
+                    encoded_body.len() - encode_lossless(tag_block, block_ctx.clone()).len();
+                let original_fields = &original_body[..fields_length.min(original_body.len())];
+                if original_fields != &encoded_body[..fields_length] {
+                    return Err(body_start
+                        + first_difference(original_fields, &encoded_body[..fields_length]));
+                }
+
+                let children_start = body_start + fields_length;
+                let children_end =
+                    locate_tags(&tag_block.tags, input, children_start, block_ctx, path)?;
+                if input.get(children_end..children_end + 2) != Some(&[0, 0]) {
+                    // 子标签列表结尾的 End 标签
+                    path.push(format!("{:?}", TagCode::End));
+                    return Err(children_end);
+                }
+            }
+        }
+        // 内容一致，不一致位置在 TagHeader 中
+        return Err(position + first_difference(original, &encoded));
+    }
+    Ok(position)
+}
+
+/// 以 input 开头的完整标签数据（包含 TagHeader），数据不完整时返回 None
+fn original_tag(input: &[u8]) -> Option<&[u8]> {
+    let header = TagHeader::parse(&mut StreamParser::new(input), ()).ok()?;
+    let length = tag_header_length(input) + header.length as usize;
+    input.get(..length)
+}
+
+/// 以 input 开头的 TagHeader 所占的字节数
+fn tag_header_length(input: &[u8]) -> usize {
+    const MASK: u8 = 0b0011_1111;
+    match input.first() {
+        Some(byte) if byte & MASK == MASK => 6,
+        _ => 2,
+    }
+}
+
+/// 第一个不一致字节的位置，其中一方为另一方的前缀时返回较短的长度
+fn first_difference(a: &[u8], b: &[u8]) -> usize {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .unwrap_or(a.len().min(b.len()))
+}
+
+/// 以无损模式写出
+fn encode_lossless(value: &impl Encodable, ctx: impl ParseContext) -> Vec<u8> {
+    let mut writer = StreamWriter::with_lossless(true);
+    value.encode(&mut writer, ctx);
+    writer.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32, EncodedUint64, Time},
+        parse::{PagParser, ParseOptions},
+        BlendMode, ByteData, Color, CompositionAttributes, FileHeader, LayerAttributes, LayerBlock,
+        LayerType, Pag, Property, Ratio, Tag, TagBlock, TagBody, TagCode, TrackMatteType,
+        VectorCompositionBlock, VideoCompositionBlock, VideoSequence,
+    };
+
+    use super::{check_round_trip, find_mismatch, first_difference, PagWriter};
+
+    fn new_pag() -> Pag {
+        let attributes = CompositionAttributes {
+            width: EncodedInt32::from(100),
+            height: EncodedInt32::from(100),
+            duration: EncodedUint64::from(30),
+            frame_rate: 30.,
+            background_color: Color::WHITE,
+        };
+        // 内容较短的标签使用 long 型 TagHeader 存储
+        let mut attributes = Tag::new(
+            TagCode::CompositionAttributes,
+            TagBody::CompositionAttributes(attributes),
+        );
+        attributes.header.is_long = true;
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![attributes],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        pag
+    }
+
+    #[test]
+    fn test_round_trip_long_header() {
+        let input = PagWriter::write_all_lossless(&new_pag());
+        assert_eq!(check_round_trip(&input).unwrap(), None);
+
+        // 非无损模式下，短标签会改用 short 型 TagHeader 写出
+        let output = PagWriter::write_all(&new_pag());
+        assert_eq!(output.len() + 4, input.len());
+    }
+
+    #[test]
+    fn test_round_trip_mismatch_path() {
+        let mut input = PagWriter::write_all_lossless(&new_pag());
+        // 9 字节文件头 + VectorCompositionBlock 的 short 型 TagHeader 与 id，
+        // 之后为 CompositionAttributes 的 long 型 TagHeader 与 width/height
+        let (block_header, attributes_header, duration) = (9, 12, 22);
+        assert_eq!(input[duration], 30);
+        // 将 duration 改为非最短的变长编码，未修改的标签按原始数据写出
+        input[duration] |= 0x80;
+        input.insert(duration + 1, 0);
+        input[block_header] += 1;
+        input[attributes_header + 2] += 1;
+        input[4] += 1;
+        assert_eq!(check_round_trip(&input).unwrap(), None);
+
+        // 修改后的标签重新编码，duration 改用最短编码
+        let options = ParseOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let mut pag = PagParser::parse_ref_with(&input, options).unwrap();
+        let TagBody::VectorCompositionBlock(composition) = &mut pag.tag_block.tags[0].body else {
+            panic!("expect VectorCompositionBlock");
+        };
+        let TagBody::CompositionAttributes(attributes) = &mut composition.tag_block.tags[0].body
+        else {
+            panic!("expect CompositionAttributes");
+        };
+        attributes.duration = EncodedUint64::from(31);
+
+        let mismatch = find_mismatch(&pag, &input).unwrap();
+        assert_eq!(mismatch.offset, duration);
+        assert_eq!(
+            mismatch.tag_path,
+            "VectorCompositionBlock[0]/CompositionAttributes"
+        );
+        assert_eq!(mismatch.expected_length, mismatch.actual_length + 1);
+    }
+
+    /// 包含依赖上下文编码的标签：带透明通道的视频序列，以及图层中的 LayerAttributesV2
+    fn new_context_pag() -> Pag {
+        let sequence = VideoSequence {
+            width: EncodedInt32::from(100),
+            height: EncodedInt32::from(100),
+            frame_rate: 30.,
+            alpha_start_x: Some(EncodedInt32::from(0)),
+            alpha_start_y: Some(EncodedInt32::from(50)),
+            sps_data: ByteData::from_vec(vec![]),
+            pps_data: ByteData::from_vec(vec![]),
+            frame_count: EncodedUint32::from(0),
+            is_key_frame_flag: vec![],
+            video_frames: vec![],
+            static_time_ranges: vec![],
+        };
+        let video = VideoCompositionBlock {
+            id: EncodedUint32::from(1),
+            has_alpha: true,
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::VideoSequence,
+                    TagBody::VideoSequence(sequence),
+                )],
+            },
+        };
+        let attributes = LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(0),
+            stretch: Ratio::one(),
+            start_time: Time::from(0),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::default(),
+            duration: Time::from(30),
+            name: "a".to_string(),
+        };
+        let layer = LayerBlock {
+            r#type: LayerType::Null,
+            id: EncodedUint32::from(2),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::LayerAttributesV2,
+                    TagBody::LayerAttributesV2(attributes),
+                )],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VideoCompositionBlock,
+            TagBody::VideoCompositionBlock(video),
+        ));
+        pag.push_tag(Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer)));
+        pag.push_tag(Tag::EMPTY);
+        pag
+    }
+
+    #[test]
+    fn test_round_trip_mismatch_with_context() {
+        let input = PagWriter::write_all_lossless(&new_context_pag());
+        assert_eq!(check_round_trip(&input).unwrap(), None);
+        let options = ParseOptions {
+            lossless: true,
+            ..Default::default()
+        };
+
+        // alpha_start_y 只在 hasAlpha 时写出
+        let mut pag = PagParser::parse_ref_with(&input, options).unwrap();
+        let TagBody::VideoCompositionBlock(video) = &mut pag.tag_block.tags[0].body else {
+            panic!("expect VideoCompositionBlock");
+        };
+        let TagBody::VideoSequence(sequence) = &mut video.tag_block.tags[0].body else {
+            panic!("expect VideoSequence");
+        };
+        sequence.alpha_start_y = Some(EncodedInt32::from(51));
+        let output = PagWriter::write_all_lossless(&pag);
+        let mismatch = find_mismatch(&pag, &input).unwrap();
+        assert_eq!(mismatch.offset, first_difference(&input, &output));
+        assert_eq!(mismatch.tag_path, "VideoCompositionBlock[0]/VideoSequence");
+
+        // name 只在 LayerAttributesV2/V3 中写出
+        let mut pag = PagParser::parse_ref_with(&input, options).unwrap();
+        let TagBody::LayerBlock(layer) = &mut pag.tag_block.tags[1].body else {
+            panic!("expect LayerBlock");
+        };
+        let TagBody::LayerAttributesV2(attributes) = &mut layer.tag_block.tags[0].body else {
+            panic!("expect LayerAttributesV2");
+        };
+        attributes.name = "b".to_string();
+        let output = PagWriter::write_all_lossless(&pag);
+        let mismatch = find_mismatch(&pag, &input).unwrap();
+        assert_eq!(mismatch.offset, first_difference(&input, &output));
+        assert_eq!(mismatch.tag_path, "LayerBlock[0]/LayerAttributesV2");
+    }
+}
@@ -0,0 +1,337 @@
+use super::Encodable;
+use crate::parse::{EncodedInt32, EncodedInt64, EncodedUint32, EncodedUint64, Time};
+
+pub trait Writer {
+    /// 已写入的字节数，未写满的比特位按一个字节计算
+    fn len(&self) -> usize;
+
+    /// 是否为无损模式，无损模式下尽量保持解析时的原有编码格式
+    fn is_lossless(&self) -> bool;
+
+    /// 创建一个继承当前写入选项的新写入器，用于先写入内容再计算长度的场景
+    fn new_writer(&self) -> StreamWriter;
+
+    /// 是否只用于计算标签摘要，此时子标签只写入其摘要
+    fn is_digest_only(&self) -> bool;
+
+    /// 写入完整的标签数据，摘要数据中以标签的摘要代替
+    fn write_tag_bytes(&mut self, bytes: &[u8], digest: Option<u64>);
+
+    /// 用于计算标签摘要的数据，只在无损模式下记录
+    fn digest_input(&self) -> Option<&[u8]>;
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 按字节对齐，后续的比特位从新的字节开始写入
+    fn align(&mut self);
+
+    /// 写入一个比特位，多个比特位之间连续存储
+    fn write_bit(&mut self, value: bool);
+
+    /// 按字节对齐后写入字节流
+    fn write_bytes(&mut self, bytes: &[u8]);
+
+    #[inline(always)]
+    fn write_u8(&mut self, value: u8) {
+        self.write_bytes(&[value]);
+    }
+    #[inline(always)]
+    fn write_i8(&mut self, value: i8) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_u16(&mut self, value: u16) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_i32(&mut self, value: i32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_i64(&mut self, value: i64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_f32(&mut self, value: f32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+    #[inline(always)]
+    fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    fn write_encoded_u32(&mut self, value: EncodedUint32) {
+        self.write_encoded_u64(EncodedUint64::from(value.to_u64()));
+    }
+
+    fn write_encoded_i32(&mut self, value: EncodedInt32) {
+        let value = value.to_i32();
+        let data = (value.unsigned_abs() << 1) | (value < 0) as u32;
+        self.write_encoded_u32(EncodedUint32::from(data));
+    }
+
+    fn write_encoded_u64(&mut self, value: EncodedUint64) {
+        let mut value = value.to_u64();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.write_u8(byte);
+                break;
+            }
+            self.write_u8(byte | 0x80);
+        }
+    }
+
+    fn write_encoded_i64(&mut self, value: EncodedInt64) {
+        let value = value.to_i64();
+        let data = (value.unsigned_abs() << 1) | (value < 0) as u64;
+        self.write_encoded_u64(EncodedUint64::from(data));
+    }
+
+    #[inline(always)]
+    fn write_enum<T>(&mut self, value: T)
+    where
+        T: Into<u8>,
+    {
+        self.write_u8(value.into());
+    }
+
+    /// 写入以 '\0' 结尾的字符串
+    fn write_string(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+        self.write_u8(0);
+    }
+
+    #[inline(always)]
+    fn write_id(&mut self, value: EncodedUint32) {
+        self.write_encoded_u32(value)
+    }
+    #[inline(always)]
+    fn write_time(&mut self, value: Time) {
+        self.write_encoded_u64(value)
+    }
+
+    /// 写入 UB[num_bits]
+    fn write_ubits(&mut self, value: u32, num_bits: u8) {
+        for i in 0..num_bits {
+            self.write_bit((value >> i) & 1 != 0);
+        }
+    }
+
+    /// 写入 SB[num_bits]，最高位为符号位
+    fn write_sbits(&mut self, value: i32, num_bits: u8) {
+        self.write_ubits(value as u32, num_bits);
+    }
+
+    /// 写入连续数据编码的头部区域：UB[5] = numBits - 1
+    fn write_num_bits(&mut self, num_bits: u8) {
+        self.write_ubits(num_bits as u32 - 1, LENGTH_FOR_STORE_NUM_BITS);
+    }
+
+    /// 写入连续无符号整数编码的列表，numBits 取能容纳所有值的最小位数
+    fn write_uint32_list(&mut self, values: &[u32]) {
+        let num_bits = values
+            .iter()
+            .map(|value| unsigned_bit_length(*value))
+            .max()
+            .unwrap_or(1);
+        self.write_num_bits(num_bits);
+        for value in values {
+            self.write_ubits(*value, num_bits);
+        }
+    }
+
+    /// 写入连续浮点数编码的列表，每个值存储为 SB[numBits] = round(value / precision)
+    fn write_float_list(&mut self, values: &[f32], precision: f32) {
+        let values = values
+            .iter()
+            .map(|value| (value / precision).round() as i32)
+            .collect::<Vec<_>>();
+        let num_bits = values
+            .iter()
+            .map(|value| unsigned_bit_length(value.unsigned_abs()) + 1)
+            .max()
+            .unwrap_or(1)
+            .min(32);
+        self.write_num_bits(num_bits);
+        for value in values {
+            self.write_sbits(value, num_bits);
+        }
+    }
+
+    #[inline(always)]
+    fn write<T>(&mut self, value: &T)
+    where
+        T: Encodable,
+        Self: Sized,
+    {
+        value.encode(self, ())
+    }
+}
+
+/// 连续数据编码中，头部区域存储 numBits 所占的比特位数
+const LENGTH_FOR_STORE_NUM_BITS: u8 = 5;
+
+/// 存储无符号整数所需的最小比特位数，至少为 1
+fn unsigned_bit_length(value: u32) -> u8 {
+    (32 - value.leading_zeros()).max(1) as u8
+}
+
+#[derive(Debug, Default)]
+pub struct StreamWriter {
+    output: Vec<u8>,
+    /// 已写入的比特位数，字节写入时对齐到 output 的末尾
+    bit_index: usize,
+    lossless: bool,
+    /// 无损模式下与 output 同步写入的数据，其中的子标签以摘要代替，用于计算标签摘要
+    digest_input: Option<Box<StreamWriter>>,
+    digest_only: bool,
+}
+
+impl StreamWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_lossless(lossless: bool) -> Self {
+        Self {
+            lossless,
+            digest_input: lossless.then(Box::default),
+            ..Self::default()
+        }
+    }
+
+    /// 只用于计算标签摘要的写入器，子标签直接写入解析时记录的摘要
+    pub(crate) fn for_digest() -> Self {
+        Self {
+            digest_only: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn buffer(&self) -> &[u8] {
+        &self.output
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.output
+    }
+}
+
+impl Writer for StreamWriter {
+    fn len(&self) -> usize {
+        self.output.len()
+    }
+
+    fn is_lossless(&self) -> bool {
+        self.lossless
+    }
+
+    fn new_writer(&self) -> StreamWriter {
+        Self {
+            digest_only: self.digest_only,
+            ..Self::with_lossless(self.lossless)
+        }
+    }
+
+    fn is_digest_only(&self) -> bool {
+        self.digest_only
+    }
+
+    fn write_tag_bytes(&mut self, bytes: &[u8], digest: Option<u64>) {
+        self.output.extend_from_slice(bytes);
+        self.bit_index = self.output.len() * 8;
+        if let (Some(input), Some(digest)) = (&mut self.digest_input, digest) {
+            input.write_bytes(&digest.to_le_bytes());
+        }
+    }
+
+    fn digest_input(&self) -> Option<&[u8]> {
+        self.digest_input.as_ref().map(|input| input.buffer())
+    }
+
+    fn align(&mut self) {
+        self.bit_index = self.output.len() * 8;
+        if let Some(input) = &mut self.digest_input {
+            input.align();
+        }
+    }
+
+    fn write_bit(&mut self, value: bool) {
+        let (i, j) = (self.bit_index / 8, self.bit_index % 8);
+        if i >= self.output.len() {
+            self.output.push(0);
+        }
+        if value {
+            self.output[i] |= 1 << j;
+        }
+        self.bit_index += 1;
+        if let Some(input) = &mut self.digest_input {
+            input.write_bit(value);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.output.extend_from_slice(bytes);
+        self.bit_index = self.output.len() * 8;
+        if let Some(input) = &mut self.digest_input {
+            input.write_bytes(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{EncodedInt32, EncodedUint32, Parser, StreamParser, SPATIAL_PRECISION};
+
+    use super::{StreamWriter, Writer};
+
+    #[test]
+    fn test_write_encoded_numbers() {
+        let mut writer = StreamWriter::new();
+        writer.write_encoded_u32(EncodedUint32::from(300));
+        writer.write_encoded_i32(EncodedInt32::from(-5));
+        writer.write_string("PAG");
+        let output = writer.into_bytes();
+        assert_eq!(output, [0xac, 0x02, 0x0b, b'P', b'A', b'G', 0]);
+
+        let mut parser = StreamParser::new(&output);
+        assert_eq!(parser.next_encoded_u32().unwrap(), 300);
+        assert_eq!(parser.next_encoded_i32().unwrap(), -5);
+        assert_eq!(parser.next_string().unwrap(), "PAG");
+        assert!(parser.is_empty());
+    }
+
+    #[test]
+    fn test_write_bits() {
+        let mut writer = StreamWriter::new();
+        writer.write_ubits(5, 3);
+        writer.write_float_list(&[20., -5.], SPATIAL_PRECISION);
+        writer.write_u8(0xff);
+        let output = writer.into_bytes();
+
+        let mut parser = StreamParser::new(&output);
+        let (verb, values) = parser
+            .next_bits(|bits| {
+                let verb = bits.next_ubits(3);
+                (verb, bits.next_float_list(2, SPATIAL_PRECISION))
+            })
+            .unwrap();
+        assert_eq!(verb, 5);
+        assert_eq!(values, [20., -5.]);
+        assert_eq!(parser.next_u8().unwrap(), 0xff);
+        assert!(parser.is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+use std::io;
+
+use crate::VideoSequence;
+
+/// Annex-B 格式中 NAL 单元前的起始码
+pub const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// 数据是否以 3 字节或 4 字节的起始码开头
+fn has_start_code(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 1]) || data.starts_with(&START_CODE)
+}
+
+/// 按起始码拆分 NAL 单元，去掉 4 字节起始码多出的前导 0
+fn split_start_codes(data: &[u8]) -> Vec<&[u8]> {
+    let mut units = vec![];
+    let mut start = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i..i + 3] == [0, 0, 1] {
+            if let Some(start) = start {
+                units.push(trim_trailing_zeros(&data[start..i]));
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(start) = start {
+        units.push(&data[start..]);
+    }
+    units.retain(|unit| !unit.is_empty());
+    units
+}
+
+fn trim_trailing_zeros(data: &[u8]) -> &[u8] {
+    let end = data
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+/// 按 4 字节大端长度前缀拆分 NAL 单元，长度与数据不一致时返回 None
+fn split_length_prefixed(mut data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut units = vec![];
+    while !data.is_empty() {
+        let (length, rest) = data.split_first_chunk::<4>()?;
+        let length = u32::from_be_bytes(*length) as usize;
+        if length == 0 || length > rest.len() {
+            return None;
+        }
+        let (unit, rest) = rest.split_at(length);
+        units.push(unit);
+        data = rest;
+    }
+    Some(units)
+}
+
+/// H.264 数据中 NAL 单元的封装格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NalFraming {
+    /// 每个 NAL 单元前有 3 字节或 4 字节的起始码
+    AnnexB,
+    /// 每个 NAL 单元前有 4 字节大端长度（AVCC）
+    LengthPrefixed,
+    /// 无法统一判断，每段数据单独判断
+    Mixed,
+}
+
+impl NalFraming {
+    /// 根据同一视频序列的所有数据判断封装格式
+    ///
+    /// 长度前缀格式中长度为 256 ~ 511 或 1 的 NAL 单元与起始码无法区分，
+    /// 因此所有数据都能被长度前缀恰好拆分时优先视为长度前缀格式
+    pub fn detect<'d>(buffers: impl IntoIterator<Item = &'d [u8]>) -> Self {
+        let buffers = buffers
+            .into_iter()
+            .filter(|data| !data.is_empty())
+            .collect::<Vec<_>>();
+        if buffers.is_empty() {
+            NalFraming::Mixed
+        } else if buffers
+            .iter()
+            .all(|data| split_length_prefixed(data).is_some())
+        {
+            NalFraming::LengthPrefixed
+        } else if buffers.iter().all(|data| has_start_code(data)) {
+            NalFraming::AnnexB
+        } else {
+            NalFraming::Mixed
+        }
+    }
+
+    /// 拆分 NAL 单元，不含起始码或长度前缀，不符合封装格式的数据视为单个 NAL 单元
+    pub fn split(self, data: &[u8]) -> Vec<&[u8]> {
+        if data.is_empty() {
+            return vec![];
+        }
+        match self {
+            NalFraming::AnnexB if has_start_code(data) => split_start_codes(data),
+            NalFraming::LengthPrefixed => split_length_prefixed(data).unwrap_or_else(|| vec![data]),
+            NalFraming::AnnexB => vec![data],
+            NalFraming::Mixed => split_nal_units(data),
+        }
+    }
+}
+
+/// 拆分单段 H.264 数据中的 NAL 单元，不含起始码或长度前缀
+/// - 能被 4 字节长度前缀（AVCC）恰好拆分时按长度前缀格式拆分
+/// - 否则以起始码开头时按 Annex-B 格式拆分
+/// - 都不符合时视为单个 NAL 单元
+///
+/// 同一视频序列的数据应使用 [`NalFraming::detect`] 统一判断封装格式
+pub fn split_nal_units(data: &[u8]) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return vec![];
+    }
+    if let Some(units) = split_length_prefixed(data) {
+        return units;
+    }
+    if has_start_code(data) {
+        return split_start_codes(data);
+    }
+    vec![data]
+}
+
+/// 将 H.264 视频帧逐帧写出为 Annex-B 格式的码流，每个关键帧前重复写入 SPS 和 PPS，
+/// 使码流可以从任意关键帧开始解码
+#[derive(Debug)]
+pub struct AnnexBWriter<'s, W> {
+    writer: W,
+    sps: &'s [u8],
+    pps: &'s [u8],
+    framing: NalFraming,
+}
+
+impl<'s, W: io::Write> AnnexBWriter<'s, W> {
+    /// 每段数据单独判断封装格式
+    pub fn new(writer: W, sps: &'s [u8], pps: &'s [u8]) -> Self {
+        Self {
+            writer,
+            sps,
+            pps,
+            framing: NalFraming::Mixed,
+        }
+    }
+
+    /// 指定 SPS、PPS 及视频帧的封装格式
+    pub fn with_framing(mut self, framing: NalFraming) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    fn write_nal_units(&mut self, data: &[u8]) -> io::Result<()> {
+        for unit in self.framing.split(data) {
+            self.writer.write_all(&START_CODE)?;
+            self.writer.write_all(unit)?;
+        }
+        Ok(())
+    }
+
+    /// 写出一帧，关键帧前写入 SPS 和 PPS
+    pub fn write_frame(&mut self, data: &[u8], is_key_frame: bool) -> io::Result<()> {
+        if is_key_frame {
+            self.write_nal_units(self.sps)?;
+            self.write_nal_units(self.pps)?;
+        }
+        self.write_nal_units(data)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl VideoSequence<'_> {
+    /// 根据 SPS、PPS 及所有视频帧判断 NAL 单元的封装格式
+    pub fn nal_framing(&self) -> NalFraming {
+        let frames = self
+            .video_frames
+            .iter()
+            .map(|frame| &*frame.file_bytes.data);
+        NalFraming::detect(
+            [&*self.sps_data.data, &*self.pps_data.data]
+                .into_iter()
+                .chain(frames),
+        )
+    }
+
+    /// 按解码顺序写出 Annex-B 格式的 H.264 码流
+    pub fn write_annexb(&self, writer: impl io::Write) -> io::Result<()> {
+        let mut writer = AnnexBWriter::new(writer, &self.sps_data.data, &self.pps_data.data)
+            .with_framing(self.nal_framing());
+        for (i, frame) in self.video_frames.iter().enumerate() {
+            // 第一帧总是作为关键帧，保证码流以 SPS 和 PPS 开头
+            writer.write_frame(&frame.file_bytes.data, frame.is_key_frame || i == 0)?;
+        }
+        Ok(())
+    }
+
+    /// Annex-B 格式的 H.264 码流，可以直接交给 ffmpeg 或解码器
+    pub fn to_annexb(&self) -> Vec<u8> {
+        let mut stream = vec![];
+        self.write_annexb(&mut stream)
+            .expect("write to Vec should not fail");
+        stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32, Time},
+        ByteData, VideoFrame, VideoSequence,
+    };
+
+    use super::{split_nal_units, AnnexBWriter, NalFraming};
+
+    fn new_frame(frame: u64, is_key_frame: bool, data: &[u8]) -> VideoFrame<'_> {
+        VideoFrame {
+            is_key_frame,
+            frame: Time::from(frame),
+            file_bytes: ByteData::from(data),
+        }
+    }
+
+    #[test]
+    fn test_to_annexb() {
+        let sps = [0, 0, 0, 1, 0x67, 0x42];
+        let pps = [0, 0, 0, 1, 0x68, 0xce];
+        // 长度前缀格式，包含 2 个 NAL 单元
+        let key_frame = [0, 0, 0, 2, 0x65, 0x88, 0, 0, 0, 1, 0x06];
+        // 3 字节起始码，转换为 4 字节起始码
+        let frame = [0, 0, 1, 0x41, 0x9a];
+        let sequence = VideoSequence {
+            width: EncodedInt32::from(16),
+            height: EncodedInt32::from(16),
+            frame_rate: 30.,
+            alpha_start_x: None,
+            alpha_start_y: None,
+            sps_data: ByteData::from(&sps),
+            pps_data: ByteData::from(&pps),
+            frame_count: EncodedUint32::from(3),
+            is_key_frame_flag: vec![true, false, true],
+            video_frames: vec![
+                new_frame(0, true, &key_frame),
+                new_frame(1, false, &frame),
+                new_frame(2, true, &frame),
+            ],
+            static_time_ranges: vec![],
+        };
+
+        let headers = [sps, pps].concat();
+        let expected = [
+            &headers[..],
+            &[0, 0, 0, 1, 0x65, 0x88, 0, 0, 0, 1, 0x06],
+            &[0, 0, 0, 1, 0x41, 0x9a],
+            &headers,
+            &[0, 0, 0, 1, 0x41, 0x9a],
+        ]
+        .concat();
+        assert_eq!(sequence.to_annexb(), expected);
+
+        // 不带起始码且不是长度前缀格式的数据视为单个 NAL 单元
+        let mut writer = AnnexBWriter::new(vec![], &[0x67], &[0x68]);
+        writer.write_frame(&[0x65, 0x88, 0x84], true).unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            [0, 0, 0, 1, 0x67, 0, 0, 0, 1, 0x68, 0, 0, 0, 1, 0x65, 0x88, 0x84]
+        );
+    }
+
+    #[test]
+    fn test_split_nal_units() {
+        let data = [0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 0, 1, 0x68, 0, 0, 1, 0x65];
+        assert_eq!(
+            split_nal_units(&data),
+            [&[0x67, 0x42][..], &[0x68], &[0x65]]
+        );
+        assert_eq!(
+            split_nal_units(&[0, 0, 0, 2, 0x65, 0x88]),
+            [&[0x65, 0x88][..]]
+        );
+        assert_eq!(split_nal_units(&[0x65, 0x88]), [&[0x65, 0x88][..]]);
+    }
+
+    #[test]
+    fn test_nal_framing() {
+        // 长度为 300 的 NAL 单元，长度前缀以 00 00 01 开头
+        let mut frame = vec![0, 0, 1, 0x2c, 0x65];
+        frame.resize(4 + 300, 0x88);
+        let sps = [0, 0, 0, 3, 0x67, 0x42, 0xc0];
+        let pps = [0, 0, 0, 2, 0x68, 0xce];
+        let framing = NalFraming::detect([&sps[..], &pps, &frame]);
+        assert_eq!(framing, NalFraming::LengthPrefixed);
+        assert_eq!(framing.split(&frame), [&frame[4..]]);
+        assert_eq!(split_nal_units(&frame), [&frame[4..]]);
+
+        let sequence = VideoSequence {
+            width: EncodedInt32::from(16),
+            height: EncodedInt32::from(16),
+            frame_rate: 30.,
+            alpha_start_x: None,
+            alpha_start_y: None,
+            sps_data: ByteData::from(&sps),
+            pps_data: ByteData::from(&pps),
+            frame_count: EncodedUint32::from(1),
+            is_key_frame_flag: vec![true],
+            video_frames: vec![new_frame(0, true, &frame)],
+            static_time_ranges: vec![],
+        };
+        let expected = [
+            &[0, 0, 0, 1, 0x67, 0x42, 0xc0][..],
+            &[0, 0, 0, 1, 0x68, 0xce],
+            &[0, 0, 0, 1],
+            &frame[4..],
+        ]
+        .concat();
+        assert_eq!(sequence.to_annexb(), expected);
+
+        // 同一序列的数据都以起始码开头时按 Annex-B 格式拆分
+        let units = [0, 0, 0, 1, 0x65, 0x88, 0, 0, 1, 0x41];
+        let framing = NalFraming::detect([&units[..], &[0, 0, 1, 0x67]]);
+        assert_eq!(framing, NalFraming::AnnexB);
+        assert_eq!(framing.split(&units), [&[0x65, 0x88][..], &[0x41]]);
+    }
+}
@@ -0,0 +1,4 @@
+mod annexb;
+mod mp4;
+
+pub use annexb::{split_nal_units, AnnexBWriter, NalFraming, START_CODE};
@@ -0,0 +1,511 @@
+use crate::VideoSequence;
+
+/// 未记录帧率时使用的帧率
+const DEFAULT_FRAME_RATE: f32 = 30.;
+/// 影片时间刻度，单位为毫秒
+const MOVIE_TIMESCALE: u32 = 1000;
+/// 每帧在媒体时间刻度中的时长，媒体时间刻度为帧率的 1000 倍，以支持 29.97 等非整数帧率
+const FRAME_DELTA: u32 = 1000;
+/// Box 头长度，包括 32 位长度及 4 字节类型
+const BOX_HEADER_LENGTH: usize = 8;
+/// avc1 中子 Box 之前固定字段的长度
+const AVC1_FIXED_LENGTH: usize = 78;
+
+/// 写入一个 Box，content 写入 Box 内容后回填长度
+fn write_box(out: &mut Vec<u8>, kind: &[u8; 4], content: impl FnOnce(&mut Vec<u8>)) {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(kind);
+    content(out);
+    let size = (out.len() - start) as u32;
+    out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+/// 写入一个带版本号和标志位的 FullBox
+fn write_full_box(
+    out: &mut Vec<u8>,
+    kind: &[u8; 4],
+    version: u8,
+    flags: u32,
+    content: impl FnOnce(&mut Vec<u8>),
+) {
+    write_box(out, kind, |out| {
+        out.extend_from_slice(&(u32::from(version) << 24 | flags).to_be_bytes());
+        content(out);
+    });
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// 单位矩阵，用于 mvhd 及 tkhd
+fn put_matrix(out: &mut Vec<u8>) {
+    for value in [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000] {
+        put_u32(out, value);
+    }
+}
+
+/// 在 Box 列表中按路径查找 Box 的内容
+fn find_box<'a>(mut data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    let (kind, rest) = path.split_first()?;
+    while data.len() >= BOX_HEADER_LENGTH {
+        let size = u32::from_be_bytes(data[..4].try_into().ok()?) as usize;
+        if size < BOX_HEADER_LENGTH || size > data.len() {
+            return None;
+        }
+        if &data[4..8] == *kind {
+            let content = &data[BOX_HEADER_LENGTH..size];
+            return match rest.is_empty() {
+                true => Some(content),
+                false => find_box(content, rest),
+            };
+        }
+        data = &data[size..];
+    }
+    None
+}
+
+/// 读取 FullBox 内容中版本号和标志位之后的第 index 个 u32
+fn read_u32(content: &[u8], index: usize) -> Option<u32> {
+    let offset = 4 + index * 4;
+    let bytes = content.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// 视频序列封装为 MP4 所需的采样信息
+struct Samples {
+    /// 每帧转换为长度前缀格式的数据
+    data: Vec<Vec<u8>>,
+    /// 每帧在媒体时间刻度中的时长
+    durations: Vec<u32>,
+    /// 关键帧序号，从 1 开始
+    sync_samples: Vec<u32>,
+    timescale: u32,
+}
+
+impl Samples {
+    fn new(sequence: &VideoSequence) -> Self {
+        let frame_rate = match sequence.frame_rate > 0. {
+            true => sequence.frame_rate,
+            false => DEFAULT_FRAME_RATE,
+        };
+        let frames = &sequence.video_frames;
+
+        let framing = sequence.nal_framing();
+        let data = frames
+            .iter()
+            .map(|frame| {
+                let mut sample = vec![];
+                for unit in framing.split(&frame.file_bytes.data) {
+                    put_u32(&mut sample, unit.len() as u32);
+                    sample.extend_from_slice(unit);
+                }
+                sample
+            })
+            .collect();
+
+        // 静态区间内的帧不会存储，按帧号计算每帧的显示时长，超出 u32 范围时截断
+        let increasing = frames
+            .windows(2)
+            .all(|pair| pair[0].frame.to_u64() < pair[1].frame.to_u64());
+        let durations = (0..frames.len())
+            .map(|i| match frames.get(i + 1) {
+                Some(next) if increasing => {
+                    let frames = next.frame.to_u64() - frames[i].frame.to_u64();
+                    u32::try_from(frames.saturating_mul(FRAME_DELTA as u64)).unwrap_or(u32::MAX)
+                }
+                _ => FRAME_DELTA,
+            })
+            .collect();
+
+        let sync_samples = frames
+            .iter()
+            .enumerate()
+            .filter(|(i, frame)| frame.is_key_frame || *i == 0)
+            .map(|(i, _)| i as u32 + 1)
+            .collect();
+
+        Self {
+            data,
+            durations,
+            sync_samples,
+            timescale: (frame_rate * FRAME_DELTA as f32).round() as u32,
+        }
+    }
+
+    fn duration(&self) -> u64 {
+        self.durations.iter().map(|&d| d as u64).sum()
+    }
+
+    fn write_mdat(&self, out: &mut Vec<u8>) {
+        write_box(out, b"mdat", |out| {
+            for sample in &self.data {
+                out.extend_from_slice(sample);
+            }
+        });
+    }
+}
+
+impl VideoSequence<'_> {
+    /// 封装为 MP4 文件，可以直接在浏览器中播放
+    ///
+    /// mp4_header 为视频组合中记录的 MP4 文件头（ftyp + moov），
+    /// 其描述的采样与当前序列完全一致时直接复用，否则重新生成文件头
+    pub fn to_mp4(&self, mp4_header: Option<&[u8]>) -> Vec<u8> {
+        let samples = Samples::new(self);
+        let mut out = match mp4_header {
+            Some(header) if self.is_mp4_header_valid(header, &samples) => header.to_vec(),
+            Some(_) => {
+                log::warn!("Mp4Header does not match the video sequence, regenerated");
+                self.mp4_header(&samples)
+            }
+            None => self.mp4_header(&samples),
+        };
+        samples.write_mdat(&mut out);
+        out
+    }
+
+    /// 文件头描述的采样与写入 mdat 的数据完全一致：
+    /// - stsz 中每一帧的长度与转换后的帧数据一致
+    /// - 所有帧位于同一个 chunk 中，且该 chunk 紧跟在文件头之后的 mdat 中
+    /// - stts 覆盖所有帧，avcC 与序列的 SPS、PPS 一致
+    fn is_mp4_header_valid(&self, header: &[u8], samples: &Samples) -> bool {
+        let count = samples.data.len();
+        if count == 0 {
+            return false;
+        }
+        let stbl: [&[u8; 4]; 5] = [b"moov", b"trak", b"mdia", b"minf", b"stbl"];
+        let table = |kind| find_box(header, &[&stbl[..], &[kind]].concat());
+        let (Some(stsz), Some(stco), Some(stsc), Some(stts), Some(stsd)) = (
+            table(b"stsz"),
+            table(b"stco"),
+            table(b"stsc"),
+            table(b"stts"),
+            table(b"stsd"),
+        ) else {
+            return false;
+        };
+
+        let sizes_match = read_u32(stsz, 0) == Some(0)
+            && read_u32(stsz, 1) == Some(count as u32)
+            && (samples.data.iter().zip(2..))
+                .all(|(sample, i)| read_u32(stsz, i) == Some(sample.len() as u32));
+        let chunk_matches = read_u32(stco, 0) == Some(1)
+            && read_u32(stco, 1) == Some((header.len() + BOX_HEADER_LENGTH) as u32)
+            && read_u32(stsc, 0) == Some(1)
+            && read_u32(stsc, 1) == Some(1)
+            && read_u32(stsc, 2) == Some(count as u32);
+        let timed_samples = (0..read_u32(stts, 0).unwrap_or(0) as usize)
+            .map(|i| read_u32(stts, 1 + i * 2).map_or(0, u64::from))
+            .sum::<u64>();
+
+        // stsd 中依次为 entry_count 及 avc1，avc1 固定部分之后为 avcC
+        let avcc = stsd
+            .get(4 + 4..)
+            .and_then(|entries| find_box(entries, &[b"avc1"]))
+            .and_then(|avc1| find_box(avc1.get(AVC1_FIXED_LENGTH..)?, &[b"avcC"]));
+        let mut expected_avcc = vec![];
+        self.write_avcc(&mut expected_avcc);
+
+        sizes_match
+            && chunk_matches
+            && timed_samples == count as u64
+            && avcc == Some(&expected_avcc[..])
+    }
+
+    /// 生成 ftyp 及 moov
+    fn mp4_header(&self, samples: &Samples) -> Vec<u8> {
+        let mut out = vec![];
+        write_box(&mut out, b"ftyp", |out| {
+            out.extend_from_slice(b"isom");
+            put_u32(out, 0x200);
+            for brand in [b"isom", b"iso2", b"avc1", b"mp41"] {
+                out.extend_from_slice(brand);
+            }
+        });
+        // moov 的长度与 chunk 偏移量无关，先计算长度再写入实际偏移量
+        let moov_length = self.write_moov(&mut vec![], samples, 0);
+        let chunk_offset = out.len() + moov_length + BOX_HEADER_LENGTH;
+        self.write_moov(&mut out, samples, chunk_offset as u32);
+        out
+    }
+
+    fn write_moov(&self, out: &mut Vec<u8>, samples: &Samples, chunk_offset: u32) -> usize {
+        let start = out.len();
+        let media_duration = samples.duration();
+        let movie_duration =
+            (media_duration * MOVIE_TIMESCALE as u64 / samples.timescale.max(1) as u64) as u32;
+        let (width, height) = (self.width.to_i32() as u32, self.height.to_i32() as u32);
+
+        write_box(out, b"moov", |out| {
+            write_full_box(out, b"mvhd", 0, 0, |out| {
+                put_u32(out, 0); // creation_time
+                put_u32(out, 0); // modification_time
+                put_u32(out, MOVIE_TIMESCALE);
+                put_u32(out, movie_duration);
+                put_u32(out, 0x0001_0000); // rate 1.0
+                put_u16(out, 0x0100); // volume 1.0
+                out.extend_from_slice(&[0; 10]);
+                put_matrix(out);
+                out.extend_from_slice(&[0; 24]);
+                put_u32(out, 2); // next_track_ID
+            });
+            write_box(out, b"trak", |out| {
+                write_full_box(out, b"tkhd", 0, 0x3, |out| {
+                    put_u32(out, 0);
+                    put_u32(out, 0);
+                    put_u32(out, 1); // track_ID
+                    put_u32(out, 0);
+                    put_u32(out, movie_duration);
+                    out.extend_from_slice(&[0; 8]);
+                    put_u16(out, 0); // layer
+                    put_u16(out, 0); // alternate_group
+                    put_u16(out, 0); // volume
+                    put_u16(out, 0);
+                    put_matrix(out);
+                    put_u32(out, width << 16);
+                    put_u32(out, height << 16);
+                });
+                write_box(out, b"mdia", |out| {
+                    write_full_box(out, b"mdhd", 0, 0, |out| {
+                        put_u32(out, 0);
+                        put_u32(out, 0);
+                        put_u32(out, samples.timescale);
+                        put_u32(out, media_duration as u32);
+                        put_u16(out, 0x55c4); // language: und
+                        put_u16(out, 0);
+                    });
+                    write_full_box(out, b"hdlr", 0, 0, |out| {
+                        put_u32(out, 0);
+                        out.extend_from_slice(b"vide");
+                        out.extend_from_slice(&[0; 12]);
+                        out.extend_from_slice(b"VideoHandler\0");
+                    });
+                    write_box(out, b"minf", |out| {
+                        write_full_box(out, b"vmhd", 0, 1, |out| {
+                            out.extend_from_slice(&[0; 8]);
+                        });
+                        write_box(out, b"dinf", |out| {
+                            write_full_box(out, b"dref", 0, 0, |out| {
+                                put_u32(out, 1);
+                                write_full_box(out, b"url ", 0, 1, |_| {});
+                            });
+                        });
+                        write_box(out, b"stbl", |out| {
+                            self.write_sample_tables(out, samples, chunk_offset);
+                        });
+                    });
+                });
+            });
+        });
+        out.len() - start
+    }
+
+    fn write_sample_tables(&self, out: &mut Vec<u8>, samples: &Samples, chunk_offset: u32) {
+        write_full_box(out, b"stsd", 0, 0, |out| {
+            put_u32(out, 1);
+            write_box(out, b"avc1", |out| {
+                out.extend_from_slice(&[0; 6]);
+                put_u16(out, 1); // data_reference_index
+                out.extend_from_slice(&[0; 16]);
+                put_u16(out, self.width.to_i32() as u16);
+                put_u16(out, self.height.to_i32() as u16);
+                put_u32(out, 0x0048_0000); // 72 dpi
+                put_u32(out, 0x0048_0000);
+                put_u32(out, 0);
+                put_u16(out, 1); // frame_count
+                out.extend_from_slice(&[0; 32]); // compressorname
+                put_u16(out, 0x0018); // depth
+                put_u16(out, 0xffff); // pre_defined = -1
+                write_box(out, b"avcC", |out| self.write_avcc(out));
+            });
+        });
+
+        // 时长相同的连续帧合并为一项
+        let mut runs: Vec<(u32, u32)> = vec![];
+        for &duration in &samples.durations {
+            match runs.last_mut() {
+                Some((count, last)) if *last == duration => *count += 1,
+                _ => runs.push((1, duration)),
+            }
+        }
+        write_full_box(out, b"stts", 0, 0, |out| {
+            put_u32(out, runs.len() as u32);
+            for (count, duration) in &runs {
+                put_u32(out, *count);
+                put_u32(out, *duration);
+            }
+        });
+
+        // 全部为关键帧时省略 stss
+        if samples.sync_samples.len() < samples.data.len() {
+            write_full_box(out, b"stss", 0, 0, |out| {
+                put_u32(out, samples.sync_samples.len() as u32);
+                for &sample in &samples.sync_samples {
+                    put_u32(out, sample);
+                }
+            });
+        }
+
+        // 所有帧存放在同一个 chunk 中
+        write_full_box(out, b"stsc", 0, 0, |out| {
+            put_u32(out, 1);
+            put_u32(out, 1); // first_chunk
+            put_u32(out, samples.data.len() as u32);
+            put_u32(out, 1); // sample_description_index
+        });
+        write_full_box(out, b"stsz", 0, 0, |out| {
+            put_u32(out, 0);
+            put_u32(out, samples.data.len() as u32);
+            for sample in &samples.data {
+                put_u32(out, sample.len() as u32);
+            }
+        });
+        write_full_box(out, b"stco", 0, 0, |out| {
+            put_u32(out, 1);
+            put_u32(out, chunk_offset);
+        });
+    }
+
+    /// AVCDecoderConfigurationRecord
+    fn write_avcc(&self, out: &mut Vec<u8>) {
+        let framing = self.nal_framing();
+        let sps = framing.split(&self.sps_data.data);
+        let pps = framing.split(&self.pps_data.data);
+        let profile = sps
+            .first()
+            .map_or(&[][..], |sps| sps.get(1..4).unwrap_or(&[]));
+        out.push(1); // configurationVersion
+        out.extend_from_slice(profile);
+        out.extend(std::iter::repeat_n(0, 3 - profile.len()));
+        out.push(0xff); // lengthSizeMinusOne = 3
+        out.push(0xe0 | sps.len() as u8);
+        for unit in sps {
+            put_u16(out, unit.len() as u16);
+            out.extend_from_slice(unit);
+        }
+        out.push(pps.len() as u8);
+        for unit in pps {
+            put_u16(out, unit.len() as u16);
+            out.extend_from_slice(unit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32, Time},
+        ByteData, VideoFrame, VideoSequence,
+    };
+
+    use super::{find_box, read_u32, Samples, AVC1_FIXED_LENGTH, FRAME_DELTA};
+
+    fn new_sequence<'a>(sps: &'a [u8], pps: &'a [u8], frames: &'a [[u8; 6]]) -> VideoSequence<'a> {
+        VideoSequence {
+            width: EncodedInt32::from(32),
+            height: EncodedInt32::from(16),
+            frame_rate: 24.,
+            alpha_start_x: None,
+            alpha_start_y: None,
+            sps_data: ByteData::from(sps),
+            pps_data: ByteData::from(pps),
+            frame_count: EncodedUint32::from(frames.len() as u32),
+            is_key_frame_flag: vec![],
+            video_frames: frames
+                .iter()
+                .enumerate()
+                .map(|(i, data)| VideoFrame {
+                    is_key_frame: i == 0,
+                    // 第 2 帧之后为静态区间，第 3 帧从 4 开始显示
+                    frame: Time::from(if i < 2 { i as u64 } else { i as u64 + 2 }),
+                    file_bytes: ByteData::from(data),
+                })
+                .collect(),
+            static_time_ranges: vec![],
+        }
+    }
+
+    #[test]
+    fn test_to_mp4() {
+        let sps = [0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e];
+        let pps = [0, 0, 0, 1, 0x68, 0xce];
+        let frames = [
+            [0, 0, 0, 1, 0x65, 0x88],
+            [0, 0, 0, 1, 0x41, 0x9a],
+            [0, 0, 0, 1, 0x41, 0x9b],
+        ];
+        let sequence = new_sequence(&sps, &pps, &frames);
+        let mp4 = sequence.to_mp4(None);
+
+        let stbl: [&[u8; 4]; 5] = [b"moov", b"trak", b"mdia", b"minf", b"stbl"];
+        let table = |kind: &[u8; 4]| find_box(&mp4, &[&stbl[..], &[kind]].concat()).unwrap();
+
+        // avc1 sample entry 固定部分之后为 avcC
+        let stsd = table(b"stsd");
+        let avcc = find_box(&stsd[8 + 8 + AVC1_FIXED_LENGTH..], &[b"avcC"]).unwrap();
+        assert_eq!(
+            avcc,
+            [1, 0x42, 0xc0, 0x1e, 0xff, 0xe1, 0, 4, 0x67, 0x42, 0xc0, 0x1e, 1, 0, 2, 0x68, 0xce]
+        );
+
+        // 24fps，时间刻度为 24000，第 2 帧显示 3 帧
+        let mdhd = find_box(&mp4, &[b"moov", b"trak", b"mdia", b"mdhd"]).unwrap();
+        assert_eq!(read_u32(mdhd, 2), Some(24000));
+        assert_eq!(read_u32(mdhd, 3), Some(5000));
+        let stts = table(b"stts");
+        assert_eq!(
+            &stts[4..],
+            [
+                0, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0x03, 0xe8, 0, 0, 0, 1, 0, 0, 0x0b, 0xb8, 0, 0, 0, 1,
+                0, 0, 0x03, 0xe8
+            ]
+        );
+        assert_eq!(&table(b"stss")[4..], [0, 0, 0, 1, 0, 0, 0, 1]);
+
+        // 帧数据转换为长度前缀格式，存放在 stco 指向的位置
+        let offset = read_u32(table(b"stco"), 1).unwrap() as usize;
+        assert_eq!(&mp4[offset - 4..offset], b"mdat");
+        assert_eq!(&mp4[offset..offset + 6], [0, 0, 0, 2, 0x65, 0x88]);
+        assert_eq!(mp4.len(), offset + 18);
+
+        // 与序列一致的文件头直接复用，否则重新生成
+        let header = &mp4[..offset - 8];
+        assert_eq!(sequence.to_mp4(Some(header)), mp4);
+        let other = new_sequence(&sps, &pps, &frames[..2]);
+        let regenerated = other.to_mp4(Some(header));
+        assert_ne!(&regenerated[..offset - 8], header);
+        assert_eq!(
+            read_u32(
+                find_box(&regenerated, &[&stbl[..], &[b"stsz"]].concat()).unwrap(),
+                1
+            ),
+            Some(2)
+        );
+
+        // 帧数一致但某一帧的长度与 stsz 不一致时同样重新生成
+        let mut tampered = header.to_vec();
+        let stsz = tampered
+            .windows(4)
+            .position(|kind| kind == b"stsz")
+            .unwrap();
+        tampered[stsz + 16..stsz + 20].copy_from_slice(&7u32.to_be_bytes());
+        assert_eq!(sequence.to_mp4(Some(&tampered)), mp4);
+    }
+
+    #[test]
+    fn test_sample_duration_overflow() {
+        let frames = [[0, 0, 0, 1, 0x65, 0x88]; 3];
+        let mut sequence = new_sequence(&[], &[], &frames);
+        // 帧号间隔过大时，时长截断为 u32 的最大值
+        sequence.video_frames[1].frame = Time::from(u32::MAX as u64);
+        sequence.video_frames[2].frame = Time::from(u64::MAX);
+        let samples = Samples::new(&sequence);
+        assert_eq!(samples.durations, [u32::MAX, u32::MAX, FRAME_DELTA]);
+    }
+}
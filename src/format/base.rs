@@ -1,19 +1,36 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug};
 
-use macros::ParsableEnum;
+use macros::{EncodableEnum, ParsableEnum};
 use num_enum::{FromPrimitive, IntoPrimitive};
 
-use crate::parse::{EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError, Parser};
+use crate::{
+    encode::{Encodable, Writer},
+    parse::{
+        AttributeType, Bits, EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError,
+        ParseErrorKind, Parser, SPATIAL_PRECISION,
+    },
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub red: u8,
     pub green: u8,
     pub blue: u8,
 }
 
-impl Parsable for Color {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl Color {
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const WHITE: Color = Color::new(0xff, 0xff, 0xff);
+    pub const RED: Color = Color::new(0xff, 0, 0);
+
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl<'a> Parsable<'a> for Color {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let red = parser.next_u8()?;
         let green = parser.next_u8()?;
         let blue = parser.next_u8()?;
@@ -21,114 +38,480 @@ impl Parsable for Color {
     }
 }
 
-pub struct ByteData {
+impl Encodable for Color {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_u8(self.red);
+        writer.write_u8(self.green);
+        writer.write_u8(self.blue);
+    }
+}
+
+/// 二进制数据，解析时直接引用输入数据
+pub struct ByteData<'a> {
     pub length: EncodedUint32,
-    pub data: Vec<u8>,
+    pub data: Cow<'a, [u8]>,
 }
 
-impl ByteData {
-    pub fn from(data: &[u8]) -> Self {
+impl<'a> ByteData<'a> {
+    pub fn from(data: &'a [u8]) -> Self {
         Self {
             length: EncodedUint32::from(data.len() as u32),
-            data: Vec::from(data),
+            data: Cow::Borrowed(data),
+        }
+    }
+
+    pub fn from_vec(data: Vec<u8>) -> ByteData<'static> {
+        ByteData {
+            length: EncodedUint32::from(data.len() as u32),
+            data: Cow::Owned(data),
+        }
+    }
+
+    /// 复制引用的数据
+    pub fn into_owned(self) -> ByteData<'static> {
+        ByteData {
+            length: self.length,
+            data: Cow::Owned(self.data.into_owned()),
         }
     }
 }
 
-impl Debug for ByteData {
+impl Debug for ByteData<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("ByteData").field(&self.length).finish()
     }
 }
 
-impl Parsable for ByteData {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let length = parser.next_encoded_u32()?;
-        let data = parser.next_bytes(length.to_usize())?;
+impl<'a> Parsable<'a> for ByteData<'a> {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let length = parser
+            .next_encoded_u32()
+            .map_err(|e| e.with_field("length"))?;
+        let data = parser
+            .next_bytes(length.to_usize())
+            .map_err(|e| e.with_field("data"))?;
         assert_eq!(length, data.len() as u32);
         let result = Self {
             length,
-            data: Vec::from(data),
+            data: Cow::Borrowed(data),
         };
         log::debug!("parse_ByteData => {:?}", result);
         Ok(result)
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+impl Encodable for ByteData<'_> {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_u32(EncodedUint32::from(self.data.len() as u32));
+        writer.write_bytes(&self.data);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ByteData<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        /// 序列化时才进行 base64 编码，省略模式下跳过 data 字段时不必编码
+        struct Base64<'d>(&'d [u8]);
+
+        impl serde::Serialize for Base64<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use base64::Engine;
+                serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(self.0))
+            }
+        }
+
+        // 省略模式下由 WithByteDataMode 跳过 data 字段
+        let mut state = serializer.serialize_struct("ByteData", 2)?;
+        state.serialize_field("length", &self.length)?;
+        state.serialize_field("data", &Base64(&self.data))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ByteData<'_> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use base64::Engine;
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            length: EncodedUint32,
+            data: Option<String>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let data = fields
+            .data
+            .ok_or_else(|| D::Error::custom("ByteData is elided, serialize with Base64 mode"))?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(D::Error::custom)?;
+        // 编辑后的数据以实际长度为准
+        if fields.length != data.len() as u32 {
+            log::warn!(
+                "ByteData length {} does not match data length {}",
+                fields.length,
+                data.len()
+            );
+        }
+        Ok(ByteData::from_vec(data))
+    }
+}
+
+/// 裁剪路径类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrimPathsType {
+    Simultaneously = 0,
+    Individually = 1,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 合并路径模式
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MergePathsMode {
+    Merge = 0,
+    Add = 1,
+    Subtract = 2,
+    Intersect = 3,
+    ExcludeIntersections = 4,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 渐变类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GradientFillType {
+    Linear = 0,
+    Radial = 1,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 线帽类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineCap {
+    Butt = 0,
+    Round = 1,
+    Square = 2,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 线段连接类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineJoin {
+    Miter = 0,
+    Round = 1,
+    Bevel = 2,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 合成顺序
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CompositeOrder {
+    BelowPreviousInSameGroup = 0,
+    AbovePreviousInSameGroup = 1,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 填充规则
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FillRule {
+    NonZeroWinding = 0,
+    EvenOdd = 1,
+    /// 未知类型
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+/// 多边星形类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PolyStarType {
+    Star = 0,
+    Polygon = 1,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+/// 遮罩蒙层的覆盖类型
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MaskMode {
+    None = 0,
+    Add = 1,
+    Subtract = 2,
+    Intersect = 3,
+    Lighten = 4,
+    Darken = 5,
+    Difference = 6,
+    Accum = 7,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
-#[derive(Debug)]
-pub struct Path {}
+/// PathVerb 文件中存储的路径动作类型，坐标数据按需省略
+#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PathRecord {
+    /// 闭合当前的路径到路径起点
+    Close = 0,
+    /// 移动坐标点到指定位置
+    Move = 1,
+    /// 绘制一条直线
+    Line = 2,
+    /// 绘制一条水平直线，Y 轴不变为上一次值
+    HLine = 3,
+    /// 绘制一条垂直直线，X 轴不变为上一次值
+    VLine = 4,
+    /// 绘制一条三次贝塞尔曲线，第一个控制点跟上个动作的结束点相同
+    Curve01 = 5,
+    /// 绘制一条三次贝塞尔曲线，结束点跟第二个控制点相同
+    Curve10 = 6,
+    /// 绘制一条三次贝塞尔曲线，依次存储两个控制点和结束点
+    Curve11 = 7,
+    /// 未知类型
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+/// 路径动作
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PathVerb {
+    /// 移动到 1 个坐标点
+    MoveTo,
+    /// 直线连接到 1 个坐标点
+    LineTo,
+    /// 三次贝塞尔曲线，依次使用第一个控制点、第二个控制点和结束点共 3 个坐标点
+    CurveTo,
+    /// 闭合当前路径，不使用坐标点
+    Close,
+}
+
+/// Path 用来标识路径等信息，主要的信息包含：动作列表与坐标列表。
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path {
+    pub verbs: Vec<PathVerb>,
+    pub points: Vec<Point>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verbs.is_empty()
+    }
+
+    pub fn move_to(&mut self, point: Point) {
+        self.verbs.push(PathVerb::MoveTo);
+        self.points.push(point);
+    }
 
-impl Parsable for Path {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        Ok(Self {})
+    pub fn line_to(&mut self, point: Point) {
+        self.verbs.push(PathVerb::LineTo);
+        self.points.push(point);
+    }
+
+    pub fn curve_to(&mut self, control1: Point, control2: Point, point: Point) {
+        self.verbs.push(PathVerb::CurveTo);
+        self.points.extend([control1, control2, point]);
+    }
+
+    pub fn close(&mut self) {
+        self.verbs.push(PathVerb::Close);
     }
 }
 
-#[derive(Debug)]
+impl<'a> Parsable<'a> for Path {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut path = Path::new();
+        let num_verbs = parser.next_encoded_u32()?.to_usize();
+        if num_verbs == 0 {
+            return Ok(path);
+        }
+        // 每个动作占用 3 个比特位，避免异常数据导致分配过大的内存
+        if num_verbs > parser.remain() * 8 / 3 {
+            return Err(
+                ParseError::from(ParseErrorKind::Incomplete(nom::Needed::Unknown))
+                    .with_offset(parser.offset())
+                    .with_field("numVerbs"),
+            );
+        }
+
+        parser.next_bits(|bits| {
+            let records = (0..num_verbs)
+                .map(|_| PathRecord::from(bits.next_ubits(3) as u8))
+                .collect::<Vec<_>>();
+            let num_bits = bits.next_num_bits();
+            let mut next_float = || bits.next_sbits(num_bits) as f32 * SPATIAL_PRECISION;
+
+            let mut last_point = Point::zero();
+            for record in records {
+                match record {
+                    PathRecord::Close => path.close(),
+                    PathRecord::Move => {
+                        last_point = Point::new(next_float(), next_float());
+                        path.move_to(last_point);
+                    }
+                    PathRecord::Line => {
+                        last_point = Point::new(next_float(), next_float());
+                        path.line_to(last_point);
+                    }
+                    PathRecord::HLine => {
+                        last_point.x = next_float();
+                        path.line_to(last_point);
+                    }
+                    PathRecord::VLine => {
+                        last_point.y = next_float();
+                        path.line_to(last_point);
+                    }
+                    PathRecord::Curve01 => {
+                        let control1 = last_point;
+                        let control2 = Point::new(next_float(), next_float());
+                        last_point = Point::new(next_float(), next_float());
+                        path.curve_to(control1, control2, last_point);
+                    }
+                    PathRecord::Curve10 => {
+                        let control1 = Point::new(next_float(), next_float());
+                        last_point = Point::new(next_float(), next_float());
+                        path.curve_to(control1, last_point, last_point);
+                    }
+                    PathRecord::Curve11 => {
+                        let control1 = Point::new(next_float(), next_float());
+                        let control2 = Point::new(next_float(), next_float());
+                        last_point = Point::new(next_float(), next_float());
+                        path.curve_to(control1, control2, last_point);
+                    }
+                    PathRecord::Unknown(record) => {
+                        log::warn!("Unknown path record: {}", record);
+                    }
+                }
+            }
+        })?;
+
+        log::debug!("parse_Path => {:?}", path);
+        Ok(path)
+    }
+}
+
+impl Encodable for Path {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut records = vec![];
+        let mut floats = vec![];
+        let mut points = self.points.iter().copied();
+        let mut next_point = || points.next().unwrap_or(Point::zero());
+
+        // 坐标与上一个点相同的部分省略存储
+        let mut last_point = Point::zero();
+        for verb in &self.verbs {
+            match verb {
+                PathVerb::Close => records.push(PathRecord::Close),
+                PathVerb::MoveTo => {
+                    last_point = next_point();
+                    records.push(PathRecord::Move);
+                    floats.extend([last_point.x, last_point.y]);
+                }
+                PathVerb::LineTo => {
+                    let point = next_point();
+                    if point.x == last_point.x {
+                        records.push(PathRecord::VLine);
+                        floats.push(point.y);
+                    } else if point.y == last_point.y {
+                        records.push(PathRecord::HLine);
+                        floats.push(point.x);
+                    } else {
+                        records.push(PathRecord::Line);
+                        floats.extend([point.x, point.y]);
+                    }
+                    last_point = point;
+                }
+                PathVerb::CurveTo => {
+                    let control1 = next_point();
+                    let control2 = next_point();
+                    let point = next_point();
+                    if control1 == last_point {
+                        records.push(PathRecord::Curve01);
+                        floats.extend([control2.x, control2.y, point.x, point.y]);
+                    } else if control2 == point {
+                        records.push(PathRecord::Curve10);
+                        floats.extend([control1.x, control1.y, point.x, point.y]);
+                    } else {
+                        records.push(PathRecord::Curve11);
+                        floats.extend([control1.x, control1.y, control2.x, control2.y]);
+                        floats.extend([point.x, point.y]);
+                    }
+                    last_point = point;
+                }
+            }
+        }
+
+        writer.write_encoded_u32(EncodedUint32::from(records.len() as u32));
+        if records.is_empty() {
+            return;
+        }
+        for record in records {
+            writer.write_ubits(u8::from(record) as u32, 3);
+        }
+        writer.write_float_list(&floats, SPATIAL_PRECISION);
+        writer.align();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -148,15 +531,51 @@ impl Point {
     }
 }
 
-impl Parsable for Point {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for Point {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let x = parser.next_f32()?;
         let y = parser.next_f32()?;
         Ok(Self { x, y })
     }
+
+    fn parse_value_bits(bits: &mut Bits, count: usize, r#type: AttributeType) -> Option<Vec<Self>> {
+        if r#type != AttributeType::SpatialProperty {
+            return None;
+        }
+        let list = bits.next_float_list(count * 2, SPATIAL_PRECISION);
+        Some(list.chunks(2).map(|xy| Self::new(xy[0], xy[1])).collect())
+    }
+
+    fn dimensionality() -> usize {
+        2
+    }
 }
 
-#[derive(Debug)]
+impl Encodable for Point {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_f32(self.x);
+        writer.write_f32(self.y);
+    }
+
+    fn encode_value_bits(
+        values: &[&Self],
+        writer: &mut impl Writer,
+        r#type: AttributeType,
+    ) -> bool {
+        if r#type != AttributeType::SpatialProperty {
+            return false;
+        }
+        let list = values
+            .iter()
+            .flat_map(|point| [point.x, point.y])
+            .collect::<Vec<_>>();
+        writer.write_float_list(&list, SPATIAL_PRECISION);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ratio {
     pub numerator: EncodedInt32,
     pub denominator: EncodedUint32,
@@ -175,8 +594,8 @@ impl Ratio {
     }
 }
 
-impl Parsable for Ratio {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for Ratio {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let numerator = parser.next_encoded_i32()?;
         let denominator = parser.next_encoded_u32()?;
         let result = Self {
@@ -188,15 +607,23 @@ impl Parsable for Ratio {
     }
 }
 
-#[derive(Debug)]
+impl Encodable for Ratio {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_i32(self.numerator);
+        writer.write_encoded_u32(self.denominator);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AlphaStop {
     pub position: u16,
     pub midpoint: u16,
     pub opacity: u8,
 }
 
-impl Parsable for AlphaStop {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for AlphaStop {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let position = parser.next_u16()?;
         let midpoint = parser.next_u16()?;
         let opacity = parser.next_u8()?;
@@ -210,15 +637,24 @@ impl Parsable for AlphaStop {
     }
 }
 
-#[derive(Debug)]
+impl Encodable for AlphaStop {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_u16(self.position);
+        writer.write_u16(self.midpoint);
+        writer.write_u8(self.opacity);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorStop {
     pub position: u16,
     pub midpoint: u16,
     pub color: Color,
 }
 
-impl Parsable for ColorStop {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for ColorStop {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let position = parser.next_u16()?;
         let midpoint = parser.next_u16()?;
         let color = parser.next()?;
@@ -232,27 +668,36 @@ impl Parsable for ColorStop {
     }
 }
 
-#[derive(Debug)]
+impl Encodable for ColorStop {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_u16(self.position);
+        writer.write_u16(self.midpoint);
+        self.color.encode(writer, ctx);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GradientColor {
-    pub alpha_count: u32,
-    pub color_count: u32,
+    pub alpha_count: EncodedUint32,
+    pub color_count: EncodedUint32,
     pub alpha_stop_list: Vec<AlphaStop>,
     pub color_stop_list: Vec<ColorStop>,
 }
 
-impl Parsable for GradientColor {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let alpha_count = parser.next_u32()?;
-        let color_count = parser.next_u32()?;
+impl<'a> Parsable<'a> for GradientColor {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let alpha_count = EncodedUint32::parse(parser, ctx.clone())?;
+        let color_count = EncodedUint32::parse(parser, ctx.clone())?;
 
         let mut alpha_stop_list = vec![];
-        for _ in 0..alpha_count {
+        for _ in 0..alpha_count.to_u32() {
             let stop = AlphaStop::parse(parser, ctx.clone())?;
             alpha_stop_list.push(stop);
         }
 
         let mut color_stop_list = vec![];
-        for _ in 0..color_count {
+        for _ in 0..color_count.to_u32() {
             let stop = ColorStop::parse(parser, ctx.clone())?;
             color_stop_list.push(stop);
         }
@@ -268,19 +713,60 @@ impl Parsable for GradientColor {
     }
 }
 
+impl Encodable for GradientColor {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(EncodedUint32::from(self.alpha_stop_list.len() as u32));
+        writer.write_encoded_u32(EncodedUint32::from(self.color_stop_list.len() as u32));
+        for stop in &self.alpha_stop_list {
+            stop.encode(writer, ctx.clone());
+        }
+        for stop in &self.color_stop_list {
+            stop.encode(writer, ctx.clone());
+        }
+    }
+}
+
 /// 混合模式
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendMode {
-    Normal,
+    Normal = 0,
+    Multiply = 1,
+    Screen = 2,
+    Overlay = 3,
+    Darken = 4,
+    Lighten = 5,
+    ColorDodge = 6,
+    ColorBurn = 7,
+    HardLight = 8,
+    SoftLight = 9,
+    Difference = 10,
+    Exclusion = 11,
+    Hue = 12,
+    Saturation = 13,
+    Color = 14,
+    Luminosity = 15,
+    Add = 16,
+    DestinationIn = 21,
+    DestinationOut = 22,
+    DestinationATop = 23,
+    SourceIn = 24,
+    SourceOut = 25,
+    Xor = 26,
     /// 未知类型
     #[num_enum(catch_all)]
     Unknown(u8),
 }
 
 /// 轨道蒙版
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrackMatteType {
     None,
     /// 未知类型
@@ -288,8 +774,11 @@ pub enum TrackMatteType {
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LayerType {
     Null = 1,
     Solid = 2,
@@ -303,8 +792,11 @@ pub enum LayerType {
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum)]
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive, ParsableEnum, EncodableEnum,
+)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParagraphJustification {
     LeftJustify,
     /// 未知类型
@@ -314,14 +806,15 @@ pub enum ParagraphJustification {
 
 /// SolidColor 标识边框宽⾼以及颜⾊属性信息。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SolidColor {
     pub solid_color: Color,
     pub width: EncodedInt32,
     pub height: EncodedInt32,
 }
 
-impl Parsable for SolidColor {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for SolidColor {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let solid_color = parser.next()?;
         let width = parser.next_encoded_i32()?;
         let height = parser.next_encoded_i32()?;
@@ -334,3 +827,47 @@ impl Parsable for SolidColor {
         Ok(result)
     }
 }
+
+impl Encodable for SolidColor {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        self.solid_color.encode(writer, ctx);
+        writer.write_encoded_i32(self.width);
+        writer.write_encoded_i32(self.height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{Parsable, Parser, StreamParser};
+
+    use super::{Path, PathVerb, Point};
+
+    #[test]
+    fn test_parse_path() {
+        // 规范中 (x: 5, y: 0, r: 15, b: 20) 矩形的例子：Move, HLine, VLine, HLine, Close
+        let input = [0x05, 0x19, 0x87, 0x44, 0x06, 0x00, 0x2c, 0x41, 0x46, 0x06];
+        let mut parser = StreamParser::new(&input);
+        let path = Path::parse(&mut parser, ()).unwrap();
+
+        assert_eq!(
+            path.verbs,
+            vec![
+                PathVerb::MoveTo,
+                PathVerb::LineTo,
+                PathVerb::LineTo,
+                PathVerb::LineTo,
+                PathVerb::Close,
+            ]
+        );
+        assert_eq!(
+            path.points,
+            vec![
+                Point::new(5., 0.),
+                Point::new(15., 0.),
+                Point::new(15., 20.),
+                Point::new(5., 20.),
+            ]
+        );
+        assert!(parser.is_empty());
+    }
+}
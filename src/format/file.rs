@@ -1,17 +1,22 @@
 use crate::{
-    parse::{EncodedInt64, Parsable, ParseContext, ParseError, Parser},
-    visit::{LayerInfo, Traversable},
-    Tag, TagBlock,
+    encode::{Encodable, Writer},
+    parse::{EncodedInt64, EncodedUint32, Parsable, ParseContext, ParseError, Parser},
+    visit::{CompositionGraph, ImageAsset, ImageIndex, LayerInfo, Traversable},
+    LayerBlock, Tag, TagBlock, TagBody,
 };
 
-/// Pag 文件格式
+/// Pag 文件格式，图片、视频等二进制数据直接引用输入数据，不进行复制
 #[derive(Debug)]
-pub struct Pag {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PagRef<'a> {
     pub header: FileHeader,
-    pub tag_block: TagBlock,
+    pub tag_block: TagBlock<'a>,
 }
 
-impl Pag {
+/// 持有全部数据的 Pag 文件格式
+pub type Pag = PagRef<'static>;
+
+impl<'a> PagRef<'a> {
     pub fn new(header: FileHeader) -> Self {
         Self {
             header,
@@ -19,12 +24,52 @@ impl Pag {
         }
     }
 
-    pub fn push_tag(&mut self, tag: Tag) {
+    pub fn push_tag(&mut self, tag: Tag<'a>) {
         self.tag_block.push(tag);
     }
+
+    /// 复制引用的二进制数据，转换为持有全部数据的 Pag
+    pub fn into_owned(self) -> Pag {
+        PagRef {
+            header: self.header,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+
+    /// 宽松模式下解析失败的标签所记录的错误信息
+    pub fn diagnostics(&self) -> Vec<&ParseError> {
+        let mut diagnostics = vec![];
+        collect_diagnostics(&self.tag_block, &mut diagnostics);
+        diagnostics
+    }
+
+    /// 建立图片 ID 到图片数据的索引，需要多次查询时应复用索引
+    pub fn image_index(&self) -> ImageIndex<'_> {
+        ImageIndex::new(&self.tag_block)
+    }
+
+    /// 建立组合之间的引用关系，主组合为最后一个顶层组合
+    pub fn composition_graph(&self) -> CompositionGraph<'_> {
+        CompositionGraph::new(&self.tag_block)
+    }
+
+    /// 查找图片图层所引用的图片数据
+    pub fn image_for_layer(&self, layer: &LayerBlock<'_>) -> Option<ImageAsset<'_>> {
+        self.image_index().for_layer(layer).copied()
+    }
+}
+
+fn collect_diagnostics<'a>(tag_block: &'a TagBlock<'_>, diagnostics: &mut Vec<&'a ParseError>) {
+    for tag in &tag_block.tags {
+        if let TagBody::Invalid { error, .. } = &tag.body {
+            diagnostics.push(error);
+        } else if let Some(tag_block) = tag.body.tag_block() {
+            collect_diagnostics(tag_block, diagnostics);
+        }
+    }
 }
 
-impl Traversable for Pag {
+impl Traversable for PagRef<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo) + Clone,
@@ -35,18 +80,32 @@ impl Traversable for Pag {
 
 /// Pag 文件头
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FileHeader {
     pub version: u8,
     pub length: u32,
     pub compress_method: i8,
 }
 
-impl Parsable for FileHeader {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let _ = parser.next_term("PAG")?;
-        let version = parser.next_u8()?;
-        let length = parser.next_u32()?;
-        let compress_method = parser.next_i8()?;
+impl Default for FileHeader {
+    /// 当前支持的文件版本，不压缩，文件长度在写出时重新计算
+    fn default() -> Self {
+        Self {
+            version: 1,
+            length: 0,
+            compress_method: 0,
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for FileHeader {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let _ = parser.next_term("PAG").map_err(|e| e.with_field("magic"))?;
+        let version = parser.next_u8().map_err(|e| e.with_field("version"))?;
+        let length = parser.next_u32().map_err(|e| e.with_field("length"))?;
+        let compress_method = parser
+            .next_i8()
+            .map_err(|e| e.with_field("compressMethod"))?;
         Ok(Self {
             version,
             length,
@@ -55,7 +114,17 @@ impl Parsable for FileHeader {
     }
 }
 
+impl Encodable for FileHeader {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_bytes(b"PAG");
+        writer.write_u8(self.version);
+        writer.write_u32(self.length);
+        writer.write_i8(self.compress_method);
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FileAttributes {
     pub timestamp: EncodedInt64,
     pub plugin_version: String,
@@ -66,8 +135,8 @@ pub struct FileAttributes {
     pub warnings: Vec<String>,
 }
 
-impl Parsable for FileAttributes {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for FileAttributes {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let timestamp = parser.next_encoded_i64()?;
         let plugin_version = parser.next_string()?;
         let ae_version = parser.next_string()?;
@@ -92,3 +161,18 @@ impl Parsable for FileAttributes {
         })
     }
 }
+
+impl Encodable for FileAttributes {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_i64(self.timestamp);
+        writer.write_string(&self.plugin_version);
+        writer.write_string(&self.ae_version);
+        writer.write_string(&self.system_version);
+        writer.write_string(&self.author);
+        writer.write_string(&self.scene);
+        writer.write_encoded_u32(EncodedUint32::from(self.warnings.len() as u32));
+        for warning in &self.warnings {
+            writer.write_string(warning);
+        }
+    }
+}
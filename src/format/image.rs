@@ -1,5 +1,8 @@
 use crate::{
-    parse::{EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError, Parser},
+    encode::{Encodable, Writer},
+    parse::{
+        EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError, ParseErrorKind, Parser,
+    },
     visit::{LayerInfo, Traversable},
 };
 
@@ -7,13 +10,28 @@ use super::{ByteData, TagBlock};
 
 /// ImageTables 是图⽚信息的合集。
 #[derive(Debug)]
-pub struct ImageTables {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageTables<'a> {
     pub count: EncodedInt32,
-    pub images: Vec<ImageBytes>,
+    pub images: Vec<ImageBytes<'a>>,
 }
 
-impl Parsable for ImageTables {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl ImageTables<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> ImageTables<'static> {
+        ImageTables {
+            count: self.count,
+            images: self
+                .images
+                .into_iter()
+                .map(ImageBytes::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for ImageTables<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let count = parser.next_encoded_i32()?;
         let mut images = vec![];
         for _ in 0..count.to_i32() {
@@ -26,63 +44,278 @@ impl Parsable for ImageTables {
     }
 }
 
+impl Encodable for ImageTables<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_i32(EncodedInt32::from(self.images.len() as i32));
+        for image in &self.images {
+            image.encode(writer, ctx.clone());
+        }
+    }
+}
+
 /// BitmapCompositionBlock 位图序列帧标签。
 #[derive(Debug)]
-pub struct BitmapCompositionBlock {
-    // pub inner: AttributeBlock,
-    pub id: u32,
-    pub tag_block: TagBlock,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitmapCompositionBlock<'a> {
+    pub id: EncodedUint32,
+    pub tag_block: TagBlock<'a>,
+}
+
+impl BitmapCompositionBlock<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> BitmapCompositionBlock<'static> {
+        BitmapCompositionBlock {
+            id: self.id,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for BitmapCompositionBlock<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_id().map_err(|e| e.with_field("id"))?;
+        let tag_block = TagBlock::parse(parser, ctx)?;
+        let result = Self { id, tag_block };
+        log::debug!("parse_BitmapCompositionBlock => {:?}", result);
+        Ok(result)
+    }
 }
 
-impl Traversable for BitmapCompositionBlock {
+impl Traversable for BitmapCompositionBlock<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
-        F: Fn(&dyn LayerInfo),
+        F: Fn(&dyn LayerInfo) + Clone,
     {
-        // self.tag_block.traverse_layer(visitor)
+        self.tag_block.traverse_layer(visitor)
     }
 }
 
-/// BitmapSequence 标签。
+impl Encodable for BitmapCompositionBlock<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_id(self.id);
+        self.tag_block.encode(writer, ctx);
+    }
+}
+
+/// BitmapSequence 标签，存储了 1 个尺寸的位图序列帧。
+/// 关键帧存储完整画面，其余帧只存储与前一帧相比发生变化的矩形区域。
 #[derive(Debug)]
-pub struct BitmapSequence {
-    // pub inner: AttributeBlock,
-    pub width: u32,
-    pub height: u32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitmapSequence<'a> {
+    pub width: EncodedInt32,
+    pub height: EncodedInt32,
     pub frame_rate: f32,
-    pub frame_count: u32,
+    pub frame_count: EncodedUint32,
     pub is_key_frame_flag: Vec<bool>,
-    pub bitmap_rect: Vec<BitmapRect>,
+    pub bitmap_frames: Vec<BitmapFrame<'a>>,
+}
+
+impl BitmapSequence<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> BitmapSequence<'static> {
+        BitmapSequence {
+            width: self.width,
+            height: self.height,
+            frame_rate: self.frame_rate,
+            frame_count: self.frame_count,
+            is_key_frame_flag: self.is_key_frame_flag,
+            bitmap_frames: self
+                .bitmap_frames
+                .into_iter()
+                .map(BitmapFrame::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for BitmapSequence<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let width = parser.next_encoded_i32()?;
+        let height = parser.next_encoded_i32()?;
+        let frame_rate = parser.next_f32()?;
+        let frame_count = parser
+            .next_encoded_u32()
+            .map_err(|e| e.with_field("frameCount"))?;
+        // 每帧占用 1 个比特位的关键帧标识，避免异常数据导致分配过大的内存
+        if frame_count.to_usize() > parser.remain() * 8 {
+            return Err(
+                ParseError::from(ParseErrorKind::Incomplete(nom::Needed::Unknown))
+                    .with_offset(parser.offset())
+                    .with_field("frameCount"),
+            );
+        }
+        let is_key_frame_flag = parser.next_bits(|bits| {
+            (0..frame_count.to_u32())
+                .map(|_| bits.next())
+                .collect::<Vec<_>>()
+        })?;
+
+        let mut bitmap_frames = vec![];
+        for i in 0..frame_count.to_u32() {
+            let mut frame = BitmapFrame::parse(parser, ctx.clone())?;
+            frame.is_key_frame = is_key_frame_flag
+                .get(i as usize)
+                .copied()
+                .unwrap_or_default();
+            bitmap_frames.push(frame);
+        }
+
+        let result = Self {
+            width,
+            height,
+            frame_rate,
+            frame_count,
+            is_key_frame_flag,
+            bitmap_frames,
+        };
+        log::debug!("parse_BitmapSequence => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for BitmapSequence<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_i32(self.width);
+        writer.write_encoded_i32(self.height);
+        writer.write_f32(self.frame_rate);
+        writer.write_encoded_u32(EncodedUint32::from(self.bitmap_frames.len() as u32));
+        for frame in &self.bitmap_frames {
+            writer.write_bit(frame.is_key_frame);
+        }
+        for frame in &self.bitmap_frames {
+            frame.encode(writer, ctx.clone());
+        }
+    }
+}
+
+/// 位图序列帧中的一帧，由若干个矩形区域的图片组成。
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitmapFrame<'a> {
+    pub is_key_frame: bool,
+    pub bitmaps: Vec<BitmapRect<'a>>,
+}
+
+impl BitmapFrame<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> BitmapFrame<'static> {
+        BitmapFrame {
+            is_key_frame: self.is_key_frame,
+            bitmaps: self
+                .bitmaps
+                .into_iter()
+                .map(BitmapRect::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for BitmapFrame<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let count = parser
+            .next_encoded_u32()
+            .map_err(|e| e.with_field("bitmapCount"))?;
+        let mut bitmaps = vec![];
+        for _ in 0..count.to_u32() {
+            bitmaps.push(BitmapRect::parse(parser, ctx.clone())?);
+        }
+        // is_key_frame 由 BitmapSequence 统一读取后设置
+        Ok(Self {
+            is_key_frame: false,
+            bitmaps,
+        })
+    }
 }
 
+impl Encodable for BitmapFrame<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(EncodedUint32::from(self.bitmaps.len() as u32));
+        for bitmap in &self.bitmaps {
+            bitmap.encode(writer, ctx.clone());
+        }
+    }
+}
+
+/// 位图序列帧中的矩形区域，存储了该区域压缩后的图片及其在画面中的位置。
 #[derive(Debug)]
-pub struct BitmapRect {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitmapRect<'a> {
+    pub x: EncodedInt32,
+    pub y: EncodedInt32,
+    pub file_bytes: ByteData<'a>,
+}
+
+impl BitmapRect<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> BitmapRect<'static> {
+        BitmapRect {
+            x: self.x,
+            y: self.y,
+            file_bytes: self.file_bytes.into_owned(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for BitmapRect<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let x = parser.next_encoded_i32()?;
+        let y = parser.next_encoded_i32()?;
+        let file_bytes = ByteData::parse(parser, ctx)?;
+        Ok(Self { x, y, file_bytes })
+    }
+}
+
+impl Encodable for BitmapRect<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_i32(self.x);
+        writer.write_encoded_i32(self.y);
+        self.file_bytes.encode(writer, ctx);
+    }
+}
 
 /// ImageReference 图⽚引⽤标签，存储的是⼀个图⽚的唯⼀ ID，通过 ID 索引真正的图⽚信息。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageReference {
     pub id: EncodedUint32,
 }
 
-impl Parsable for ImageReference {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_id()?;
+impl<'a> Parsable<'a> for ImageReference {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_id().map_err(|e| e.with_field("id"))?;
         let result = Self { id };
         log::debug!("parse_ImageReference => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for ImageReference {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_id(self.id);
+    }
+}
+
 /// ImageBytes 图⽚标签，存储了压缩后的图⽚相关属性信息。
 #[derive(Debug)]
-pub struct ImageBytes {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageBytes<'a> {
     pub id: EncodedUint32,
-    pub file_bytes: ByteData,
+    pub file_bytes: ByteData<'a>,
+}
+
+impl ImageBytes<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> ImageBytes<'static> {
+        ImageBytes {
+            id: self.id,
+            file_bytes: self.file_bytes.into_owned(),
+        }
+    }
 }
 
-impl Parsable for ImageBytes {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_encoded_u32()?;
+impl<'a> Parsable<'a> for ImageBytes<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_encoded_u32().map_err(|e| e.with_field("id"))?;
         let file_bytes = ByteData::parse(parser, ctx)?;
         let result = Self { id, file_bytes };
         log::debug!("parse_ImageBytes => {:?}", result);
@@ -90,17 +323,36 @@ impl Parsable for ImageBytes {
     }
 }
 
+impl Encodable for ImageBytes<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(self.id);
+        self.file_bytes.encode(writer, ctx);
+    }
+}
+
 /// ImageBytes2 图⽚标签版本 2，除了存储 ImageBytes 的信息外，还允许记录图⽚的缩放参数，通常根据实际最⼤⽤到的⼤⼩来存储图⽚，⽽不是按原始⼤⼩。
 #[derive(Debug)]
-pub struct ImageBytes2 {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageBytes2<'a> {
     pub id: EncodedUint32,
-    pub file_bytes: ByteData,
+    pub file_bytes: ByteData<'a>,
     pub scale_factor: f32,
 }
 
-impl Parsable for ImageBytes2 {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_encoded_u32()?;
+impl ImageBytes2<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> ImageBytes2<'static> {
+        ImageBytes2 {
+            id: self.id,
+            file_bytes: self.file_bytes.into_owned(),
+            scale_factor: self.scale_factor,
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for ImageBytes2<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_encoded_u32().map_err(|e| e.with_field("id"))?;
         let file_bytes = ByteData::parse(parser, ctx)?;
         let scale_factor = parser.next_f32()?;
         let result = Self {
@@ -113,11 +365,20 @@ impl Parsable for ImageBytes2 {
     }
 }
 
+impl Encodable for ImageBytes2<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(self.id);
+        self.file_bytes.encode(writer, ctx);
+        writer.write_f32(self.scale_factor);
+    }
+}
+
 /// ImageBytes3 图⽚标签版本 3， 除了包含 ImageBytes2 的信息外，还允许记录剔除透明边框后的图⽚。
 #[derive(Debug)]
-pub struct ImageBytes3 {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageBytes3<'a> {
     pub id: EncodedUint32,
-    pub file_bytes: ByteData,
+    pub file_bytes: ByteData<'a>,
     pub scale_factor: f32,
     pub width: EncodedInt32,
     pub height: EncodedInt32,
@@ -125,9 +386,24 @@ pub struct ImageBytes3 {
     pub anchor_y: EncodedInt32,
 }
 
-impl Parsable for ImageBytes3 {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_encoded_u32()?;
+impl ImageBytes3<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> ImageBytes3<'static> {
+        ImageBytes3 {
+            id: self.id,
+            file_bytes: self.file_bytes.into_owned(),
+            scale_factor: self.scale_factor,
+            width: self.width,
+            height: self.height,
+            anchor_x: self.anchor_x,
+            anchor_y: self.anchor_y,
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for ImageBytes3<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_encoded_u32().map_err(|e| e.with_field("id"))?;
         let file_bytes = ByteData::parse(parser, ctx)?;
         let scale_factor = parser.next_f32()?;
         let width = parser.next_encoded_i32()?;
@@ -147,3 +423,121 @@ impl Parsable for ImageBytes3 {
         Ok(result)
     }
 }
+
+impl Encodable for ImageBytes3<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(self.id);
+        self.file_bytes.encode(writer, ctx);
+        writer.write_f32(self.scale_factor);
+        writer.write_encoded_i32(self.width);
+        writer.write_encoded_i32(self.height);
+        writer.write_encoded_i32(self.anchor_x);
+        writer.write_encoded_i32(self.anchor_y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encode::PagWriter,
+        parse::{
+            EncodedInt32, EncodedUint32, PagParser, Parsable, ParseErrorKind, ParseOptions,
+            StreamParser,
+        },
+        BitmapCompositionBlock, BitmapFrame, BitmapRect, BitmapSequence, ByteData, FileHeader, Pag,
+        Tag, TagBlock, TagBody, TagCode,
+    };
+
+    fn new_rect(x: i32, y: i32, data: &'static [u8]) -> BitmapRect<'static> {
+        BitmapRect {
+            x: EncodedInt32::from(x),
+            y: EncodedInt32::from(y),
+            file_bytes: ByteData::from(data),
+        }
+    }
+
+    #[test]
+    fn test_bitmap_sequence() {
+        let sequence = BitmapSequence {
+            width: EncodedInt32::from(64),
+            height: EncodedInt32::from(32),
+            frame_rate: 24.,
+            frame_count: EncodedUint32::from(3),
+            is_key_frame_flag: vec![true, false, false],
+            bitmap_frames: vec![
+                BitmapFrame {
+                    is_key_frame: true,
+                    bitmaps: vec![new_rect(0, 0, b"key")],
+                },
+                // 画面没有变化的帧不包含矩形区域
+                BitmapFrame {
+                    is_key_frame: false,
+                    bitmaps: vec![],
+                },
+                BitmapFrame {
+                    is_key_frame: false,
+                    bitmaps: vec![new_rect(8, 4, b"a"), new_rect(40, 16, b"b")],
+                },
+            ],
+        };
+        let composition = BitmapCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::BitmapSequence,
+                    TagBody::BitmapSequence(sequence),
+                )],
+            },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::BitmapCompositionBlock,
+            TagBody::BitmapCompositionBlock(composition),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        let input = PagWriter::write_all(&pag);
+
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let parsed = PagParser::parse_all_with(&input, options).unwrap();
+        let graph = parsed.composition_graph();
+        let composition = graph.main().unwrap();
+        assert_eq!(composition.id(), 1);
+        let sequences = composition.bitmap_sequences().collect::<Vec<_>>();
+        assert_eq!(sequences.len(), 1);
+        let sequence = sequences[0];
+        assert_eq!(
+            (sequence.width.to_i32(), sequence.height.to_i32()),
+            (64, 32)
+        );
+        assert_eq!(sequence.frame_rate, 24.);
+        let frames = &sequence.bitmap_frames;
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.is_key_frame)
+                .collect::<Vec<_>>(),
+            [true, false, false]
+        );
+        assert!(frames[1].bitmaps.is_empty());
+        let rect = &frames[2].bitmaps[1];
+        assert_eq!((rect.x.to_i32(), rect.y.to_i32()), (40, 16));
+        assert_eq!(*rect.file_bytes.data, *b"b");
+
+        assert_eq!(PagWriter::write_all(&parsed), input);
+    }
+
+    #[test]
+    fn test_bitmap_sequence_frame_count_overflow() {
+        // width、height、frameRate 之后的 frameCount 远大于剩余数据可容纳的关键帧标识
+        let input = [
+            0x80, 0x01, 0x40, 0x00, 0x00, 0xc0, 0x41, 0xff, 0xff, 0xff, 0xff, 0x0f,
+        ];
+        let mut parser = StreamParser::new(&input);
+        let error = BitmapSequence::parse(&mut parser, ()).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Incomplete(_)));
+        assert_eq!(error.field, Some("frameCount"));
+    }
+}
@@ -0,0 +1,238 @@
+use super::{Color, Point};
+
+/// 可在两个属性值之间插值的类型
+pub trait Interpolate: Clone {
+    /// 按进度 t（0 ~ 1）在 self 与 other 之间插值
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+
+    /// 多维属性按各个维度的进度分别插值，进度个数少于维度时使用第一个进度
+    fn interpolate_dimensions(&self, other: &Self, t: &[f32]) -> Self {
+        self.interpolate(other, t[0])
+    }
+
+    /// 沿空间贝塞尔曲线插值，t 为曲线长度的比例，只有坐标点支持空间插值
+    fn interpolate_spatial(&self, other: &Self, _out: Point, _in: Point, t: f32) -> Self {
+        self.interpolate(other, t)
+    }
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for u8 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        (*self as f32).interpolate(&(*other as f32), t).round() as u8
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Color {
+            red: self.red.interpolate(&other.red, t),
+            green: self.green.interpolate(&other.green, t),
+            blue: self.blue.interpolate(&other.blue, t),
+        }
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Point::new(
+            self.x.interpolate(&other.x, t),
+            self.y.interpolate(&other.y, t),
+        )
+    }
+
+    fn interpolate_dimensions(&self, other: &Self, t: &[f32]) -> Self {
+        let (tx, ty) = (t[0], t.get(1).copied().unwrap_or(t[0]));
+        Point::new(
+            self.x.interpolate(&other.x, tx),
+            self.y.interpolate(&other.y, ty),
+        )
+    }
+
+    fn interpolate_spatial(&self, other: &Self, out: Point, r#in: Point, t: f32) -> Self {
+        SpatialBezier::new(
+            *self,
+            Point::new(self.x + out.x, self.y + out.y),
+            Point::new(other.x + r#in.x, other.y + r#in.y),
+            *other,
+        )
+        .position(t)
+    }
+}
+
+/// 时间缓动曲线，起点为 (0, 0)，终点为 (1, 1)，x 为时间进度，y 为数值进度
+#[derive(Debug, Clone, Copy)]
+pub struct BezierEasing {
+    control1: Point,
+    control2: Point,
+}
+
+impl BezierEasing {
+    /// 求解曲线参数时允许的误差
+    const EPSILON: f32 = 1e-6;
+
+    pub fn new(control1: Point, control2: Point) -> Self {
+        Self { control1, control2 }
+    }
+
+    /// 一维三次贝塞尔曲线在 t 处的值，两端点分别为 0 和 1
+    fn sample(c1: f32, c2: f32, t: f32) -> f32 {
+        let u = 1. - t;
+        3. * u * u * t * c1 + 3. * u * t * t * c2 + t * t * t
+    }
+
+    fn sample_derivative(c1: f32, c2: f32, t: f32) -> f32 {
+        let u = 1. - t;
+        3. * u * u * c1 + 6. * u * t * (c2 - c1) + 3. * t * t * (1. - c2)
+    }
+
+    /// 求解 x(t) = x 时的曲线参数 t，先使用牛顿迭代，不收敛时改用二分法
+    fn solve_curve_x(&self, x: f32) -> f32 {
+        let (c1, c2) = (self.control1.x, self.control2.x);
+        let mut t = x;
+        for _ in 0..8 {
+            let error = Self::sample(c1, c2, t) - x;
+            if error.abs() < Self::EPSILON {
+                return t;
+            }
+            let derivative = Self::sample_derivative(c1, c2, t);
+            if derivative.abs() < Self::EPSILON {
+                break;
+            }
+            t -= error / derivative;
+        }
+
+        let (mut low, mut high) = (0f32, 1f32);
+        t = x;
+        while low < high {
+            let value = Self::sample(c1, c2, t);
+            if (value - x).abs() < Self::EPSILON {
+                break;
+            }
+            if x > value {
+                low = t;
+            } else {
+                high = t;
+            }
+            let next = (high - low) * 0.5 + low;
+            if next == t {
+                break;
+            }
+            t = next;
+        }
+        t
+    }
+
+    /// 时间进度对应的数值进度
+    pub fn value(&self, progress: f32) -> f32 {
+        if progress <= 0. || progress >= 1. {
+            return progress.clamp(0., 1.);
+        }
+        let t = self.solve_curve_x(progress);
+        Self::sample(self.control1.y, self.control2.y, t)
+    }
+}
+
+/// 空间贝塞尔曲线，按曲线长度比例取点
+#[derive(Debug, Clone)]
+pub struct SpatialBezier {
+    /// 曲线上均匀分布的采样点
+    points: Vec<Point>,
+    /// 起点到各个采样点的折线长度
+    lengths: Vec<f32>,
+}
+
+impl SpatialBezier {
+    /// 折线近似曲线时的分段数
+    const SEGMENTS: usize = 64;
+
+    pub fn new(start: Point, control1: Point, control2: Point, end: Point) -> Self {
+        let mut points = Vec::with_capacity(Self::SEGMENTS + 1);
+        let mut lengths = Vec::with_capacity(Self::SEGMENTS + 1);
+        for i in 0..=Self::SEGMENTS {
+            let t = i as f32 / Self::SEGMENTS as f32;
+            let u = 1. - t;
+            let (a, b, c, d) = (u * u * u, 3. * u * u * t, 3. * u * t * t, t * t * t);
+            let point = Point::new(
+                a * start.x + b * control1.x + c * control2.x + d * end.x,
+                a * start.y + b * control1.y + c * control2.y + d * end.y,
+            );
+            let length = match points.last() {
+                Some(last) => lengths[i - 1] + distance(*last, point),
+                None => 0.,
+            };
+            points.push(point);
+            lengths.push(length);
+        }
+        Self { points, lengths }
+    }
+
+    /// 曲线总长度
+    pub fn length(&self) -> f32 {
+        self.lengths[Self::SEGMENTS]
+    }
+
+    /// 从起点沿曲线走过 percent 比例的长度所在的点
+    pub fn position(&self, percent: f32) -> Point {
+        let total = self.length();
+        if percent <= 0. || total == 0. {
+            return self.points[0];
+        }
+        if percent >= 1. {
+            return self.points[Self::SEGMENTS];
+        }
+        let length = total * percent;
+        let index = self.lengths.partition_point(|&l| l < length).max(1);
+        let (start, end) = (self.lengths[index - 1], self.lengths[index]);
+        let t = if end > start {
+            (length - start) / (end - start)
+        } else {
+            0.
+        };
+        self.points[index - 1].interpolate(&self.points[index], t)
+    }
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::{BezierEasing, SpatialBezier};
+
+    #[test]
+    fn test_bezier_easing() {
+        // 控制点位于对角线上时为线性
+        let linear = BezierEasing::new(Point::new(0.25, 0.25), Point::new(0.75, 0.75));
+        assert!((linear.value(0.3) - 0.3).abs() < 1e-4);
+
+        // CSS ease-in-out: cubic-bezier(0.42, 0, 0.58, 1)
+        let ease = BezierEasing::new(Point::new(0.42, 0.), Point::new(0.58, 1.));
+        assert!((ease.value(0.5) - 0.5).abs() < 1e-4);
+        assert!((ease.value(0.25) - 0.1291).abs() < 1e-3);
+        assert!((ease.value(0.75) - 0.8709).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_spatial_bezier() {
+        // 控制点位于线段上时，按长度比例取点与线性插值一致
+        let bezier = SpatialBezier::new(
+            Point::zero(),
+            Point::new(10., 0.),
+            Point::new(20., 0.),
+            Point::new(100., 0.),
+        );
+        assert!((bezier.length() - 100.).abs() < 1e-3);
+        let point = bezier.position(0.25);
+        assert!((point.x - 25.).abs() < 1e-3);
+        assert_eq!(point.y, 0.);
+    }
+}
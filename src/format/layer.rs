@@ -1,4 +1,7 @@
+use std::ops::Range;
+
 use crate::{
+    encode::{AttributeBlockWriter, Encodable, Writer},
     parse::{
         AttributeConfig, AttributeType, EncodedUint32, EncodedUint64, Parsable, ParseContext,
         ParseError, Parser, Time,
@@ -8,22 +11,52 @@ use crate::{
 };
 
 use super::{
-    BlendMode, Color, CompositeOrder, LayerType, MaskMode, Path, Point, Ratio, TagBlock, TagCode,
-    TrackMatteType,
+    BlendMode, Color, CompositeOrder, Frame, LayerType, MaskMode, Matrix2D, Path, Point, Property,
+    Ratio, TagBlock, TagCode, TrackMatteType,
 };
 
 /// LayerBlock 是图层信息的合集。
 #[derive(Debug)]
-pub struct LayerBlock {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerBlock<'a> {
     pub r#type: LayerType,
     pub id: EncodedUint32,
-    pub tag_block: TagBlock,
+    pub tag_block: TagBlock<'a>,
 }
 
-impl Parsable for LayerBlock {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let r#type = parser.next_enum()?;
-        let id = parser.next_id()?;
+impl LayerBlock<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> LayerBlock<'static> {
+        LayerBlock {
+            r#type: self.r#type,
+            id: self.id,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+
+    /// 图层的属性信息，依次查找 LayerAttributes 的各个版本
+    pub fn attributes(&self) -> Option<&LayerAttributes> {
+        self.tag_block.tags.iter().find_map(|tag| match &tag.body {
+            TagBody::LayerAttributes(attributes) => Some(attributes),
+            TagBody::LayerAttributesV2(attributes) => Some(attributes),
+            TagBody::LayerAttributesV3(attributes) => Some(attributes),
+            _ => None,
+        })
+    }
+
+    /// 图层的 2D 变换信息
+    pub fn transform(&self) -> Option<&Transform2D> {
+        self.tag_block.tags.iter().find_map(|tag| match &tag.body {
+            TagBody::Transform2D(transform) => Some(transform),
+            _ => None,
+        })
+    }
+}
+
+impl<'a> Parsable<'a> for LayerBlock<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let r#type = parser.next_enum().map_err(|e| e.with_field("type"))?;
+        let id = parser.next_id().map_err(|e| e.with_field("id"))?;
         let tag_block = TagBlock::parse(parser, ctx.with_layer_type(r#type))?;
         let result = Self {
             r#type,
@@ -35,23 +68,35 @@ impl Parsable for LayerBlock {
     }
 }
 
-impl LayerInfo for LayerBlock {
+impl Encodable for LayerBlock<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_enum(self.r#type);
+        writer.write_id(self.id);
+        self.tag_block
+            .encode(writer, ctx.with_layer_type(self.r#type));
+    }
+}
+
+impl LayerInfo for LayerBlock<'_> {
     fn get_layer_name(&self) -> Option<&str> {
-        let attribute = self.tag_block.tags.iter().find_map(|tag| match &tag.body {
-            TagBody::LayerAttributes(attributes) => Some(attributes),
-            TagBody::LayerAttributesV2(attributes) => Some(attributes),
-            TagBody::LayerAttributesV3(attributes) => Some(attributes),
-            _ => None,
-        });
-        attribute.map(|attr| attr.name.as_str())
+        self.attributes().map(|attr| attr.name.as_str())
     }
 
     fn get_layer_type(&self) -> LayerType {
         self.r#type
     }
+
+    fn get_layer_id(&self) -> u32 {
+        self.id.to_u32()
+    }
+
+    fn get_layer_time_span(&self) -> Option<Range<Frame>> {
+        self.attributes()
+            .map(|attr| attr.in_point()..attr.out_point())
+    }
 }
 
-impl Traversable for LayerBlock {
+impl Traversable for LayerBlock<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo),
@@ -62,6 +107,7 @@ impl Traversable for LayerBlock {
 
 /// LayerAttributes 是图层的属性信息。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerAttributes {
     pub is_active: bool,
     pub auto_orientation: bool,
@@ -71,13 +117,42 @@ pub struct LayerAttributes {
     pub start_time: Time,
     pub blend_mode: BlendMode,
     pub track_matte_type: TrackMatteType,
-    pub time_remap: f32,
+    pub time_remap: Property<f32>,
     pub duration: Time,
     pub name: String,
 }
 
-impl Parsable for LayerAttributes {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl LayerAttributes {
+    /// 图层在所属组合中的起始帧（包含）
+    pub fn in_point(&self) -> Frame {
+        self.start_time.to_i64()
+    }
+
+    /// 图层在所属组合中的结束帧（不包含）
+    pub fn out_point(&self) -> Frame {
+        self.in_point() + self.duration.to_i64()
+    }
+
+    /// 图层在组合的某一帧是否可见
+    pub fn is_active_at(&self, frame: Frame) -> bool {
+        self.is_active && self.in_point() <= frame && frame < self.out_point()
+    }
+
+    /// 时间伸缩比例，大于 1 时图层内容播放变慢，无效值按 1 处理
+    pub fn stretch_factor(&self) -> f64 {
+        let (numerator, denominator) = (
+            self.stretch.numerator.to_i32(),
+            self.stretch.denominator.to_u32(),
+        );
+        if numerator <= 0 || denominator == 0 {
+            return 1.;
+        }
+        numerator as f64 / denominator as f64
+    }
+}
+
+impl<'a> Parsable<'a> for LayerAttributes {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let is_active = block.flag(AttributeConfig::BitFlag(true));
         let auto_orientation = block.flag(AttributeConfig::BitFlag(false));
@@ -115,17 +190,31 @@ impl Parsable for LayerAttributes {
         };
 
         let mut result = Self {
-            is_active: block.read(is_active).unwrap_or(true),
-            auto_orientation: block.read(auto_orientation).unwrap_or(false),
-            motion_blur: block.read(motion_blur).unwrap_or(false),
-            parent: block.read(parent).unwrap_or(EncodedUint32::from(0)),
-            stretch: block.read(stretch).unwrap_or(Ratio::one()),
-            start_time: block.read(start_time).unwrap_or(EncodedUint64::from(0)),
-            blend_mode: block.read(blend_mode).unwrap_or(BlendMode::Normal),
-            track_matte_type: block.read(track_matte_type).unwrap_or(TrackMatteType::None),
-            time_remap: block.read(time_remap).unwrap_or(0.),
-            duration: block.read(duration).unwrap_or(EncodedUint64::from(0)),
-            name: block.read(name).unwrap_or_default(),
+            is_active: block.read("is_active", is_active)?.unwrap_or(true),
+            auto_orientation: block
+                .read("auto_orientation", auto_orientation)?
+                .unwrap_or(false),
+            motion_blur: block.read("motion_blur", motion_blur)?.unwrap_or(false),
+            parent: block
+                .read("parent", parent)?
+                .unwrap_or(EncodedUint32::from(0)),
+            stretch: block.read("stretch", stretch)?.unwrap_or(Ratio::one()),
+            start_time: block
+                .read("start_time", start_time)?
+                .unwrap_or(EncodedUint64::from(0)),
+            blend_mode: block
+                .read("blend_mode", blend_mode)?
+                .unwrap_or(BlendMode::Normal),
+            track_matte_type: block
+                .read("track_matte_type", track_matte_type)?
+                .unwrap_or(TrackMatteType::None),
+            time_remap: block
+                .read_property("time_remap", time_remap)?
+                .unwrap_or(Property::Value(0.)),
+            duration: block
+                .read("duration", duration)?
+                .unwrap_or(EncodedUint64::from(0)),
+            name: block.read("name", name)?.unwrap_or_default(),
         };
 
         // The duration can not be zero, fix it when the value is parsed from an old file format.
@@ -134,19 +223,57 @@ impl Parsable for LayerAttributes {
         }
 
         // let input = block.finish();
+        parser.advance(block.byte_length()?);
         log::debug!("parse_LayerAttributes => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for LayerAttributes {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        let is_camera = ctx.layer_type() == Some(LayerType::Camera);
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::BitFlag(true), &self.is_active);
+        block.write(AttributeConfig::BitFlag(false), &self.auto_orientation);
+        // v3 && !Camera => motionBlur
+        if ctx.parent_code() == Some(TagCode::LayerAttributesV3)
+            && ctx.layer_type().is_some()
+            && !is_camera
+        {
+            block.write(AttributeConfig::BitFlag(false), &self.motion_blur);
+        }
+        block.write(AttributeConfig::Value(EncodedUint32::from(0)), &self.parent);
+        block.write(AttributeConfig::Value(Ratio::one()), &self.stretch);
+        block.write(AttributeConfig::Value(Time::from(0)), &self.start_time);
+        // !Camera => blendMode, track_matte_type
+        if !is_camera {
+            block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+            block.write(
+                AttributeConfig::Value(TrackMatteType::None),
+                &self.track_matte_type,
+            );
+        }
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.time_remap);
+        block.write(AttributeConfig::FixedValue(Time::from(0)), &self.duration);
+        // v2 || v3 => name
+        if let Some(TagCode::LayerAttributesV2) | Some(TagCode::LayerAttributesV3) =
+            ctx.parent_code()
+        {
+            block.write(AttributeConfig::Value(String::new()), &self.name);
+        }
+        block.finish(writer);
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerAttributesExtra {
     pub name: String,
     pub motion_blur: bool,
 }
 
-impl Parsable for LayerAttributesExtra {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for LayerAttributesExtra {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let name = block.flag(AttributeConfig::Value("".to_string()));
         // !Camera => motionBlur
@@ -157,26 +284,41 @@ impl Parsable for LayerAttributesExtra {
         };
 
         let result = Self {
-            name: block.read(name).unwrap_or_default(),
-            motion_blur: block.read(motion_blur).unwrap_or(false),
+            name: block.read("name", name)?.unwrap_or_default(),
+            motion_blur: block.read("motion_blur", motion_blur)?.unwrap_or(false),
         };
 
         // let input = block.finish();
+        parser.advance(block.byte_length()?);
         log::debug!("parse_LayerAttributesExtra => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for LayerAttributesExtra {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(String::new()), &self.name);
+        // !Camera => motionBlur
+        match ctx.layer_type() {
+            Some(LayerType::Camera) | None => {}
+            Some(_) => block.write(AttributeConfig::BitFlag(false), &self.motion_blur),
+        }
+        block.finish(writer);
+    }
+}
+
 /// CompositionReference 图层组合索引标签，存储的是⼀个图层组合的唯⼀ ID，通过 ID 索引真正的图层组合。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompositionReference {
     pub id: EncodedUint32,
     pub composition_start_time: Time,
 }
 
-impl Parsable for CompositionReference {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_encoded_u32()?;
+impl<'a> Parsable<'a> for CompositionReference {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_encoded_u32().map_err(|e| e.with_field("id"))?;
         let composition_start_time = parser.next_time()?;
         let result = Self {
             id,
@@ -187,20 +329,52 @@ impl Parsable for CompositionReference {
     }
 }
 
+impl Encodable for CompositionReference {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_u32(self.id);
+        writer.write_time(self.composition_start_time);
+    }
+}
+
 /// Transform2D 2D 变换信息，包含：锚点，缩放，旋转，x 轴偏移，y 轴偏移等信息。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform2D {
-    pub anchor_point: Point,
-    pub position: Point,
-    pub x_position: f32,
-    pub y_position: f32,
-    pub scale: Point,
-    pub rotation: f32,
-    pub opacity: u8,
+    pub anchor_point: Property<Point>,
+    pub position: Property<Point>,
+    pub x_position: Property<f32>,
+    pub y_position: Property<f32>,
+    pub scale: Property<Point>,
+    pub rotation: Property<f32>,
+    pub opacity: Property<u8>,
 }
 
-impl Parsable for Transform2D {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl Transform2D {
+    /// 某一帧的变换矩阵：以锚点为原点，依次缩放、旋转，再平移到 position
+    pub fn matrix_at(&self, frame: Frame) -> Matrix2D {
+        // 关键帧列表为空的属性按默认值处理
+        let anchor_point = self.anchor_point.value_at(frame).unwrap_or(Point::zero());
+        // position 与 x_position/y_position 只会存在其一，不存在的一方为默认值 0
+        let position = self.position.value_at(frame).unwrap_or(Point::zero());
+        let x = position.x + self.x_position.value_at(frame).unwrap_or(0.);
+        let y = position.y + self.y_position.value_at(frame).unwrap_or(0.);
+        let scale = self.scale.value_at(frame).unwrap_or(Point::one());
+        let rotation = self.rotation.value_at(frame).unwrap_or(0.);
+
+        Matrix2D::translate(-anchor_point.x, -anchor_point.y)
+            .then(&Matrix2D::scale(scale.x, scale.y))
+            .then(&Matrix2D::rotate(rotation))
+            .then(&Matrix2D::translate(x, y))
+    }
+
+    /// 某一帧的不透明度，范围 0 ~ 1
+    pub fn opacity_at(&self, frame: Frame) -> f32 {
+        self.opacity.value_at(frame).unwrap_or(0xff) as f32 / 255.
+    }
+}
+
+impl<'a> Parsable<'a> for Transform2D {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let anchor_point = block.flag(AttributeType::SpatialProperty);
         let position = block.flag(AttributeType::SpatialProperty);
@@ -211,69 +385,218 @@ impl Parsable for Transform2D {
         let opacity = block.flag(AttributeType::SimpleProperty);
 
         let result = Self {
-            anchor_point: block.read(anchor_point).unwrap_or(Point::zero()),
-            position: block.read(position).unwrap_or(Point::zero()),
-            x_position: block.read(x_position).unwrap_or(0.),
-            y_position: block.read(y_position).unwrap_or(0.),
-            scale: block.read(scale).unwrap_or(Point::one()),
-            rotation: block.read(rotation).unwrap_or(0.),
-            opacity: block.read(opacity).unwrap_or(0xff),
+            anchor_point: block
+                .read_property("anchor_point", anchor_point)?
+                .unwrap_or(Property::Value(Point::zero())),
+            position: block
+                .read_property("position", position)?
+                .unwrap_or(Property::Value(Point::zero())),
+            x_position: block
+                .read_property("x_position", x_position)?
+                .unwrap_or(Property::Value(0.)),
+            y_position: block
+                .read_property("y_position", y_position)?
+                .unwrap_or(Property::Value(0.)),
+            scale: block
+                .read_property("scale", scale)?
+                .unwrap_or(Property::Value(Point::one())),
+            rotation: block
+                .read_property("rotation", rotation)?
+                .unwrap_or(Property::Value(0.)),
+            opacity: block
+                .read_property("opacity", opacity)?
+                .unwrap_or(Property::Value(0xff)),
         };
         // let input = block.finish();
 
+        parser.advance(block.byte_length()?);
         log::debug!("parse_Transform2D => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for Transform2D {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.anchor_point,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.position,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.x_position);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.y_position);
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::one()),
+            &self.scale,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.rotation);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.finish(writer);
+    }
+}
+
 /// Mask 遮罩标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mask {
     pub id: EncodedUint32,
     pub inverted: bool,
     pub mask_mode: MaskMode,
-    pub mask_path: Path,
-    pub mask_opacity: u8,
-    pub mask_expansion: f32,
+    pub mask_path: Property<Path>,
+    pub mask_opacity: Property<u8>,
+    pub mask_expansion: Property<f32>,
 }
 
-impl Parsable for Mask {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_id()?;
-        let inverted = parser.next_bool()?;
-        let mask_mode = parser.next_enum()?;
-        let mask_path = Path::parse(parser, ctx)?;
-        let mask_opacity = parser.next_u8()?;
-        let mask_expansion = parser.next_f32()?;
+impl<'a> Parsable<'a> for Mask {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let id = block.flag(AttributeConfig::FixedValue(0));
+        let inverted = block.flag(AttributeConfig::BitFlag(false));
+        let mask_mode = block.flag(AttributeConfig::Value(MaskMode::Add));
+        let mask_path = block.flag(AttributeConfig::SimpleProperty(Path::new()));
+        let mask_opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+        let mask_expansion = block.flag(AttributeConfig::SimpleProperty(0.));
+
         let result = Self {
-            id,
-            inverted,
-            mask_mode,
-            mask_path,
-            mask_opacity,
-            mask_expansion,
+            id: block.read("id", id)?.unwrap_or(EncodedUint32::from(0)),
+            inverted: block.read("inverted", inverted)?.unwrap_or(false),
+            mask_mode: block.read("mask_mode", mask_mode)?.unwrap_or(MaskMode::Add),
+            mask_path: block
+                .read_property("mask_path", mask_path)?
+                .unwrap_or(Property::Value(Path::new())),
+            mask_opacity: block
+                .read_property("mask_opacity", mask_opacity)?
+                .unwrap_or(Property::Value(0xff)),
+            mask_expansion: block
+                .read_property("mask_expansion", mask_expansion)?
+                .unwrap_or(Property::Value(0.)),
         };
+
+        parser.advance(block.byte_length()?);
         log::debug!("parse_Mask => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for Mask {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(
+            AttributeConfig::FixedValue(EncodedUint32::from(0)),
+            &self.id,
+        );
+        block.write(AttributeConfig::BitFlag(false), &self.inverted);
+        block.write(AttributeConfig::Value(MaskMode::Add), &self.mask_mode);
+        block.write_property(
+            AttributeConfig::SimpleProperty(Path::new()),
+            &self.mask_path,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.mask_opacity);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.mask_expansion);
+        block.finish(writer);
+    }
+}
+
 /// Repeater 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Repeater {
     pub composite: CompositeOrder,
-    pub copies: f32,
-    pub offset: f32,
-    pub anchor_point: Point,
-    pub position: Point,
-    pub scale: Point,
-    pub rotation: f32,
-    pub start_opacity: u8,
-    pub end_opacity: u8,
+    pub copies: Property<f32>,
+    pub offset: Property<f32>,
+    pub anchor_point: Property<Point>,
+    pub position: Property<Point>,
+    pub scale: Property<Point>,
+    pub rotation: Property<f32>,
+    pub start_opacity: Property<u8>,
+    pub end_opacity: Property<u8>,
+}
+
+impl<'a> Parsable<'a> for Repeater {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let composite = block.flag(AttributeConfig::Value(
+            CompositeOrder::BelowPreviousInSameGroup,
+        ));
+        let copies = block.flag(AttributeConfig::SimpleProperty(3.));
+        let offset = block.flag(AttributeConfig::SimpleProperty(0.));
+        let anchor_point = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let position = block.flag(AttributeConfig::SpatialProperty(Point::new(100., 0.)));
+        let scale = block.flag(AttributeConfig::MultiDimensionProperty(Point::one()));
+        let rotation = block.flag(AttributeConfig::SimpleProperty(0.));
+        let start_opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+        let end_opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+
+        let result = Self {
+            composite: block
+                .read("composite", composite)?
+                .unwrap_or(CompositeOrder::BelowPreviousInSameGroup),
+            copies: block
+                .read_property("copies", copies)?
+                .unwrap_or(Property::Value(3.)),
+            offset: block
+                .read_property("offset", offset)?
+                .unwrap_or(Property::Value(0.)),
+            anchor_point: block
+                .read_property("anchor_point", anchor_point)?
+                .unwrap_or(Property::Value(Point::zero())),
+            position: block
+                .read_property("position", position)?
+                .unwrap_or(Property::Value(Point::new(100., 0.))),
+            scale: block
+                .read_property("scale", scale)?
+                .unwrap_or(Property::Value(Point::one())),
+            rotation: block
+                .read_property("rotation", rotation)?
+                .unwrap_or(Property::Value(0.)),
+            start_opacity: block
+                .read_property("start_opacity", start_opacity)?
+                .unwrap_or(Property::Value(0xff)),
+            end_opacity: block
+                .read_property("end_opacity", end_opacity)?
+                .unwrap_or(Property::Value(0xff)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_Repeater => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for Repeater {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(
+            AttributeConfig::Value(CompositeOrder::BelowPreviousInSameGroup),
+            &self.composite,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(3.), &self.copies);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.offset);
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.anchor_point,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::new(100., 0.)),
+            &self.position,
+        );
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::one()),
+            &self.scale,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.rotation);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.start_opacity);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.end_opacity);
+        block.finish(writer);
+    }
 }
 
 /// DropShadowStyle 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DropShadowStyle {
     pub blend_mode: BlendMode,
     pub color: Color,
@@ -282,3 +605,19 @@ pub struct DropShadowStyle {
     pub distance: f32,
     pub size: f32,
 }
+
+impl Encodable for DropShadowStyle {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(
+            AttributeConfig::DiscreteProperty(BlendMode::Normal),
+            &self.blend_mode,
+        );
+        block.write(AttributeConfig::SimpleProperty(Color::BLACK), &self.color);
+        block.write(AttributeConfig::SimpleProperty(191), &self.opacity);
+        block.write(AttributeConfig::SimpleProperty(120.), &self.angle);
+        block.write(AttributeConfig::SimpleProperty(5.), &self.distance);
+        block.write(AttributeConfig::DiscreteProperty(5.), &self.size);
+        block.finish(writer);
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::Mul;
+
+use super::Point;
+
+/// Matrix2D 2D 仿射变换矩阵，将点 (x, y) 映射为 (a * x + c * y + tx, b * x + d * y + ty)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Matrix2D {
+    pub const IDENTITY: Matrix2D = Matrix2D {
+        a: 1.,
+        b: 0.,
+        c: 0.,
+        d: 1.,
+        tx: 0.,
+        ty: 0.,
+    };
+
+    pub fn new(a: f32, b: f32, c: f32, d: f32, tx: f32, ty: f32) -> Self {
+        Self { a, b, c, d, tx, ty }
+    }
+
+    /// 平移矩阵
+    pub fn translate(tx: f32, ty: f32) -> Self {
+        Self::new(1., 0., 0., 1., tx, ty)
+    }
+
+    /// 缩放矩阵
+    pub fn scale(sx: f32, sy: f32) -> Self {
+        Self::new(sx, 0., 0., sy, 0., 0.)
+    }
+
+    /// 旋转矩阵，单位为角度，y 轴向下时为顺时针方向
+    pub fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::new(cos, sin, -sin, cos, 0., 0.)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// 先应用当前变换，再应用 next 变换
+    pub fn then(&self, next: &Matrix2D) -> Matrix2D {
+        *next * *self
+    }
+
+    /// 对点应用变换
+    pub fn map_point(&self, point: Point) -> Point {
+        Point::new(
+            self.a * point.x + self.c * point.y + self.tx,
+            self.b * point.x + self.d * point.y + self.ty,
+        )
+    }
+
+    /// 逆矩阵，不可逆时返回 None
+    pub fn invert(&self) -> Option<Matrix2D> {
+        let determinant = self.a * self.d - self.b * self.c;
+        if determinant == 0. || !determinant.is_finite() {
+            return None;
+        }
+        let (a, b, c, d) = (
+            self.d / determinant,
+            -self.b / determinant,
+            -self.c / determinant,
+            self.a / determinant,
+        );
+        Some(Self::new(
+            a,
+            b,
+            c,
+            d,
+            -(a * self.tx + c * self.ty),
+            -(b * self.tx + d * self.ty),
+        ))
+    }
+}
+
+impl Default for Matrix2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// 矩阵乘法 self * rhs，即先应用 rhs 变换，再应用 self 变换
+impl Mul for Matrix2D {
+    type Output = Matrix2D;
+
+    fn mul(self, rhs: Matrix2D) -> Matrix2D {
+        Matrix2D {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            tx: self.a * rhs.tx + self.c * rhs.ty + self.tx,
+            ty: self.b * rhs.tx + self.d * rhs.ty + self.ty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::Matrix2D;
+
+    #[test]
+    fn test_matrix_concat() {
+        let matrix = Matrix2D::translate(-10., 0.)
+            .then(&Matrix2D::scale(2., 3.))
+            .then(&Matrix2D::rotate(90.))
+            .then(&Matrix2D::translate(5., 5.));
+        let point = matrix.map_point(Point::new(11., 1.));
+        assert!((point.x - 2.).abs() < 1e-5);
+        assert!((point.y - 7.).abs() < 1e-5);
+
+        let point = matrix.invert().unwrap().map_point(point);
+        assert!((point.x - 11.).abs() < 1e-5);
+        assert!((point.y - 1.).abs() < 1e-5);
+        assert!(Matrix2D::scale(0., 1.).invert().is_none());
+    }
+}
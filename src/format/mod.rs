@@ -1,16 +1,26 @@
 mod base;
+#[cfg(feature = "serde")]
+mod elide;
 mod file;
 mod image;
+mod interpolate;
 mod layer;
+mod matrix;
+mod property;
 mod shape;
 mod tag;
 mod text;
 mod video;
 
 pub use base::*;
+#[cfg(feature = "serde")]
+pub use elide::{ByteDataMode, WithByteDataMode};
 pub use file::*;
 pub use image::*;
+pub use interpolate::*;
 pub use layer::*;
+pub use matrix::*;
+pub use property::*;
 pub use shape::*;
 pub use tag::*;
 pub use text::*;
@@ -0,0 +1,219 @@
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+use crate::parse::Time;
+
+use super::{BezierEasing, Interpolate, Point};
+
+/// 帧序号，换算到子组合的时间轴后可能为负数
+pub type Frame = i64;
+
+/// 关键帧插值器类型
+#[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyframeInterpolationType {
+    /// 无效
+    None = 0,
+    /// 线性插值
+    Linear = 1,
+    /// 根据时间缓动参数使用贝塞尔曲线插值
+    Bezier = 2,
+    /// 整个区间除了 endTime 时刻，都等于 startValue，endTime 瞬间切换到 endValue
+    Hold = 3,
+    /// 未知类型
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+/// Keyframe 关键帧，包含此帧的开始和结束时间、开始和结束的属性值、插值器类型以及缓动参数。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe<T> {
+    pub start_value: T,
+    pub end_value: T,
+    pub start_time: Time,
+    pub end_time: Time,
+    pub interpolation_type: KeyframeInterpolationType,
+    /// 时间缓动参数数组（贝塞尔曲线第 1 个控制点），长度为 dimensionality
+    pub bezier_out: Vec<Point>,
+    /// 时间缓动参数数组（贝塞尔曲线第 2 个控制点），长度为 dimensionality
+    pub bezier_in: Vec<Point>,
+    /// 空间缓动参数（贝塞尔曲线第 1 个控制点）
+    pub spatial_out: Point,
+    /// 空间缓动参数（贝塞尔曲线第 2 个控制点）
+    pub spatial_in: Point,
+}
+
+/// Property 时间轴属性，不含关键帧时退化为一个静态值。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Property<T> {
+    /// 静态值
+    Value(T),
+    /// 关键帧列表，为空时没有可用的属性值
+    Animated(Vec<Keyframe<T>>),
+}
+
+impl<T> Property<T> {
+    /// 是否含有关键帧
+    pub fn is_animated(&self) -> bool {
+        !self.keyframes().is_empty()
+    }
+
+    /// 静态值，或者第一个关键帧的起始值，关键帧列表为空时返回 None
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Animated(keyframes) => keyframes.first().map(|keyframe| &keyframe.start_value),
+        }
+    }
+
+    /// 关键帧列表，静态值时为空
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        match self {
+            Self::Value(_) => &[],
+            Self::Animated(keyframes) => keyframes,
+        }
+    }
+
+    /// 转换为静态值，或者第一个关键帧的起始值，关键帧列表为空时返回 None
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Animated(keyframes) => keyframes
+                .into_iter()
+                .next()
+                .map(|keyframe| keyframe.start_value),
+        }
+    }
+}
+
+impl<T: Interpolate> Keyframe<T> {
+    /// 计算关键帧区间内某一帧的属性值
+    pub fn value_at(&self, frame: Frame) -> T {
+        let (start, end) = (self.start_time.to_i64(), self.end_time.to_i64());
+        if frame <= start || end <= start {
+            return self.start_value.clone();
+        }
+        if frame >= end {
+            return self.end_value.clone();
+        }
+        let progress = (frame - start) as f32 / (end - start) as f32;
+        let progresses = match self.interpolation_type {
+            KeyframeInterpolationType::Hold => return self.start_value.clone(),
+            KeyframeInterpolationType::Bezier if !self.bezier_out.is_empty() => self
+                .bezier_out
+                .iter()
+                .zip(&self.bezier_in)
+                .map(|(out, r#in)| BezierEasing::new(*out, *r#in).value(progress))
+                .collect(),
+            _ => vec![progress],
+        };
+
+        // 空间缓动参数全为 0 时按直线插值
+        if self.spatial_out != Point::zero() || self.spatial_in != Point::zero() {
+            self.start_value.interpolate_spatial(
+                &self.end_value,
+                self.spatial_out,
+                self.spatial_in,
+                progresses[0],
+            )
+        } else {
+            self.start_value
+                .interpolate_dimensions(&self.end_value, &progresses)
+        }
+    }
+}
+
+impl<T: Interpolate> Property<T> {
+    /// 计算某一帧的属性值，第一个关键帧之前取起始值，最后一个关键帧之后取结束值，
+    /// 关键帧列表为空时返回 None
+    pub fn value_at(&self, frame: Frame) -> Option<T> {
+        let keyframes = match self {
+            Self::Value(value) => return Some(value.clone()),
+            Self::Animated(keyframes) => keyframes,
+        };
+        let keyframe = keyframes
+            .iter()
+            .find(|keyframe| frame < keyframe.end_time.to_i64())
+            .or(keyframes.last())?;
+        Some(keyframe.value_at(frame))
+    }
+}
+
+impl<T: Default> Default for Property<T> {
+    fn default() -> Self {
+        Self::Value(T::default())
+    }
+}
+
+impl<T> From<T> for Property<T> {
+    fn from(value: T) -> Self {
+        Self::Value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse::Time, Point};
+
+    use super::{Keyframe, KeyframeInterpolationType, Property};
+
+    fn new_keyframe<T>(
+        (start_value, end_value): (T, T),
+        (start_time, end_time): (u64, u64),
+        interpolation_type: KeyframeInterpolationType,
+        bezier: &[(Point, Point)],
+    ) -> Keyframe<T> {
+        Keyframe {
+            start_value,
+            end_value,
+            start_time: Time::from(start_time),
+            end_time: Time::from(end_time),
+            interpolation_type,
+            bezier_out: bezier.iter().map(|(out, _)| *out).collect(),
+            bezier_in: bezier.iter().map(|(_, r#in)| *r#in).collect(),
+            spatial_out: Point::zero(),
+            spatial_in: Point::zero(),
+        }
+    }
+
+    #[test]
+    fn test_property_value_at() {
+        let property = Property::Animated(vec![
+            new_keyframe((0., 10.), (10, 20), KeyframeInterpolationType::Linear, &[]),
+            new_keyframe((10., 30.), (20, 30), KeyframeInterpolationType::Hold, &[]),
+        ]);
+        assert_eq!(property.value_at(0), Some(0.));
+        assert_eq!(property.value_at(15), Some(5.));
+        assert_eq!(property.value_at(20), Some(10.));
+        assert_eq!(property.value_at(29), Some(10.));
+        assert_eq!(property.value_at(30), Some(30.));
+        assert_eq!(property.value_at(100), Some(30.));
+    }
+
+    #[test]
+    fn test_empty_keyframes() {
+        let property = Property::<f32>::Animated(vec![]);
+        assert!(!property.is_animated());
+        assert_eq!(property.value(), None);
+        assert_eq!(property.value_at(0), None);
+        assert_eq!(property.into_value(), None);
+    }
+
+    #[test]
+    fn test_multi_dimension_value_at() {
+        // x 维度为线性缓动，y 维度为先慢后快的缓动
+        let ease_in = (Point::new(1., 0.), Point::new(1., 0.));
+        let linear = (Point::new(0.5, 0.5), Point::new(0.5, 0.5));
+        let property = Property::Animated(vec![new_keyframe(
+            (Point::zero(), Point::new(100., 100.)),
+            (0, 10),
+            KeyframeInterpolationType::Bezier,
+            &[linear, ease_in],
+        )]);
+        let value = property.value_at(5).unwrap();
+        assert!((value.x - 50.).abs() < 1e-3);
+        assert!(value.y < 20.);
+    }
+}
@@ -1,25 +1,38 @@
 use crate::{
+    encode::{AttributeBlockWriter, Encodable, Writer},
     parse::{
-        EncodedInt32, EncodedUint32, EncodedUint64, Parsable, ParseContext, ParseError, Parser,
+        AttributeConfig, EncodedInt32, EncodedUint32, EncodedUint64, Parsable, ParseContext,
+        ParseError, Parser,
     },
     visit::{LayerInfo, Traversable},
 };
 
 use super::{
-    BlendMode, Color, CompositeOrder, FillRule, GradientFillType, LineCap, LineJoin,
-    MergePathsMode, Path, Point, TagBlock, TrimPathsType,
+    BlendMode, Color, CompositeOrder, FillRule, GradientColor, GradientFillType, LineCap, LineJoin,
+    MergePathsMode, Path, Point, PolyStarType, Property, TagBlock, TrimPathsType,
 };
 
 /// VectorCompositionBlock 是⽮量图形的合集。⾥⾯可以包含简单的⽮量图形，也可以再包含⼀个或是多个 VectorComposition。
 #[derive(Debug)]
-pub struct VectorCompositionBlock {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VectorCompositionBlock<'a> {
     pub id: EncodedUint32,
-    pub tag_block: TagBlock,
+    pub tag_block: TagBlock<'a>,
 }
 
-impl Parsable for VectorCompositionBlock {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_id()?;
+impl VectorCompositionBlock<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> VectorCompositionBlock<'static> {
+        VectorCompositionBlock {
+            id: self.id,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for VectorCompositionBlock<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_id().map_err(|e| e.with_field("id"))?;
         let tag_block = TagBlock::parse(parser, ctx)?;
         let result = Self { id, tag_block };
         log::debug!("parse_VectorCompositionBlock => {:?}", result);
@@ -27,7 +40,14 @@ impl Parsable for VectorCompositionBlock {
     }
 }
 
-impl Traversable for VectorCompositionBlock {
+impl Encodable for VectorCompositionBlock<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_id(self.id);
+        self.tag_block.encode(writer, ctx);
+    }
+}
+
+impl Traversable for VectorCompositionBlock<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo) + Clone,
@@ -38,6 +58,7 @@ impl Traversable for VectorCompositionBlock {
 
 /// CompositionAttribute 存储了 Composition 基本属性信息。⾥⾯可以包含简单的⽮量图形，也可以再包含⼀个或是多个 VectorComposition。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompositionAttributes {
     pub width: EncodedInt32,
     pub height: EncodedInt32,
@@ -46,8 +67,8 @@ pub struct CompositionAttributes {
     pub background_color: Color,
 }
 
-impl Parsable for CompositionAttributes {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for CompositionAttributes {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let width = parser.next_encoded_i32()?;
         let height = parser.next_encoded_i32()?;
         let duration = parser.next_encoded_u64()?;
@@ -67,137 +88,924 @@ impl Parsable for CompositionAttributes {
     }
 }
 
-/// ShapeGroup 投影标签。
+impl Encodable for CompositionAttributes {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_i32(self.width);
+        writer.write_encoded_i32(self.height);
+        writer.write_encoded_u64(self.duration);
+        writer.write_f32(self.frame_rate);
+        self.background_color.encode(writer, ctx);
+    }
+}
+
+/// ShapeGroup 标签，包含一组矢量图形及其变换信息。
 #[derive(Debug)]
-pub struct ShapeGroup {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeGroup<'a> {
     pub blend_mode: BlendMode,
-    pub anchor_point: Point,
-    pub position: Point,
-    pub scale: Point,
-    pub skew: f32,
-    pub skew_axis: f32,
-    pub rotation: f32,
-    pub opacity: u8,
-    pub tag_block: TagBlock,
+    pub anchor_point: Property<Point>,
+    pub position: Property<Point>,
+    pub scale: Property<Point>,
+    pub skew: Property<f32>,
+    pub skew_axis: Property<f32>,
+    pub rotation: Property<f32>,
+    pub opacity: Property<u8>,
+    pub tag_block: TagBlock<'a>,
+}
+
+impl ShapeGroup<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> ShapeGroup<'static> {
+        ShapeGroup {
+            blend_mode: self.blend_mode,
+            anchor_point: self.anchor_point,
+            position: self.position,
+            scale: self.scale,
+            skew: self.skew,
+            skew_axis: self.skew_axis,
+            rotation: self.rotation,
+            opacity: self.opacity,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for ShapeGroup<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let blend_mode = block.flag(AttributeConfig::Value(BlendMode::Normal));
+        let anchor_point = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let position = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let scale = block.flag(AttributeConfig::MultiDimensionProperty(Point::one()));
+        let skew = block.flag(AttributeConfig::SimpleProperty(0.));
+        let skew_axis = block.flag(AttributeConfig::SimpleProperty(0.));
+        let rotation = block.flag(AttributeConfig::SimpleProperty(0.));
+        let opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+
+        let blend_mode = block
+            .read("blend_mode", blend_mode)?
+            .unwrap_or(BlendMode::Normal);
+        let anchor_point = block
+            .read_property("anchor_point", anchor_point)?
+            .unwrap_or(Property::Value(Point::zero()));
+        let position = block
+            .read_property("position", position)?
+            .unwrap_or(Property::Value(Point::zero()));
+        let scale = block
+            .read_property("scale", scale)?
+            .unwrap_or(Property::Value(Point::one()));
+        let skew = block
+            .read_property("skew", skew)?
+            .unwrap_or(Property::Value(0.));
+        let skew_axis = block
+            .read_property("skew_axis", skew_axis)?
+            .unwrap_or(Property::Value(0.));
+        let rotation = block
+            .read_property("rotation", rotation)?
+            .unwrap_or(Property::Value(0.));
+        let opacity = block
+            .read_property("opacity", opacity)?
+            .unwrap_or(Property::Value(0xff));
+
+        // 属性块之后紧跟子标签列表
+        let length = block.byte_length()?;
+        parser.advance(length);
+        let tag_block = TagBlock::parse(parser, ctx)?;
+
+        let result = Self {
+            blend_mode,
+            anchor_point,
+            position,
+            scale,
+            skew,
+            skew_axis,
+            rotation,
+            opacity,
+            tag_block,
+        };
+
+        log::debug!("parse_ShapeGroup => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for ShapeGroup<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.anchor_point,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.position,
+        );
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::one()),
+            &self.scale,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.skew);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.skew_axis);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.rotation);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.finish(writer);
+
+        self.tag_block.encode(writer, ctx);
+    }
 }
 
-impl Traversable for ShapeGroup {
+impl Traversable for ShapeGroup<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
-        F: Fn(&dyn LayerInfo),
+        F: Fn(&dyn LayerInfo) + Clone,
     {
-        // self.tag_block.traverse_layer(visitor)
+        self.tag_block.traverse_layer(visitor)
     }
 }
 
 /// 矩形标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rectangle {
     pub reversed: bool,
-    pub size: Point,
-    pub position: Point,
-    pub roundness: f32,
+    pub size: Property<Point>,
+    pub position: Property<Point>,
+    pub roundness: Property<f32>,
+}
+
+impl<'a> Parsable<'a> for Rectangle {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let reversed = block.flag(AttributeConfig::BitFlag(false));
+        let size = block.flag(AttributeConfig::MultiDimensionProperty(Point::new(
+            100., 100.,
+        )));
+        let position = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let roundness = block.flag(AttributeConfig::SimpleProperty(0.));
+
+        let result = Self {
+            reversed: block.read("reversed", reversed)?.unwrap_or(false),
+            size: block
+                .read_property("size", size)?
+                .unwrap_or(Property::Value(Point::new(100., 100.))),
+            position: block
+                .read_property("position", position)?
+                .unwrap_or(Property::Value(Point::zero())),
+            roundness: block
+                .read_property("roundness", roundness)?
+                .unwrap_or(Property::Value(0.)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_Rectangle => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for Rectangle {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::BitFlag(false), &self.reversed);
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::new(100., 100.)),
+            &self.size,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.position,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.roundness);
+        block.finish(writer);
+    }
 }
 
 /// Ellipse 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ellipse {
     pub reversed: bool,
-    pub size: Point,
-    pub position: Point,
+    pub size: Property<Point>,
+    pub position: Property<Point>,
+}
+
+impl<'a> Parsable<'a> for Ellipse {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let reversed = block.flag(AttributeConfig::BitFlag(false));
+        let size = block.flag(AttributeConfig::MultiDimensionProperty(Point::new(
+            100., 100.,
+        )));
+        let position = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+
+        let result = Self {
+            reversed: block.read("reversed", reversed)?.unwrap_or(false),
+            size: block
+                .read_property("size", size)?
+                .unwrap_or(Property::Value(Point::new(100., 100.))),
+            position: block
+                .read_property("position", position)?
+                .unwrap_or(Property::Value(Point::zero())),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_Ellipse => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for Ellipse {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::BitFlag(false), &self.reversed);
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::new(100., 100.)),
+            &self.size,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.position,
+        );
+        block.finish(writer);
+    }
 }
 
 /// 多边星形标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PolyStar {
     pub reversed: bool,
-    pub poly_type: u8,
-    pub points: f32,
-    pub position: Point,
-    pub rotate: f32,
-    pub inner_radius: f32,
-    pub outer_radius: f32,
-    pub inner_roundness: f32,
-    pub outer_roundness: f32,
+    pub poly_type: PolyStarType,
+    pub points: Property<f32>,
+    pub position: Property<Point>,
+    pub rotation: Property<f32>,
+    pub inner_radius: Property<f32>,
+    pub outer_radius: Property<f32>,
+    pub inner_roundness: Property<f32>,
+    pub outer_roundness: Property<f32>,
+}
+
+impl<'a> Parsable<'a> for PolyStar {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let reversed = block.flag(AttributeConfig::BitFlag(false));
+        let poly_type = block.flag(AttributeConfig::Value(PolyStarType::Star));
+        let points = block.flag(AttributeConfig::SimpleProperty(5.));
+        let position = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let rotation = block.flag(AttributeConfig::SimpleProperty(0.));
+        let inner_radius = block.flag(AttributeConfig::SimpleProperty(50.));
+        let outer_radius = block.flag(AttributeConfig::SimpleProperty(100.));
+        let inner_roundness = block.flag(AttributeConfig::SimpleProperty(0.));
+        let outer_roundness = block.flag(AttributeConfig::SimpleProperty(0.));
+
+        let result = Self {
+            reversed: block.read("reversed", reversed)?.unwrap_or(false),
+            poly_type: block
+                .read("poly_type", poly_type)?
+                .unwrap_or(PolyStarType::Star),
+            points: block
+                .read_property("points", points)?
+                .unwrap_or(Property::Value(5.)),
+            position: block
+                .read_property("position", position)?
+                .unwrap_or(Property::Value(Point::zero())),
+            rotation: block
+                .read_property("rotation", rotation)?
+                .unwrap_or(Property::Value(0.)),
+            inner_radius: block
+                .read_property("inner_radius", inner_radius)?
+                .unwrap_or(Property::Value(50.)),
+            outer_radius: block
+                .read_property("outer_radius", outer_radius)?
+                .unwrap_or(Property::Value(100.)),
+            inner_roundness: block
+                .read_property("inner_roundness", inner_roundness)?
+                .unwrap_or(Property::Value(0.)),
+            outer_roundness: block
+                .read_property("outer_roundness", outer_roundness)?
+                .unwrap_or(Property::Value(0.)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_PolyStar => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for PolyStar {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::BitFlag(false), &self.reversed);
+        block.write(AttributeConfig::Value(PolyStarType::Star), &self.poly_type);
+        block.write_property(AttributeConfig::SimpleProperty(5.), &self.points);
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.position,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.rotation);
+        block.write_property(AttributeConfig::SimpleProperty(50.), &self.inner_radius);
+        block.write_property(AttributeConfig::SimpleProperty(100.), &self.outer_radius);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.inner_roundness);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.outer_roundness);
+        block.finish(writer);
+    }
 }
 
 /// ShapePath 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapePath {
-    pub shape_path: Path,
+    pub shape_path: Property<Path>,
+}
+
+impl<'a> Parsable<'a> for ShapePath {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let shape_path = block.flag(AttributeConfig::SimpleProperty(Path::new()));
+
+        let result = Self {
+            shape_path: block
+                .read_property("shape_path", shape_path)?
+                .unwrap_or(Property::Value(Path::new())),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_ShapePath => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for ShapePath {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_property(
+            AttributeConfig::SimpleProperty(Path::new()),
+            &self.shape_path,
+        );
+        block.finish(writer);
+    }
 }
 
 /// Fill 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fill {
     pub blend_mode: BlendMode,
     pub composite: CompositeOrder,
     pub fill_rule: FillRule,
-    pub color: Color,
-    pub opacity: u8,
+    pub color: Property<Color>,
+    pub opacity: Property<u8>,
+}
+
+impl<'a> Parsable<'a> for Fill {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let blend_mode = block.flag(AttributeConfig::Value(BlendMode::Normal));
+        let composite = block.flag(AttributeConfig::Value(
+            CompositeOrder::BelowPreviousInSameGroup,
+        ));
+        let fill_rule = block.flag(AttributeConfig::Value(FillRule::NonZeroWinding));
+        let color = block.flag(AttributeConfig::SimpleProperty(Color::RED));
+        let opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+
+        let result = Self {
+            blend_mode: block
+                .read("blend_mode", blend_mode)?
+                .unwrap_or(BlendMode::Normal),
+            composite: block
+                .read("composite", composite)?
+                .unwrap_or(CompositeOrder::BelowPreviousInSameGroup),
+            fill_rule: block
+                .read("fill_rule", fill_rule)?
+                .unwrap_or(FillRule::NonZeroWinding),
+            color: block
+                .read_property("color", color)?
+                .unwrap_or(Property::Value(Color::RED)),
+            opacity: block
+                .read_property("opacity", opacity)?
+                .unwrap_or(Property::Value(0xff)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_Fill => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for Fill {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+        block.write(
+            AttributeConfig::Value(CompositeOrder::BelowPreviousInSameGroup),
+            &self.composite,
+        );
+        block.write(
+            AttributeConfig::Value(FillRule::NonZeroWinding),
+            &self.fill_rule,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(Color::RED), &self.color);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.finish(writer);
+    }
 }
 
 /// Stroke 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stroke {
     pub blend_mode: BlendMode,
+    pub composite: CompositeOrder,
     pub line_cap: LineCap,
     pub line_join: LineJoin,
-    pub miter_limit: f32,
-    pub color: Color,
-    pub opacity: u8,
-    pub stroke_width: f32,
+    pub miter_limit: Property<f32>,
+    pub color: Property<Color>,
+    pub opacity: Property<u8>,
+    pub stroke_width: Property<f32>,
+    pub dashes: StrokeDashes,
+}
+
+impl<'a> Parsable<'a> for Stroke {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let blend_mode = block.flag(AttributeConfig::Value(BlendMode::Normal));
+        let composite = block.flag(AttributeConfig::Value(
+            CompositeOrder::BelowPreviousInSameGroup,
+        ));
+        let line_cap = block.flag(AttributeConfig::Value(LineCap::Butt));
+        let line_join = block.flag(AttributeConfig::Value(LineJoin::Miter));
+        let miter_limit = block.flag(AttributeConfig::SimpleProperty(4.));
+        let color = block.flag(AttributeConfig::SimpleProperty(Color::WHITE));
+        let opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+        let stroke_width = block.flag(AttributeConfig::SimpleProperty(2.));
+        let dashes = block.flag(AttributeConfig::Custom(StrokeDashes::default()));
+
+        let result = Self {
+            blend_mode: block
+                .read("blend_mode", blend_mode)?
+                .unwrap_or(BlendMode::Normal),
+            composite: block
+                .read("composite", composite)?
+                .unwrap_or(CompositeOrder::BelowPreviousInSameGroup),
+            line_cap: block.read("line_cap", line_cap)?.unwrap_or(LineCap::Butt),
+            line_join: block
+                .read("line_join", line_join)?
+                .unwrap_or(LineJoin::Miter),
+            miter_limit: block
+                .read_property("miter_limit", miter_limit)?
+                .unwrap_or(Property::Value(4.)),
+            color: block
+                .read_property("color", color)?
+                .unwrap_or(Property::Value(Color::WHITE)),
+            opacity: block
+                .read_property("opacity", opacity)?
+                .unwrap_or(Property::Value(0xff)),
+            stroke_width: block
+                .read_property("stroke_width", stroke_width)?
+                .unwrap_or(Property::Value(2.)),
+            dashes: block.read("dashes", dashes)?.unwrap_or_default(),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_Stroke => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for Stroke {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+        block.write(
+            AttributeConfig::Value(CompositeOrder::BelowPreviousInSameGroup),
+            &self.composite,
+        );
+        block.write(AttributeConfig::Value(LineCap::Butt), &self.line_cap);
+        block.write(AttributeConfig::Value(LineJoin::Miter), &self.line_join);
+        block.write_property(AttributeConfig::SimpleProperty(4.), &self.miter_limit);
+        block.write_property(AttributeConfig::SimpleProperty(Color::WHITE), &self.color);
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.write_property(AttributeConfig::SimpleProperty(2.), &self.stroke_width);
+        block.write(
+            AttributeConfig::Custom(StrokeDashes::default()),
+            &self.dashes,
+        );
+        block.finish(writer);
+    }
+}
+
+/// 虚线参数，作为 Stroke / GradientStroke 的自定义属性存储。
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrokeDashes {
+    pub dash_offset: Property<f32>,
+    pub dashes: Vec<Property<f32>>,
+}
+
+impl<'a> Parsable<'a> for StrokeDashes {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let dash_length = block.next_ubits(3) as usize + 1;
+        let dash_offset = block.flag(AttributeConfig::SimpleProperty(0.));
+        let dashes = (0..dash_length)
+            .map(|_| block.flag(AttributeConfig::SimpleProperty(0.)))
+            .collect::<Vec<_>>();
+
+        let dash_offset = block
+            .read_property("dash_offset", dash_offset)?
+            .unwrap_or(Property::Value(0.));
+        let dashes = dashes
+            .into_iter()
+            .map(|dash| {
+                let dash = block.read_property("dashes", dash)?;
+                Ok(dash.unwrap_or(Property::Value(0.)))
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        let length = block.byte_length()?;
+        parser.advance(length);
+
+        let result = Self {
+            dash_offset,
+            dashes,
+        };
+        log::debug!("parse_StrokeDashes => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for StrokeDashes {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_ubits(self.dashes.len().max(1) as u32 - 1, 3);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.dash_offset);
+        for dash in &self.dashes {
+            block.write_property(AttributeConfig::SimpleProperty(0.), dash);
+        }
+        block.finish(writer);
+    }
 }
 
 /// GradientFill 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GradientFill {
     pub blend_mode: BlendMode,
     pub composite: CompositeOrder,
     pub fill_rule: FillRule,
     pub fill_type: GradientFillType,
-    pub start_point: Point,
-    pub end_point: Point,
-    pub colors: Vec<Color>,
-    pub opacity: u8,
+    pub start_point: Property<Point>,
+    pub end_point: Property<Point>,
+    pub colors: Property<GradientColor>,
+    pub opacity: Property<u8>,
+}
+
+impl<'a> Parsable<'a> for GradientFill {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let blend_mode = block.flag(AttributeConfig::Value(BlendMode::Normal));
+        let composite = block.flag(AttributeConfig::Value(
+            CompositeOrder::BelowPreviousInSameGroup,
+        ));
+        let fill_rule = block.flag(AttributeConfig::Value(FillRule::NonZeroWinding));
+        let fill_type = block.flag(AttributeConfig::Value(GradientFillType::Linear));
+        let start_point = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let end_point = block.flag(AttributeConfig::SpatialProperty(Point::new(100., 0.)));
+        let colors = block.flag(AttributeConfig::SimpleProperty(GradientColor::default()));
+        let opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+
+        let result = Self {
+            blend_mode: block
+                .read("blend_mode", blend_mode)?
+                .unwrap_or(BlendMode::Normal),
+            composite: block
+                .read("composite", composite)?
+                .unwrap_or(CompositeOrder::BelowPreviousInSameGroup),
+            fill_rule: block
+                .read("fill_rule", fill_rule)?
+                .unwrap_or(FillRule::NonZeroWinding),
+            fill_type: block
+                .read("fill_type", fill_type)?
+                .unwrap_or(GradientFillType::Linear),
+            start_point: block
+                .read_property("start_point", start_point)?
+                .unwrap_or(Property::Value(Point::zero())),
+            end_point: block
+                .read_property("end_point", end_point)?
+                .unwrap_or(Property::Value(Point::new(100., 0.))),
+            colors: block
+                .read_property("colors", colors)?
+                .unwrap_or_else(|| Property::Value(GradientColor::default())),
+            opacity: block
+                .read_property("opacity", opacity)?
+                .unwrap_or(Property::Value(0xff)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_GradientFill => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for GradientFill {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+        block.write(
+            AttributeConfig::Value(CompositeOrder::BelowPreviousInSameGroup),
+            &self.composite,
+        );
+        block.write(
+            AttributeConfig::Value(FillRule::NonZeroWinding),
+            &self.fill_rule,
+        );
+        block.write(
+            AttributeConfig::Value(GradientFillType::Linear),
+            &self.fill_type,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.start_point,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::new(100., 0.)),
+            &self.end_point,
+        );
+        block.write_property(
+            AttributeConfig::SimpleProperty(GradientColor::default()),
+            &self.colors,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.finish(writer);
+    }
 }
 
 /// GradientStroke 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GradientStroke {
     pub blend_mode: BlendMode,
     pub composite: CompositeOrder,
     pub fill_type: GradientFillType,
-    pub start_point: Point,
-    pub end_point: Point,
-    pub color: Color,
-    pub opacity: u8,
-    pub stroke_width: f32,
+    pub start_point: Property<Point>,
+    pub end_point: Property<Point>,
+    pub colors: Property<GradientColor>,
+    pub opacity: Property<u8>,
+    pub stroke_width: Property<f32>,
     pub line_cap: LineCap,
     pub line_join: LineJoin,
-    pub miter_limit: f32,
-    pub dash_length: [u32; 3],
-    pub dash_offset_flag_exist: [bool; 1],
-    pub dash_offset_flag_animatable: [bool; 1],
+    pub miter_limit: Property<f32>,
+    pub dashes: StrokeDashes,
+}
+
+impl<'a> Parsable<'a> for GradientStroke {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let blend_mode = block.flag(AttributeConfig::Value(BlendMode::Normal));
+        let composite = block.flag(AttributeConfig::Value(
+            CompositeOrder::BelowPreviousInSameGroup,
+        ));
+        let fill_type = block.flag(AttributeConfig::Value(GradientFillType::Linear));
+        let start_point = block.flag(AttributeConfig::SpatialProperty(Point::zero()));
+        let end_point = block.flag(AttributeConfig::SpatialProperty(Point::new(100., 0.)));
+        let colors = block.flag(AttributeConfig::SimpleProperty(GradientColor::default()));
+        let opacity = block.flag(AttributeConfig::SimpleProperty(0xff));
+        let stroke_width = block.flag(AttributeConfig::SimpleProperty(2.));
+        let line_cap = block.flag(AttributeConfig::Value(LineCap::Butt));
+        let line_join = block.flag(AttributeConfig::Value(LineJoin::Miter));
+        let miter_limit = block.flag(AttributeConfig::SimpleProperty(4.));
+        let dashes = block.flag(AttributeConfig::Custom(StrokeDashes::default()));
+
+        let result = Self {
+            blend_mode: block
+                .read("blend_mode", blend_mode)?
+                .unwrap_or(BlendMode::Normal),
+            composite: block
+                .read("composite", composite)?
+                .unwrap_or(CompositeOrder::BelowPreviousInSameGroup),
+            fill_type: block
+                .read("fill_type", fill_type)?
+                .unwrap_or(GradientFillType::Linear),
+            start_point: block
+                .read_property("start_point", start_point)?
+                .unwrap_or(Property::Value(Point::zero())),
+            end_point: block
+                .read_property("end_point", end_point)?
+                .unwrap_or(Property::Value(Point::new(100., 0.))),
+            colors: block
+                .read_property("colors", colors)?
+                .unwrap_or_else(|| Property::Value(GradientColor::default())),
+            opacity: block
+                .read_property("opacity", opacity)?
+                .unwrap_or(Property::Value(0xff)),
+            stroke_width: block
+                .read_property("stroke_width", stroke_width)?
+                .unwrap_or(Property::Value(2.)),
+            line_cap: block.read("line_cap", line_cap)?.unwrap_or(LineCap::Butt),
+            line_join: block
+                .read("line_join", line_join)?
+                .unwrap_or(LineJoin::Miter),
+            miter_limit: block
+                .read_property("miter_limit", miter_limit)?
+                .unwrap_or(Property::Value(4.)),
+            dashes: block.read("dashes", dashes)?.unwrap_or_default(),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_GradientStroke => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for GradientStroke {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(BlendMode::Normal), &self.blend_mode);
+        block.write(
+            AttributeConfig::Value(CompositeOrder::BelowPreviousInSameGroup),
+            &self.composite,
+        );
+        block.write(
+            AttributeConfig::Value(GradientFillType::Linear),
+            &self.fill_type,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::zero()),
+            &self.start_point,
+        );
+        block.write_property(
+            AttributeConfig::SpatialProperty(Point::new(100., 0.)),
+            &self.end_point,
+        );
+        block.write_property(
+            AttributeConfig::SimpleProperty(GradientColor::default()),
+            &self.colors,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0xff), &self.opacity);
+        block.write_property(AttributeConfig::SimpleProperty(2.), &self.stroke_width);
+        block.write(AttributeConfig::Value(LineCap::Butt), &self.line_cap);
+        block.write(AttributeConfig::Value(LineJoin::Miter), &self.line_join);
+        block.write_property(AttributeConfig::SimpleProperty(4.), &self.miter_limit);
+        block.write(
+            AttributeConfig::Custom(StrokeDashes::default()),
+            &self.dashes,
+        );
+        block.finish(writer);
+    }
 }
 
 /// MergePaths 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MergePaths {
     pub mode: MergePathsMode,
 }
 
+impl<'a> Parsable<'a> for MergePaths {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let mode = block.flag(AttributeConfig::Value(MergePathsMode::Add));
+
+        let result = Self {
+            mode: block.read("mode", mode)?.unwrap_or(MergePathsMode::Add),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_MergePaths => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for MergePaths {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(MergePathsMode::Add), &self.mode);
+        block.finish(writer);
+    }
+}
+
 /// TrimPaths 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrimPaths {
-    pub start: f32,
-    pub end: f32,
-    pub offset: f32,
+    pub start: Property<f32>,
+    pub end: Property<f32>,
+    pub offset: Property<f32>,
     pub trim_type: TrimPathsType,
 }
 
+impl<'a> Parsable<'a> for TrimPaths {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let start = block.flag(AttributeConfig::SimpleProperty(0.));
+        let end = block.flag(AttributeConfig::SimpleProperty(100.));
+        let offset = block.flag(AttributeConfig::SimpleProperty(0.));
+        let trim_type = block.flag(AttributeConfig::Value(TrimPathsType::Simultaneously));
+
+        let result = Self {
+            start: block
+                .read_property("start", start)?
+                .unwrap_or(Property::Value(0.)),
+            end: block
+                .read_property("end", end)?
+                .unwrap_or(Property::Value(100.)),
+            offset: block
+                .read_property("offset", offset)?
+                .unwrap_or(Property::Value(0.)),
+            trim_type: block
+                .read("trim_type", trim_type)?
+                .unwrap_or(TrimPathsType::Simultaneously),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_TrimPaths => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for TrimPaths {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.start);
+        block.write_property(AttributeConfig::SimpleProperty(100.), &self.end);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.offset);
+        block.write(
+            AttributeConfig::Value(TrimPathsType::Simultaneously),
+            &self.trim_type,
+        );
+        block.finish(writer);
+    }
+}
+
 /// RoundCorners 标签。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RoundCorners {
-    pub radius: f32,
+    pub radius: Property<f32>,
+}
+
+impl<'a> Parsable<'a> for RoundCorners {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut block = parser.new_attribute_block();
+        let radius = block.flag(AttributeConfig::SimpleProperty(10.));
+
+        let result = Self {
+            radius: block
+                .read_property("radius", radius)?
+                .unwrap_or(Property::Value(10.)),
+        };
+
+        parser.advance(block.byte_length()?);
+        log::debug!("parse_RoundCorners => {:?}", result);
+        Ok(result)
+    }
+}
+
+impl Encodable for RoundCorners {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_property(AttributeConfig::SimpleProperty(10.), &self.radius);
+        block.finish(writer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::{Point, TagBody},
+        parse::{Parsable, Parser, StreamParser},
+    };
+
+    use super::ShapeGroup;
+
+    #[test]
+    fn test_parse_shape_group() {
+        let mut input = vec![
+            // ShapeGroup 属性全部使用默认值
+            0b0000_0000,
+            // Rectangle 标签头：code = 16, length = 9
+            0x09,
+            0x04,
+            // reversed = true, size 存在且不含关键帧
+            0b0000_0011,
+        ];
+        input.extend_from_slice(&50f32.to_le_bytes());
+        input.extend_from_slice(&20f32.to_le_bytes());
+        // End 标签
+        input.extend_from_slice(&[0x00, 0x00]);
+
+        let mut parser = StreamParser::new(&input);
+        let group = ShapeGroup::parse(&mut parser, ()).unwrap();
+        assert!(parser.is_empty());
+        assert_eq!(group.opacity.value().copied(), Some(0xff));
+        assert_eq!(group.tag_block.tags.len(), 1);
+
+        let TagBody::Rectangle(rectangle) = &group.tag_block.tags[0].body else {
+            panic!("expect Rectangle, got {:?}", group.tag_block.tags[0].body);
+        };
+        assert!(rectangle.reversed);
+        assert_eq!(rectangle.size.value().copied(), Some(Point::new(50., 20.)));
+        assert_eq!(rectangle.position.value().copied(), Some(Point::zero()));
+        assert_eq!(rectangle.roundness.value().copied(), Some(0.));
+    }
 }
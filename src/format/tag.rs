@@ -1,12 +1,20 @@
+use std::collections::hash_map::DefaultHasher;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 use num_enum::FromPrimitive;
 use num_enum::IntoPrimitive;
 
+use crate::encode::Encodable;
+use crate::encode::StreamWriter;
+use crate::encode::Writer;
+use crate::parse::EncodedInt64;
 use crate::parse::Parsable;
 use crate::parse::ParseContext;
 use crate::parse::ParseError;
+use crate::parse::ParseErrorKind;
 use crate::parse::Parser;
+use crate::parse::StreamParser;
 use crate::visit::LayerInfo;
 use crate::visit::Traversable;
 
@@ -19,6 +27,7 @@ use super::*;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, IntoPrimitive, FromPrimitive)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TagCode {
     /// 结束标识
     End = 0,
@@ -195,25 +204,33 @@ pub enum TagCode {
 }
 
 #[derive(Debug, Default)]
-pub struct TagBlock {
-    pub tags: Vec<Tag>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagBlock<'a> {
+    pub tags: Vec<Tag<'a>>,
 }
 
-impl TagBlock {
-    pub fn push(&mut self, tag: Tag) {
+impl<'a> TagBlock<'a> {
+    pub fn push(&mut self, tag: Tag<'a>) {
         self.tags.push(tag);
     }
 
-    pub fn find<P>(&self, predicate: P) -> Option<&Tag>
+    pub fn find<P>(&self, predicate: P) -> Option<&Tag<'a>>
     where
-        P: FnMut(&&Tag) -> bool,
+        P: FnMut(&&Tag<'a>) -> bool,
     {
         self.tags.iter().find(predicate)
     }
+
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> TagBlock<'static> {
+        TagBlock {
+            tags: self.tags.into_iter().map(Tag::into_owned).collect(),
+        }
+    }
 }
 
-impl Parsable for TagBlock {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TagBlock<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = TagBlock { tags: vec![] };
         loop {
             let tag = Tag::parse(parser, ctx.clone())?;
@@ -227,7 +244,16 @@ impl Parsable for TagBlock {
     }
 }
 
-impl Traversable for TagBlock {
+impl Encodable for TagBlock<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        for tag in &self.tags {
+            tag.encode(writer, ctx.clone());
+        }
+        Tag::EMPTY.encode(writer, ctx);
+    }
+}
+
+impl Traversable for TagBlock<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo) + Clone,
@@ -239,27 +265,79 @@ impl Traversable for TagBlock {
 }
 
 #[derive(Debug)]
-pub struct Tag {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tag<'a> {
     pub header: TagHeader,
-    pub body: TagBody,
+    pub body: TagBody<'a>,
+    /// 无损模式解析时记录的原始内容
+    #[cfg_attr(feature = "serde", serde(skip))]
+    original: Option<OriginalBody<'a>>,
+}
+
+/// 标签内容的原始数据，以及解析结果的摘要
+///
+/// 变长整数的非最短编码、默认值属性的标志位、UB[nBits] 的位数等编码方式无法从解析结果中还原，
+/// 写出时若摘要仍然一致，说明内容未被修改，直接写出原始数据
+///
+/// 摘要由标签代码及内容按默认方式编码后的数据计算，其中的子标签以子标签的摘要代替，
+/// 因此每个标签的摘要只需自底向上计算一次
+#[derive(Debug)]
+struct OriginalBody<'a> {
+    bytes: ByteData<'a>,
+    digest: u64,
 }
 
-impl Tag {
-    pub const EMPTY: Tag = Self {
-        header: TagHeader {
-            code: TagCode::End,
-            length: 0,
-        },
-        body: TagBody::End,
-    };
+impl<'a> Tag<'a> {
+    /// 创建标签，TagHeader 中的长度在写出时重新计算
+    pub const fn new(code: TagCode, body: TagBody<'a>) -> Self {
+        Self {
+            header: TagHeader {
+                code,
+                length: 0,
+                is_long: false,
+            },
+            body,
+            original: None,
+        }
+    }
+
+    /// 标签的摘要，子标签使用解析时记录的摘要，只在解析时调用
+    fn digest(&self, ctx: impl ParseContext) -> u64 {
+        let mut writer = StreamWriter::for_digest();
+        self.body.encode(&mut writer, ctx);
+        Self::hash_digest(self.header.code, writer.buffer())
+    }
+
+    fn hash_digest(code: TagCode, input: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        u8::from(code).hash(&mut hasher);
+        input.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Tag<'_> {
+    pub const EMPTY: Tag<'static> = Tag::new(TagCode::End, TagBody::End);
 
     pub fn is_empty(&self) -> bool {
         self.header.code == TagCode::End
     }
+
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> Tag<'static> {
+        Tag {
+            header: self.header,
+            body: self.body.into_owned(),
+            original: self.original.map(|original| OriginalBody {
+                bytes: original.bytes.into_owned(),
+                digest: original.digest,
+            }),
+        }
+    }
 }
 
-impl Parsable for Tag {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for Tag<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         log::debug!(
             "parse_Tag <= {} bytes: {:?}",
             parser.remain(),
@@ -267,10 +345,57 @@ impl Parsable for Tag {
         );
 
         let header = TagHeader::parse(parser, ctx.clone())?;
+        let mut body = parser
+            .new_slice(header.length as usize)
+            .map_err(|e| e.with_tag(header.code))?;
+        let input = body.clone();
+
+        let body = match Self::parse_body(&mut body, &header, ctx.clone()) {
+            Ok(body) => body,
+            // 宽松模式下保留解析失败的标签内容，继续解析后续标签
+            Err(error) if ctx.options().lenient => {
+                let error = ctx
+                    .tag_stack()
+                    .iter()
+                    .rev()
+                    .fold(error.with_tag(header.code), |error, code| {
+                        error.with_tag(*code)
+                    });
+                log::warn!("parse_Tag => {}", error);
+                TagBody::Invalid {
+                    error,
+                    bytes: ByteData::from(input.buffer()),
+                }
+            }
+            Err(error) => return Err(error.with_tag(header.code)),
+        };
+
+        let mut tag = Self {
+            header,
+            body,
+            original: None,
+        };
+        if ctx.options().lossless {
+            let digest = tag.digest(ctx.with_tag_code(tag.header.code));
+            tag.original = Some(OriginalBody {
+                bytes: ByteData::from(input.buffer()),
+                digest,
+            });
+        }
+        Ok(tag)
+    }
+}
+
+impl<'a> Tag<'a> {
+    fn parse_body(
+        body: &mut StreamParser<'a>,
+        header: &TagHeader,
+        ctx: impl ParseContext,
+    ) -> Result<TagBody<'a>, ParseError> {
+        let options = ctx.options();
         let ctx = ctx.with_tag_code(header.code);
-        let body = &mut parser.new_slice(header.length as usize)?;
 
-        let body = match header.code {
+        let result = match header.code {
             TagCode::End => TagBody::End,
             TagCode::FontTables => TagBody::FontTables(FontTables::parse(body, ctx)?),
             TagCode::VectorCompositionBlock => {
@@ -286,6 +411,7 @@ impl Parsable for Tag {
             }
             TagCode::SolidColor => TagBody::SolidColor(SolidColor::parse(body, ctx)?),
             TagCode::TextSource => TagBody::TextSource(TextSource::parse(body, ctx)?),
+            #[allow(deprecated)]
             TagCode::DeprecatedTextPathOption => {
                 TagBody::TextPathOption(TextPathOption::parse(body, ctx)?)
             }
@@ -296,19 +422,19 @@ impl Parsable for Tag {
             }
             TagCode::Transform2D => TagBody::Transform2D(Transform2D::parse(body, ctx)?),
             TagCode::Mask => TagBody::Mask(Mask::parse(body, ctx)?),
-            // TagCode::ShapeGroup => TagBody::ShapeGroup(ShapeGroup::parse(body, ctx)?),
-            // TagCode::Rectangle => TagBody::Rectangle(Rectangle::parse(body, ctx)?),
-            // TagCode::Ellipse => TagBody::Ellipse(Ellipse::parse(body, ctx)?),
-            // TagCode::PolyStar => TagBody::PolyStar(PolyStar::parse(body, ctx)?),
-            // TagCode::ShapePath => TagBody::ShapePath(ShapePath::parse(body, ctx)?),
-            // TagCode::Fill => TagBody::Fill(Fill::parse(body, ctx)?),
-            // TagCode::Stroke => TagBody::Stroke(Stroke::parse(body, ctx)?),
-            // TagCode::GradientFill => TagBody::GradientFill(GradientFill::parse(body, ctx)?),
-            // TagCode::GradientStroke => TagBody::GradientStroke(GradientStroke::parse(body, ctx)?),
-            // TagCode::MergePaths => TagBody::MergePaths(MergePaths::parse(body, ctx)?),
-            // TagCode::TrimPaths => TagBody::TrimPaths(TrimPaths::parse(body, ctx)?),
-            // TagCode::Repeater => TagBody::Repeater(Repeater::parse(body, ctx)?),
-            // TagCode::RoundCorners => TagBody::RoundCorners(RoundCorners::parse(body, ctx)?),
+            TagCode::ShapeGroup => TagBody::ShapeGroup(ShapeGroup::parse(body, ctx)?),
+            TagCode::Rectangle => TagBody::Rectangle(Rectangle::parse(body, ctx)?),
+            TagCode::Ellipse => TagBody::Ellipse(Ellipse::parse(body, ctx)?),
+            TagCode::PolyStar => TagBody::PolyStar(PolyStar::parse(body, ctx)?),
+            TagCode::ShapePath => TagBody::ShapePath(ShapePath::parse(body, ctx)?),
+            TagCode::Fill => TagBody::Fill(Fill::parse(body, ctx)?),
+            TagCode::Stroke => TagBody::Stroke(Stroke::parse(body, ctx)?),
+            TagCode::GradientFill => TagBody::GradientFill(GradientFill::parse(body, ctx)?),
+            TagCode::GradientStroke => TagBody::GradientStroke(GradientStroke::parse(body, ctx)?),
+            TagCode::MergePaths => TagBody::MergePaths(MergePaths::parse(body, ctx)?),
+            TagCode::TrimPaths => TagBody::TrimPaths(TrimPaths::parse(body, ctx)?),
+            TagCode::Repeater => TagBody::Repeater(Repeater::parse(body, ctx)?),
+            TagCode::RoundCorners => TagBody::RoundCorners(RoundCorners::parse(body, ctx)?),
             // TagCode::Performance => TagBody::Performance(Performance::parse(body, ctx)?),
             // TagCode::DropShadowStyle => {
             //     TagBody::DropShadowStyle(DropShadowStyle::parse(body, ctx)?)
@@ -318,11 +444,11 @@ impl Parsable for Tag {
             // TagCode::TimeStretchMode => {
             //     TagBody::TimeStretchMode(TimeStretchMode::parse(body, ctx)?)
             // }
-            // TagCode::Mp4Header => TagBody::Mp4Header(Mp4Header::parse(body, ctx)?),
-            // TagCode::BitmapCompositionBlock => {
-            //     TagBody::BitmapCompositionBlock(BitmapCompositionBlock::parse(body, ctx)?)
-            // }
-            // TagCode::BitmapSequence => TagBody::BitmapSequence(BitmapSequence::parse(body, ctx)?),
+            TagCode::Mp4Header => TagBody::Mp4Header(ByteData::parse(body, ctx)?),
+            TagCode::BitmapCompositionBlock => {
+                TagBody::BitmapCompositionBlock(BitmapCompositionBlock::parse(body, ctx)?)
+            }
+            TagCode::BitmapSequence => TagBody::BitmapSequence(BitmapSequence::parse(body, ctx)?),
             TagCode::ImageBytes => TagBody::ImageBytes(ImageBytes::parse(body, ctx)?),
             TagCode::ImageBytes2 => TagBody::ImageBytes2(ImageBytes2::parse(body, ctx)?),
             TagCode::ImageBytes3 => TagBody::ImageBytes3(ImageBytes3::parse(body, ctx)?),
@@ -335,7 +461,7 @@ impl Parsable for Tag {
             }
             // TagCode::MarkerList => TagBody::MarkerList(MarkerList::parse_block(body, ctx)?),
             // TagCode::ImageFillRule => TagBody::ImageFillRule(ImageFillRule::parse_block(body, ctx)?),
-            // TagCode::AudioBytes => TagBody::AudioBytes(AudioBytes::parse_block(body, ctx)?),
+            TagCode::AudioBytes => TagBody::AudioBytes(ByteData::parse(body, ctx)?),
             // TagCode::MotionTileEffect => {
             //     TagBody::MotionTileEffect(MotionTileEffect::parse_block(body, ctx)?)
             // }
@@ -431,13 +557,62 @@ impl Parsable for Tag {
             // TagCode::ImageScaleModes => {
             //     TagBody::ImageScaleModes(ImageScaleModes::parse_block(body, ctx)?)
             // }
-            _ => TagBody::Raw(ByteData::from(body.buffer())),
+            _ => TagBody::Raw(ByteData::from(body.next_bytes(body.remain())?)),
         };
-        Ok(Self { header, body })
+
+        if !body.is_empty() {
+            let error = ParseError::from(ParseErrorKind::UnconsumedBytes {
+                code: header.code,
+                remain: body.remain(),
+            })
+            .with_offset(body.offset());
+            if options.strict {
+                return Err(error);
+            }
+            log::debug!("parse_Tag => {}", error);
+        }
+        Ok(result)
     }
 }
 
-impl Traversable for Tag {
+impl Encodable for Tag<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        let ctx = ctx.with_tag_code(self.header.code);
+        if writer.is_digest_only() {
+            // 解析时子标签未被修改，直接使用记录的摘要
+            let digest = match &self.original {
+                Some(original) => original.digest,
+                None => self.digest(ctx),
+            };
+            writer.write_bytes(&digest.to_le_bytes());
+            return;
+        }
+
+        let mut body = writer.new_writer();
+        self.body.encode(&mut body, ctx.clone());
+        let digest = body
+            .digest_input()
+            .map(|input| Self::hash_digest(self.header.code, input));
+        let content = match &self.original {
+            // 无损模式下，未修改的标签写出原始内容
+            Some(original) if digest == Some(original.digest) => &original.bytes.data,
+            _ => body.buffer(),
+        };
+
+        let header = TagHeader {
+            code: self.header.code,
+            length: content.len() as u32,
+            // 无损模式下保持原有的 TagHeader 格式
+            is_long: writer.is_lossless() && self.header.is_long,
+        };
+        let mut bytes = StreamWriter::new();
+        header.encode(&mut bytes, ctx);
+        bytes.write_bytes(content);
+        writer.write_tag_bytes(bytes.buffer(), digest);
+    }
+}
+
+impl Traversable for Tag<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo) + Clone,
@@ -462,27 +637,35 @@ impl Traversable for Tag {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TagEnd {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TagHeader {
     pub code: TagCode,
     pub length: u32,
+    /// 是否使用 long 型 TagHeader 存储，无损写出时保持原有格式
+    pub is_long: bool,
 }
 
-impl Parsable for TagHeader {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TagHeader {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         const MASK: u32 = 0b0011_1111;
 
-        let code_and_length = parser.next_u16()?;
+        let code_and_length = parser
+            .next_u16()
+            .map_err(|e| e.with_field("codeAndLength"))?;
         let code = (code_and_length >> 6) as u8;
         let mut length = code_and_length as u32 & MASK;
-        if length == MASK {
-            length = parser.next_u32()?;
+        let is_long = length == MASK;
+        if is_long {
+            length = parser.next_u32().map_err(|e| e.with_field("length"))?;
         }
         let result = Self {
             code: TagCode::from(code),
             length,
+            is_long,
         };
 
         log::debug!("parse_TagHeader => {:?}", result);
@@ -490,20 +673,35 @@ impl Parsable for TagHeader {
     }
 }
 
+impl Encodable for TagHeader {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        const MASK: u32 = 0b0011_1111;
+
+        let code = u8::from(self.code) as u16;
+        if self.length < MASK && !self.is_long {
+            writer.write_u16(code << 6 | self.length as u16);
+        } else {
+            writer.write_u16(code << 6 | MASK as u16);
+            writer.write_u32(self.length);
+        }
+    }
+}
+
 #[derive(Debug)]
-pub enum TagBody {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TagBody<'a> {
     /// 结束标识
     End,
     /// 字体集合，包含多个字体
     FontTables(FontTables),
     /// 矢量组合信息
-    VectorCompositionBlock(VectorCompositionBlock),
+    VectorCompositionBlock(VectorCompositionBlock<'a>),
     /// 组合基本属性信息
     CompositionAttributes(CompositionAttributes),
     /// 图片合集信息
-    ImageTables(ImageTables),
+    ImageTables(ImageTables<'a>),
     /// 图层信息
-    LayerBlock(LayerBlock),
+    LayerBlock(LayerBlock<'a>),
     /// 图层基本属性信息
     LayerAttributes(LayerAttributes),
     /// 边框颜色
@@ -523,7 +721,7 @@ pub enum TagBody {
     /// 遮罩信息
     Mask(Mask),
     /// Shape 信息
-    ShapeGroup(ShapeGroup),
+    ShapeGroup(ShapeGroup<'a>),
     /// 矩形信息
     Rectangle(Rectangle),
     /// 椭圆信息
@@ -553,36 +751,181 @@ pub enum TagBody {
     /// 投影
     DropShadowStyle(DropShadowStyle),
     /// 位图序列帧
-    BitmapCompositionBlock(BitmapCompositionBlock),
+    BitmapCompositionBlock(BitmapCompositionBlock<'a>),
     /// 位图序列
-    BitmapSequence(BitmapSequence),
+    BitmapSequence(BitmapSequence<'a>),
     /// 文件属性
     FileAttributes(FileAttributes),
     /// 图片字节流
-    ImageBytes(ImageBytes),
+    ImageBytes(ImageBytes<'a>),
     /// 图片字节流（带缩放）
-    ImageBytes2(ImageBytes2),
+    ImageBytes2(ImageBytes2<'a>),
     /// 图片字节流（带透明通道）
-    ImageBytes3(ImageBytes3),
+    ImageBytes3(ImageBytes3<'a>),
     /// 视频序列帧
-    VideoCompositionBlock(VideoCompositionBlock),
+    VideoCompositionBlock(VideoCompositionBlock<'a>),
     /// 视频序列
-    VideoSequence(VideoSequence),
+    VideoSequence(VideoSequence<'a>),
     /// 图层基本属性信息 V2
     LayerAttributesV2(LayerAttributes),
     /// 图层基本属性信息 V3
     LayerAttributesV3(LayerAttributes),
     /// 图层基本属性信息（额外）
     LayerAttributesExtra(LayerAttributesExtra),
+    /// 组合的音频，存储为 MP4 文件
+    AudioBytes(ByteData<'a>),
+    /// 视频序列的 MP4 文件头
+    Mp4Header(ByteData<'a>),
     /// 未知
-    Raw(ByteData),
+    Raw(ByteData<'a>),
+    /// 宽松模式下解析失败的标签，保留错误信息及原始内容
+    Invalid {
+        error: ParseError,
+        bytes: ByteData<'a>,
+    },
+}
+
+impl<'a> TagBody<'a> {
+    /// 标签内嵌套的子标签列表
+    pub fn tag_block(&self) -> Option<&TagBlock<'a>> {
+        match self {
+            TagBody::VectorCompositionBlock(block) => Some(&block.tag_block),
+            TagBody::LayerBlock(block) => Some(&block.tag_block),
+            TagBody::ShapeGroup(block) => Some(&block.tag_block),
+            TagBody::BitmapCompositionBlock(block) => Some(&block.tag_block),
+            TagBody::VideoCompositionBlock(block) => Some(&block.tag_block),
+            _ => None,
+        }
+    }
+
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> TagBody<'static> {
+        match self {
+            TagBody::End => TagBody::End,
+            TagBody::VectorCompositionBlock(body) => {
+                TagBody::VectorCompositionBlock(body.into_owned())
+            }
+            TagBody::ImageTables(body) => TagBody::ImageTables(body.into_owned()),
+            TagBody::LayerBlock(body) => TagBody::LayerBlock(body.into_owned()),
+            TagBody::ShapeGroup(body) => TagBody::ShapeGroup(body.into_owned()),
+            TagBody::BitmapCompositionBlock(body) => {
+                TagBody::BitmapCompositionBlock(body.into_owned())
+            }
+            TagBody::ImageBytes(body) => TagBody::ImageBytes(body.into_owned()),
+            TagBody::ImageBytes2(body) => TagBody::ImageBytes2(body.into_owned()),
+            TagBody::ImageBytes3(body) => TagBody::ImageBytes3(body.into_owned()),
+            TagBody::VideoCompositionBlock(body) => {
+                TagBody::VideoCompositionBlock(body.into_owned())
+            }
+            TagBody::VideoSequence(body) => TagBody::VideoSequence(body.into_owned()),
+            TagBody::AudioBytes(body) => TagBody::AudioBytes(body.into_owned()),
+            TagBody::Mp4Header(body) => TagBody::Mp4Header(body.into_owned()),
+            TagBody::Raw(body) => TagBody::Raw(body.into_owned()),
+            TagBody::Invalid { error, bytes } => TagBody::Invalid {
+                error,
+                bytes: bytes.into_owned(),
+            },
+            TagBody::FontTables(body) => TagBody::FontTables(body),
+            TagBody::CompositionAttributes(body) => TagBody::CompositionAttributes(body),
+            TagBody::LayerAttributes(body) => TagBody::LayerAttributes(body),
+            TagBody::SolidColor(body) => TagBody::SolidColor(body),
+            TagBody::TextSource(body) => TagBody::TextSource(body),
+            TagBody::TextPathOption(body) => TagBody::TextPathOption(body),
+            TagBody::TextMoreOption(body) => TagBody::TextMoreOption(body),
+            TagBody::ImageReference(body) => TagBody::ImageReference(body),
+            TagBody::CompositionReference(body) => TagBody::CompositionReference(body),
+            TagBody::Transform2D(body) => TagBody::Transform2D(body),
+            TagBody::Mask(body) => TagBody::Mask(body),
+            TagBody::Rectangle(body) => TagBody::Rectangle(body),
+            TagBody::Ellipse(body) => TagBody::Ellipse(body),
+            TagBody::PolyStar(body) => TagBody::PolyStar(body),
+            TagBody::ShapePath(body) => TagBody::ShapePath(body),
+            TagBody::Fill(body) => TagBody::Fill(body),
+            TagBody::Stroke(body) => TagBody::Stroke(body),
+            TagBody::GradientFill(body) => TagBody::GradientFill(body),
+            TagBody::GradientStroke(body) => TagBody::GradientStroke(body),
+            TagBody::MergePaths(body) => TagBody::MergePaths(body),
+            TagBody::TrimPaths(body) => TagBody::TrimPaths(body),
+            TagBody::Repeater(body) => TagBody::Repeater(body),
+            TagBody::RoundCorners(body) => TagBody::RoundCorners(body),
+            TagBody::Performance(body) => TagBody::Performance(body),
+            TagBody::DropShadowStyle(body) => TagBody::DropShadowStyle(body),
+            TagBody::BitmapSequence(body) => TagBody::BitmapSequence(body.into_owned()),
+            TagBody::FileAttributes(body) => TagBody::FileAttributes(body),
+            TagBody::LayerAttributesV2(body) => TagBody::LayerAttributesV2(body),
+            TagBody::LayerAttributesV3(body) => TagBody::LayerAttributesV3(body),
+            TagBody::LayerAttributesExtra(body) => TagBody::LayerAttributesExtra(body),
+        }
+    }
+}
+
+impl Encodable for TagBody<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        match self {
+            TagBody::End => {}
+            TagBody::FontTables(body) => body.encode(writer, ctx),
+            TagBody::VectorCompositionBlock(body) => body.encode(writer, ctx),
+            TagBody::CompositionAttributes(body) => body.encode(writer, ctx),
+            TagBody::ImageTables(body) => body.encode(writer, ctx),
+            TagBody::LayerBlock(body) => body.encode(writer, ctx),
+            TagBody::LayerAttributes(body) => body.encode(writer, ctx),
+            TagBody::SolidColor(body) => body.encode(writer, ctx),
+            TagBody::TextSource(body) => body.encode(writer, ctx),
+            TagBody::TextPathOption(body) => body.encode(writer, ctx),
+            TagBody::TextMoreOption(body) => body.encode(writer, ctx),
+            TagBody::ImageReference(body) => body.encode(writer, ctx),
+            TagBody::CompositionReference(body) => body.encode(writer, ctx),
+            TagBody::Transform2D(body) => body.encode(writer, ctx),
+            TagBody::Mask(body) => body.encode(writer, ctx),
+            TagBody::ShapeGroup(body) => body.encode(writer, ctx),
+            TagBody::Rectangle(body) => body.encode(writer, ctx),
+            TagBody::Ellipse(body) => body.encode(writer, ctx),
+            TagBody::PolyStar(body) => body.encode(writer, ctx),
+            TagBody::ShapePath(body) => body.encode(writer, ctx),
+            TagBody::Fill(body) => body.encode(writer, ctx),
+            TagBody::Stroke(body) => body.encode(writer, ctx),
+            TagBody::GradientFill(body) => body.encode(writer, ctx),
+            TagBody::GradientStroke(body) => body.encode(writer, ctx),
+            TagBody::MergePaths(body) => body.encode(writer, ctx),
+            TagBody::TrimPaths(body) => body.encode(writer, ctx),
+            TagBody::Repeater(body) => body.encode(writer, ctx),
+            TagBody::RoundCorners(body) => body.encode(writer, ctx),
+            TagBody::Performance(body) => body.encode(writer, ctx),
+            TagBody::DropShadowStyle(body) => body.encode(writer, ctx),
+            TagBody::BitmapCompositionBlock(body) => body.encode(writer, ctx),
+            TagBody::BitmapSequence(body) => body.encode(writer, ctx),
+            TagBody::FileAttributes(body) => body.encode(writer, ctx),
+            TagBody::ImageBytes(body) => body.encode(writer, ctx),
+            TagBody::ImageBytes2(body) => body.encode(writer, ctx),
+            TagBody::ImageBytes3(body) => body.encode(writer, ctx),
+            TagBody::VideoCompositionBlock(body) => body.encode(writer, ctx),
+            TagBody::VideoSequence(body) => body.encode(writer, ctx),
+            TagBody::LayerAttributesV2(body) => body.encode(writer, ctx),
+            TagBody::LayerAttributesV3(body) => body.encode(writer, ctx),
+            TagBody::LayerAttributesExtra(body) => body.encode(writer, ctx),
+            TagBody::AudioBytes(body) => body.encode(writer, ctx),
+            TagBody::Mp4Header(body) => body.encode(writer, ctx),
+            TagBody::Raw(body) => writer.write_bytes(&body.data),
+            TagBody::Invalid { bytes, .. } => writer.write_bytes(&bytes.data),
+        }
+    }
 }
 
 /// Performance 标签主要存储 PAG 的性能指标数据。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Performance {
     pub rendering_time: i64,
     pub image_decoding_time: i64,
     pub presenting_time: i64,
     pub graphics_memory: i64,
 }
+
+impl Encodable for Performance {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_encoded_i64(EncodedInt64::from(self.rendering_time));
+        writer.write_encoded_i64(EncodedInt64::from(self.image_decoding_time));
+        writer.write_encoded_i64(EncodedInt64::from(self.presenting_time));
+        writer.write_encoded_i64(EncodedInt64::from(self.graphics_memory));
+    }
+}
@@ -1,16 +1,20 @@
-use crate::parse::{AttributeConfig, EncodedUint32, Parsable, ParseContext, ParseError, Parser};
+use crate::{
+    encode::{AttributeBlockWriter, Encodable, Writer},
+    parse::{AttributeConfig, EncodedUint32, Parsable, ParseContext, ParseError, Parser},
+};
 
-use super::{Color, ParagraphJustification, Point};
+use super::{Color, ParagraphJustification, Point, Property};
 
 /// FontTables 是字体信息的合集。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontTables {
     pub count: EncodedUint32,
     pub font_datas: Vec<FontData>,
 }
 
-impl Parsable for FontTables {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for FontTables {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let count = parser.next_encoded_u32()?;
         let mut font_datas = vec![];
         for _ in 0..count.to_u32() {
@@ -23,15 +27,25 @@ impl Parsable for FontTables {
     }
 }
 
+impl Encodable for FontTables {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_u32(EncodedUint32::from(self.font_datas.len() as u32));
+        for font_data in &self.font_datas {
+            font_data.encode(writer, ctx.clone());
+        }
+    }
+}
+
 /// FontData 标识字体
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontData {
     pub font_family: String,
     pub font_style: String,
 }
 
-impl Parsable for FontData {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for FontData {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let font_family = parser.next_string()?;
         let font_style = parser.next_string()?;
         let result = Self {
@@ -43,7 +57,15 @@ impl Parsable for FontData {
     }
 }
 
-#[derive(Debug, Default)]
+impl Encodable for FontData {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_string(&self.font_family);
+        writer.write_string(&self.font_style);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextDocument {
     pub apply_fill_flag: bool,
     pub apply_stroke_flag: bool,
@@ -86,91 +108,68 @@ impl TextDocument {
     }
 }
 
-impl Parsable for TextDocument {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let mut bits = parser.new_bits();
-
-        let apply_fill_flag = bits.next();
-        let apply_stroke_flag = bits.next();
-        let box_text_flag = bits.next();
-        let faux_bold_flag = bits.next();
-        let faux_italic_flag = bits.next();
-        let stroke_over_fill_flag = bits.next();
-        let baseline_shift_flag = bits.next();
-        let first_baseline_flag = bits.next();
-        let box_text_pos_flag = bits.next();
-        let box_text_size_flag = bits.next();
-        let fill_color_flag = bits.next();
-        let font_size_flag = bits.next();
-        let stroke_color_flag = bits.next();
-        let stroke_width_flag = bits.next();
-        let text_flag = bits.next();
-        let justification_flag = bits.next();
-        let leading_flag = bits.next();
-        let tracking_flag = bits.next();
-        let has_font_data_flag = bits.next();
-        let parser = &mut bits.finish()?;
-
-        let mut result = Self {
-            apply_fill_flag,
-            apply_stroke_flag,
-            box_text_flag,
-            faux_bold_flag,
-            faux_italic_flag,
-            stroke_over_fill_flag,
-            baseline_shift_flag,
-            first_baseline_flag,
-            box_text_pos_flag,
-            box_text_size_flag,
-            fill_color_flag,
-            font_size_flag,
-            stroke_color_flag,
-            stroke_width_flag,
-            text_flag,
-            justification_flag,
-            leading_flag,
-            tracking_flag,
-            has_font_data_flag,
+impl<'a> Parsable<'a> for TextDocument {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let mut result = parser.next_bits(|bits| Self {
+            apply_fill_flag: bits.next(),
+            apply_stroke_flag: bits.next(),
+            box_text_flag: bits.next(),
+            faux_bold_flag: bits.next(),
+            faux_italic_flag: bits.next(),
+            stroke_over_fill_flag: bits.next(),
+            baseline_shift_flag: bits.next(),
+            first_baseline_flag: bits.next(),
+            box_text_pos_flag: bits.next(),
+            box_text_size_flag: bits.next(),
+            fill_color_flag: bits.next(),
+            font_size_flag: bits.next(),
+            stroke_color_flag: bits.next(),
+            stroke_width_flag: bits.next(),
+            text_flag: bits.next(),
+            justification_flag: bits.next(),
+            leading_flag: bits.next(),
+            tracking_flag: bits.next(),
+            has_font_data_flag: bits.next(),
             ..Default::default()
-        };
+        })?;
 
-        if baseline_shift_flag {
+        if result.baseline_shift_flag {
             result.baseline_shift = Some(parser.next_f32()?);
         }
-        if first_baseline_flag {
+        if result.first_baseline_flag {
             result.first_baseline = Some(parser.next_f32()?);
         }
-        if box_text_pos_flag {
+        if result.box_text_pos_flag {
             result.box_text_pos = Some(parser.next()?);
         }
-        if box_text_size_flag {
+        if result.box_text_size_flag {
             result.box_text_size = Some(parser.next()?);
         }
-        if fill_color_flag {
+        if result.fill_color_flag {
             result.fill_color = Some(parser.next()?);
         }
-        if font_size_flag {
+        if result.font_size_flag {
             result.font_size = Some(parser.next_f32()?);
         }
-        if stroke_color_flag {
+        if result.stroke_color_flag {
             result.stroke_color = Some(parser.next()?);
         }
-        if stroke_width_flag {
+        if result.stroke_width_flag {
             result.stroke_width = Some(parser.next_f32()?);
         }
-        if text_flag {
+        if result.text_flag {
             result.text = Some(parser.next_string()?);
         }
-        if justification_flag {
+        if result.justification_flag {
             result.justification = Some(parser.next_u8()?);
         }
-        if leading_flag {
+        if result.leading_flag {
             result.leading = Some(parser.next_f32()?);
         }
-        if tracking_flag {
+        if result.tracking_flag {
             result.tracking = Some(parser.next_f32()?);
         }
-        if has_font_data_flag {
+        if result.has_font_data_flag {
             result.font_id = Some(parser.next_encoded_u32()?);
         }
 
@@ -179,19 +178,86 @@ impl Parsable for TextDocument {
     }
 }
 
+impl Encodable for TextDocument {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        // 带有数据的标识位以数据是否存在为准
+        writer.write_bit(self.apply_fill_flag);
+        writer.write_bit(self.apply_stroke_flag);
+        writer.write_bit(self.box_text_flag);
+        writer.write_bit(self.faux_bold_flag);
+        writer.write_bit(self.faux_italic_flag);
+        writer.write_bit(self.stroke_over_fill_flag);
+        writer.write_bit(self.baseline_shift.is_some());
+        writer.write_bit(self.first_baseline.is_some());
+        writer.write_bit(self.box_text_pos.is_some());
+        writer.write_bit(self.box_text_size.is_some());
+        writer.write_bit(self.fill_color.is_some());
+        writer.write_bit(self.font_size.is_some());
+        writer.write_bit(self.stroke_color.is_some());
+        writer.write_bit(self.stroke_width.is_some());
+        writer.write_bit(self.text.is_some());
+        writer.write_bit(self.justification.is_some());
+        writer.write_bit(self.leading.is_some());
+        writer.write_bit(self.tracking.is_some());
+        writer.write_bit(self.font_id.is_some());
+
+        if let Some(value) = self.baseline_shift {
+            writer.write_f32(value);
+        }
+        if let Some(value) = self.first_baseline {
+            writer.write_f32(value);
+        }
+        if let Some(value) = &self.box_text_pos {
+            value.encode(writer, ctx.clone());
+        }
+        if let Some(value) = &self.box_text_size {
+            value.encode(writer, ctx.clone());
+        }
+        if let Some(value) = &self.fill_color {
+            value.encode(writer, ctx.clone());
+        }
+        if let Some(value) = self.font_size {
+            writer.write_f32(value);
+        }
+        if let Some(value) = &self.stroke_color {
+            value.encode(writer, ctx.clone());
+        }
+        if let Some(value) = self.stroke_width {
+            writer.write_f32(value);
+        }
+        if let Some(value) = &self.text {
+            writer.write_string(value);
+        }
+        if let Some(value) = self.justification {
+            writer.write_u8(value);
+        }
+        if let Some(value) = self.leading {
+            writer.write_f32(value);
+        }
+        if let Some(value) = self.tracking {
+            writer.write_f32(value);
+        }
+        if let Some(value) = self.font_id {
+            writer.write_encoded_u32(value);
+        }
+        writer.align();
+    }
+}
+
 /// TextPathOption ⽂本绘制信息，包含：绘制路径，前后左右间距等。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextPathOption {
     pub path: EncodedUint32,
-    pub reversed_path: bool,
-    pub perpendicular_to_path: bool,
-    pub force_alignment: bool,
-    pub first_margin: f32,
-    pub last_margin: f32,
+    pub reversed_path: Property<bool>,
+    pub perpendicular_to_path: Property<bool>,
+    pub force_alignment: Property<bool>,
+    pub first_margin: Property<f32>,
+    pub last_margin: Property<f32>,
 }
 
-impl Parsable for TextPathOption {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TextPathOption {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let path = block.flag(AttributeConfig::Value(0)); // EncodedUint32
         let reversed_path = block.flag(AttributeConfig::DiscreteProperty(false));
@@ -201,27 +267,61 @@ impl Parsable for TextPathOption {
         let last_margin = block.flag(AttributeConfig::SimpleProperty(0.));
 
         let result = Self {
-            path: block.read(path).unwrap_or(EncodedUint32::from(0)),
-            reversed_path: block.read(reversed_path).unwrap_or(false),
-            perpendicular_to_path: block.read(perpendicular_to_path).unwrap_or(false),
-            force_alignment: block.read(force_aligment).unwrap_or(false),
-            first_margin: block.read(first_margin).unwrap_or(0.),
-            last_margin: block.read(last_margin).unwrap_or(0.),
+            path: block.read("path", path)?.unwrap_or(EncodedUint32::from(0)),
+            reversed_path: block
+                .read_property("reversed_path", reversed_path)?
+                .unwrap_or(Property::Value(false)),
+            perpendicular_to_path: block
+                .read_property("perpendicular_to_path", perpendicular_to_path)?
+                .unwrap_or(Property::Value(false)),
+            force_alignment: block
+                .read_property("force_aligment", force_aligment)?
+                .unwrap_or(Property::Value(false)),
+            first_margin: block
+                .read_property("first_margin", first_margin)?
+                .unwrap_or(Property::Value(0.)),
+            last_margin: block
+                .read_property("last_margin", last_margin)?
+                .unwrap_or(Property::Value(0.)),
         };
 
+        parser.advance(block.byte_length()?);
         log::debug!("parse_TextPathOption => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for TextPathOption {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(AttributeConfig::Value(EncodedUint32::from(0)), &self.path);
+        block.write_property(
+            AttributeConfig::DiscreteProperty(false),
+            &self.reversed_path,
+        );
+        block.write_property(
+            AttributeConfig::DiscreteProperty(false),
+            &self.perpendicular_to_path,
+        );
+        block.write_property(
+            AttributeConfig::DiscreteProperty(false),
+            &self.force_alignment,
+        );
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.first_margin);
+        block.write_property(AttributeConfig::SimpleProperty(0.), &self.last_margin);
+        block.finish(writer);
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextMoreOption {
     pub anchor_point_grouping: ParagraphJustification,
-    pub grouping_alignment: Point,
+    pub grouping_alignment: Property<Point>,
 }
 
-impl Parsable for TextMoreOption {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TextMoreOption {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let anchor_point_grouping =
             block.flag(AttributeConfig::Value(ParagraphJustification::LeftJustify));
@@ -229,32 +329,65 @@ impl Parsable for TextMoreOption {
 
         let result = Self {
             anchor_point_grouping: block
-                .read(anchor_point_grouping)
+                .read("anchor_point_grouping", anchor_point_grouping)?
                 .unwrap_or(ParagraphJustification::LeftJustify),
-            grouping_alignment: block.read(grouping_alignment).unwrap_or(Point::zero()),
+            grouping_alignment: block
+                .read_property("grouping_alignment", grouping_alignment)?
+                .unwrap_or(Property::Value(Point::zero())),
         };
 
+        parser.advance(block.byte_length()?);
         log::debug!("parse_TextMoreOption => {:?}", result);
         Ok(result)
     }
 }
 
+impl Encodable for TextMoreOption {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write(
+            AttributeConfig::Value(ParagraphJustification::LeftJustify),
+            &self.anchor_point_grouping,
+        );
+        block.write_property(
+            AttributeConfig::MultiDimensionProperty(Point::zero()),
+            &self.grouping_alignment,
+        );
+        block.finish(writer);
+    }
+}
+
 /// TextSource ⽂本信息，包含：⽂本，字体，⼤⼩，颜⾊等基础信息。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextSource {
-    pub source_text: TextDocument,
+    pub source_text: Property<TextDocument>,
 }
 
-impl Parsable for TextSource {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TextSource {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let mut block = parser.new_attribute_block();
         let source_text = block.flag(AttributeConfig::DiscreteProperty(TextDocument::new())); // ??
 
         let result = Self {
-            source_text: block.read(source_text).unwrap_or_default(), // ??
+            source_text: block
+                .read_property("source_text", source_text)?
+                .unwrap_or(Property::Value(TextDocument::new())),
         };
 
+        parser.advance(block.byte_length()?);
         log::debug!("parse_TextSource => {:?}", result);
         Ok(result)
     }
 }
+
+impl Encodable for TextSource {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        let mut block = AttributeBlockWriter::new();
+        block.write_property(
+            AttributeConfig::DiscreteProperty(TextDocument::new()),
+            &self.source_text,
+        );
+        block.finish(writer);
+    }
+}
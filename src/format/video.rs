@@ -1,21 +1,50 @@
 use crate::{
-    parse::{EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError, Parser, Time},
+    encode::{Encodable, Writer},
+    parse::{
+        EncodedInt32, EncodedUint32, Parsable, ParseContext, ParseError, ParseErrorKind, Parser,
+        Time,
+    },
     visit::{LayerInfo, Traversable},
 };
 
-use super::{ByteData, TagBlock};
+use super::{ByteData, Frame, TagBlock, TagBody};
 
 /// VideoCompositionBlock 存储了 1 个或多个不同尺⼨的视频序列帧。
 #[derive(Debug)]
-pub struct VideoCompositionBlock {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoCompositionBlock<'a> {
     pub id: EncodedUint32,
     pub has_alpha: bool,
-    pub tag_block: TagBlock,
+    pub tag_block: TagBlock<'a>,
 }
 
-impl Parsable for VideoCompositionBlock {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
-        let id = parser.next_id()?;
+impl VideoCompositionBlock<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> VideoCompositionBlock<'static> {
+        VideoCompositionBlock {
+            id: self.id,
+            has_alpha: self.has_alpha,
+            tag_block: self.tag_block.into_owned(),
+        }
+    }
+
+    /// 视频序列对应的 MP4 文件头，位于该序列之后的 Mp4Header 标签中
+    pub fn mp4_header(&self, sequence: &VideoSequence) -> Option<&[u8]> {
+        let tags = &self.tag_block.tags;
+        let index = tags.iter().position(|tag| match &tag.body {
+            TagBody::VideoSequence(other) => std::ptr::eq(other, sequence),
+            _ => false,
+        })?;
+        match &tags.get(index + 1)?.body {
+            TagBody::Mp4Header(header) => Some(&header.data),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for VideoCompositionBlock<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
+        let id = parser.next_id().map_err(|e| e.with_field("id"))?;
         let has_alpha = parser.next_bool()?;
         let tag_block = TagBlock::parse(parser, ctx.with_alpha(has_alpha))?;
         let result = Self {
@@ -28,7 +57,16 @@ impl Parsable for VideoCompositionBlock {
     }
 }
 
-impl Traversable for VideoCompositionBlock {
+impl Encodable for VideoCompositionBlock<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_id(self.id);
+        writer.write_bool(self.has_alpha);
+        self.tag_block
+            .encode(writer, ctx.with_alpha(self.has_alpha));
+    }
+}
+
+impl Traversable for VideoCompositionBlock<'_> {
     fn traverse_layer<F>(&self, visitor: F)
     where
         F: Fn(&dyn LayerInfo) + Clone,
@@ -39,22 +77,46 @@ impl Traversable for VideoCompositionBlock {
 
 /// VideoSequence 存储了 1 个版本的视频序列帧的结构。
 #[derive(Debug)]
-pub struct VideoSequence {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoSequence<'a> {
     pub width: EncodedInt32,
     pub height: EncodedInt32,
     pub frame_rate: f32,
     pub alpha_start_x: Option<EncodedInt32>,
     pub alpha_start_y: Option<EncodedInt32>,
-    pub sps_data: ByteData,
-    pub pps_data: ByteData,
+    pub sps_data: ByteData<'a>,
+    pub pps_data: ByteData<'a>,
     pub frame_count: EncodedUint32,
     pub is_key_frame_flag: Vec<bool>,
-    pub video_frames: Vec<VideoFrame>,
+    pub video_frames: Vec<VideoFrame<'a>>,
     pub static_time_ranges: Vec<TimeRange>,
 }
 
-impl Parsable for VideoSequence {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl VideoSequence<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> VideoSequence<'static> {
+        VideoSequence {
+            width: self.width,
+            height: self.height,
+            frame_rate: self.frame_rate,
+            alpha_start_x: self.alpha_start_x,
+            alpha_start_y: self.alpha_start_y,
+            sps_data: self.sps_data.into_owned(),
+            pps_data: self.pps_data.into_owned(),
+            frame_count: self.frame_count,
+            is_key_frame_flag: self.is_key_frame_flag,
+            video_frames: self
+                .video_frames
+                .into_iter()
+                .map(VideoFrame::into_owned)
+                .collect(),
+            static_time_ranges: self.static_time_ranges,
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for VideoSequence<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let width = parser.next_encoded_i32()?;
         let height = parser.next_encoded_i32()?;
         let frame_rate = parser.next_f32()?;
@@ -68,19 +130,22 @@ impl Parsable for VideoSequence {
         };
         let sps_data = ByteData::parse(parser, ctx.clone())?;
         let pps_data = ByteData::parse(parser, ctx.clone())?;
-        let frame_count = parser.next_encoded_u32()?;
-        // log::warn!("{}", frame_count);
-
-        let mut bits = parser.new_bits();
-        // log::warn!("{:?}", bits);
-        let mut is_key_frame_flag = vec![];
-        for _ in 0..frame_count.to_u32() {
-            is_key_frame_flag.push(bits.next());
+        let frame_count = parser
+            .next_encoded_u32()
+            .map_err(|e| e.with_field("frameCount"))?;
+        // 每帧占用 1 个比特位的关键帧标识，避免异常数据导致分配过大的内存
+        if frame_count.to_usize() > parser.remain() * 8 {
+            return Err(
+                ParseError::from(ParseErrorKind::Incomplete(nom::Needed::Unknown))
+                    .with_offset(parser.offset())
+                    .with_field("frameCount"),
+            );
         }
-        // let mut input = bits.finish();
-        // log::warn!("{:?}", &input[..8]);
-        // log::warn!("{:?}", bits);
-        let parser = &mut bits.finish()?;
+        let is_key_frame_flag = parser.next_bits(|bits| {
+            (0..frame_count.to_u32())
+                .map(|_| bits.next())
+                .collect::<Vec<_>>()
+        })?;
 
         let mut video_frames = vec![];
         for i in 0..frame_count.to_u32() {
@@ -120,30 +185,82 @@ impl Parsable for VideoSequence {
     }
 }
 
+impl Encodable for VideoSequence<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_encoded_i32(self.width);
+        writer.write_encoded_i32(self.height);
+        writer.write_f32(self.frame_rate);
+        if ctx.has_alpha() {
+            writer.write_encoded_i32(self.alpha_start_x.unwrap_or_default());
+            writer.write_encoded_i32(self.alpha_start_y.unwrap_or_default());
+        }
+        self.sps_data.encode(writer, ctx.clone());
+        self.pps_data.encode(writer, ctx.clone());
+        writer.write_encoded_u32(EncodedUint32::from(self.video_frames.len() as u32));
+        for frame in &self.video_frames {
+            writer.write_bit(frame.is_key_frame);
+        }
+        for frame in &self.video_frames {
+            frame.encode(writer, ctx.clone());
+        }
+        writer.write_encoded_u32(EncodedUint32::from(self.static_time_ranges.len() as u32));
+        for time_range in &self.static_time_ranges {
+            time_range.encode(writer, ctx.clone());
+        }
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeRange {
     pub start: Time,
     pub end: Time,
 }
 
-impl Parsable for TimeRange {
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl<'a> Parsable<'a> for TimeRange {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         let start = parser.next_time()?;
         let end = parser.next_time()?;
         Ok(Self { start, end })
     }
 }
 
+impl TimeRange {
+    /// 帧是否位于区间内，区间包含首尾两帧
+    pub fn contains(&self, frame: Frame) -> bool {
+        self.start.to_u64() as Frame <= frame && frame <= self.end.to_u64() as Frame
+    }
+}
+
+impl Encodable for TimeRange {
+    fn encode(&self, writer: &mut impl Writer, _ctx: impl ParseContext) {
+        writer.write_time(self.start);
+        writer.write_time(self.end);
+    }
+}
+
 /// 视频帧信息。
 #[derive(Debug)]
-pub struct VideoFrame {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoFrame<'a> {
     pub is_key_frame: bool,
     pub frame: Time,
-    pub file_bytes: ByteData,
+    pub file_bytes: ByteData<'a>,
 }
 
-impl Parsable for VideoFrame {
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError> {
+impl VideoFrame<'_> {
+    /// 复制引用的二进制数据
+    pub fn into_owned(self) -> VideoFrame<'static> {
+        VideoFrame {
+            is_key_frame: self.is_key_frame,
+            frame: self.frame,
+            file_bytes: self.file_bytes.into_owned(),
+        }
+    }
+}
+
+impl<'a> Parsable<'a> for VideoFrame<'a> {
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError> {
         let frame = parser.next_time()?;
         let file_bytes = ByteData::parse(parser, ctx)?;
         let result = Self {
@@ -155,3 +272,29 @@ impl Parsable for VideoFrame {
         Ok(result)
     }
 }
+
+impl Encodable for VideoFrame<'_> {
+    fn encode(&self, writer: &mut impl Writer, ctx: impl ParseContext) {
+        writer.write_time(self.frame);
+        self.file_bytes.encode(writer, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{Parsable, ParseErrorKind, StreamParser};
+
+    use super::VideoSequence;
+
+    #[test]
+    fn test_video_sequence_frame_count_overflow() {
+        // width、height、frameRate、空的 SPS/PPS 之后的 frameCount 远大于剩余数据可容纳的关键帧标识
+        let input = [
+            0x80, 0x01, 0x40, 0x00, 0x00, 0xc0, 0x41, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x0f,
+        ];
+        let mut parser = StreamParser::new(&input);
+        let error = VideoSequence::parse(&mut parser, ()).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Incomplete(_)));
+        assert_eq!(error.field, Some("frameCount"));
+    }
+}
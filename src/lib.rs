@@ -5,5 +5,14 @@ pub use format::*;
 /// Parser
 pub mod parse;
 
+/// Encoder
+pub mod encode;
+
 /// Visit the file document
 pub mod visit;
+
+/// Export embedded media to standard formats
+pub mod export;
+
+/// Render the frames of sequences
+pub mod render;
@@ -1,4 +1,11 @@
-use super::{bits::Bits, parsable::Parsable, parser::StreamParser};
+use crate::format::{Keyframe, KeyframeInterpolationType, Point, Property};
+
+use super::{
+    bits::Bits,
+    parsable::Parsable,
+    parser::{Parser, StreamParser},
+    ParseError, ParseErrorKind, BEZIER_PRECISION, SPATIAL_PRECISION,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AttributeType {
@@ -25,6 +32,22 @@ pub enum AttributeConfig<T> {
     Custom(T),
 }
 
+impl<T> AttributeConfig<T> {
+    /// 属性的默认值
+    pub fn default_value(&self) -> &T {
+        match self {
+            Self::Value(value)
+            | Self::FixedValue(value)
+            | Self::SimpleProperty(value)
+            | Self::DiscreteProperty(value)
+            | Self::MultiDimensionProperty(value)
+            | Self::SpatialProperty(value)
+            | Self::BitFlag(value)
+            | Self::Custom(value) => value,
+        }
+    }
+}
+
 impl<T> From<&AttributeConfig<T>> for AttributeType {
     fn from(config: &AttributeConfig<T>) -> Self {
         match config {
@@ -75,16 +98,42 @@ enum AttributeBlockState<'a> {
 
 #[derive(Debug)]
 pub struct AttributeBlock<'a> {
+    input: &'a [u8],
     state: AttributeBlockState<'a>,
 }
 
 impl<'a> AttributeBlock<'a> {
     pub fn new(input: &'a [u8]) -> Self {
         AttributeBlock {
+            input,
             state: AttributeBlockState::Flag(Bits::new(input)),
         }
     }
 
+    /// 指定 input 起始位置在完整输入中的绝对偏移量
+    pub fn with_offset(self, offset: usize) -> Self {
+        AttributeBlock {
+            input: self.input,
+            state: AttributeBlockState::Flag(Bits::new(self.input).with_offset(offset)),
+        }
+    }
+
+    /// 在标志位区域中读取自定义的 UB[num_bits] 数据
+    pub fn next_ubits(&mut self, num_bits: u8) -> u32 {
+        match &mut self.state {
+            AttributeBlockState::Flag(bits) => bits.next_ubits(num_bits),
+            AttributeBlockState::Content(_) => 0,
+        }
+    }
+
+    /// AttributeBlock 目前已读取的字节数，包含标志位区域以及内容区域
+    pub fn byte_length(&self) -> Result<usize, ParseError> {
+        match &self.state {
+            AttributeBlockState::Flag(bits) => bits.byte_length(),
+            AttributeBlockState::Content(parser) => Ok(self.input.len() - parser.remain()),
+        }
+    }
+
     pub fn flag(&mut self, r#type: impl Into<AttributeType>) -> (AttributeType, AttributeFlag) {
         let r#type = r#type.into();
         let flag = match &self.state {
@@ -121,10 +170,7 @@ impl<'a> AttributeBlock<'a> {
         }
 
         flag.animatable = bits.next();
-        if !flag.animatable {
-            return flag;
-        }
-        if let AttributeType::SpatialProperty = r#type {
+        if !flag.animatable || r#type != AttributeType::SpatialProperty {
             return flag;
         }
 
@@ -132,52 +178,325 @@ impl<'a> AttributeBlock<'a> {
         flag
     }
 
+    /// 读取 Value 属性；若属性含有关键帧，则返回第一个关键帧的起始值
+    /// - field: 属性名称，用于错误信息
     pub fn read<T>(
         &mut self,
+        field: &'static str,
         (r#type, flag): (impl Into<AttributeType>, AttributeFlag),
-    ) -> Option<T>
+    ) -> Result<Option<T>, ParseError>
     where
-        T: Parsable,
+        T: Parsable<'a> + Clone,
+    {
+        let property = self.read_property(field, (r#type, flag))?;
+        Ok(property.and_then(Property::into_value))
+    }
+
+    /// 读取 Property 属性，包含完整的关键帧信息
+    /// - field: 属性名称，用于错误信息
+    pub fn read_property<T>(
+        &mut self,
+        field: &'static str,
+        (r#type, flag): (impl Into<AttributeType>, AttributeFlag),
+    ) -> Result<Option<Property<T>>, ParseError>
+    where
+        T: Parsable<'a> + Clone,
     {
         if let AttributeBlockState::Flag(bits) = &self.state {
-            match bits.clone().finish() {
-                Ok(parser) => {
-                    self.state = AttributeBlockState::Content(parser);
-                }
-                Err(e) => {
-                    log::error!("Error: {:?}", e);
-                    return None;
-                }
-            }
+            let parser = bits.clone().finish().map_err(|e| e.with_field(field))?;
+            self.state = AttributeBlockState::Content(parser);
         }
-        let parser = match &mut self.state {
-            AttributeBlockState::Content(parser) => parser,
-            _ => return None,
+        let AttributeBlockState::Content(parser) = &mut self.state else {
+            return Ok(None);
         };
 
         let r#type = r#type.into();
-        match r#type {
-            AttributeType::NotExisted => None,
-            AttributeType::BitFlag => T::from_bool(flag.exist),
-            AttributeType::FixedValue | AttributeType::Value => {
-                if flag.exist {
-                    T::parse(parser, ()).ok()
-                } else {
-                    None
+        let result = match r#type {
+            AttributeType::NotExisted => Ok(None),
+            AttributeType::BitFlag => Ok(T::from_bool(flag.exist).map(Property::Value)),
+            _ if !flag.exist => Ok(None),
+            AttributeType::FixedValue | AttributeType::Value | AttributeType::Custom => {
+                T::parse(parser, ()).map(|value| Some(Property::Value(value)))
+            }
+            _ if flag.animatable => read_keyframes(parser, r#type, &flag).map(Some),
+            _ => T::parse(parser, ()).map(|value| Some(Property::Value(value))),
+        };
+        result.map_err(|e| e.with_field(field))
+    }
+}
+
+/// 读取 Property 的关键帧列表
+fn read_keyframes<'a, T>(
+    parser: &mut impl Parser<'a>,
+    r#type: AttributeType,
+    flag: &AttributeFlag,
+) -> Result<Property<T>, ParseError>
+where
+    T: Parsable<'a> + Clone,
+{
+    let count_offset = parser.offset();
+    let count = parser.next_encoded_u32()?.to_usize();
+    // 每个关键帧至少占用一个字节的时间值，避免异常数据导致分配过大的内存
+    if count > parser.remain() {
+        let needed = nom::Needed::new(count - parser.remain());
+        return Err(
+            ParseError::from(ParseErrorKind::Incomplete(needed)).with_offset(parser.offset())
+        );
+    }
+
+    let interpolation_types = match r#type {
+        AttributeType::DiscreteProperty => vec![KeyframeInterpolationType::Hold; count],
+        _ => parser.next_bits(|bits| {
+            (0..count)
+                .map(|_| KeyframeInterpolationType::from(bits.next_ubits(2) as u8))
+                .collect()
+        })?,
+    };
+
+    let mut times = Vec::with_capacity(count + 1);
+    for _ in 0..=count {
+        times.push(parser.next_time()?);
+    }
+
+    let dimensionality = match r#type {
+        AttributeType::MultiDimensionProperty => T::dimensionality(),
+        _ => 1,
+    };
+    let read_eases =
+        |bits: &mut Bits| read_eases(bits, &interpolation_types, dimensionality, flag.has_spatial);
+
+    // 按比特位存储的属性值与之后的缓动参数之间不进行字节对齐
+    let bit_values = parser.next_bits(|bits| {
+        let values = T::parse_value_bits(bits, count + 1, r#type)?;
+        Some((values, read_eases(bits)))
+    })?;
+    let (values, eases) = match bit_values {
+        Some(result) => result,
+        None => {
+            let mut values = Vec::with_capacity(count + 1);
+            for _ in 0..=count {
+                values.push(T::parse(parser, ())?);
+            }
+            (values, parser.next_bits(read_eases)?)
+        }
+    };
+
+    if count == 0 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "animated property without keyframes".to_string(),
+        ))
+        .with_offset(count_offset));
+    }
+
+    let keyframes = eases
+        .into_iter()
+        .enumerate()
+        .map(|(i, ease)| Keyframe {
+            start_value: values[i].clone(),
+            end_value: values[i + 1].clone(),
+            start_time: times[i],
+            end_time: times[i + 1],
+            interpolation_type: interpolation_types[i],
+            bezier_out: ease.bezier_out,
+            bezier_in: ease.bezier_in,
+            spatial_out: ease.spatial_out,
+            spatial_in: ease.spatial_in,
+        })
+        .collect();
+    Ok(Property::Animated(keyframes))
+}
+
+#[derive(Debug)]
+struct KeyframeEase {
+    bezier_out: Vec<Point>,
+    bezier_in: Vec<Point>,
+    spatial_out: Point,
+    spatial_in: Point,
+}
+
+/// 读取时间缓动参数，以及空间缓动参数（若存在）
+fn read_eases(
+    bits: &mut Bits,
+    interpolation_types: &[KeyframeInterpolationType],
+    dimensionality: usize,
+    has_spatial: bool,
+) -> Vec<KeyframeEase> {
+    let next_point = |bits: &mut Bits, num_bits: u8, precision: f32| {
+        let x = bits.next_sbits(num_bits) as f32 * precision;
+        let y = bits.next_sbits(num_bits) as f32 * precision;
+        Point::new(x, y)
+    };
+
+    let num_bits = bits.next_num_bits();
+    let mut eases = interpolation_types
+        .iter()
+        .map(|interpolation_type| {
+            let mut ease = KeyframeEase {
+                bezier_out: vec![],
+                bezier_in: vec![],
+                spatial_out: Point::zero(),
+                spatial_in: Point::zero(),
+            };
+            if *interpolation_type == KeyframeInterpolationType::Bezier {
+                for _ in 0..dimensionality {
+                    ease.bezier_out
+                        .push(next_point(bits, num_bits, BEZIER_PRECISION));
+                    ease.bezier_in
+                        .push(next_point(bits, num_bits, BEZIER_PRECISION));
                 }
             }
-            _ => {
-                if flag.exist {
-                    if flag.animatable {
-                        let key_frames = Vec::<String>::new();
-                        T::from_key_frames(key_frames.as_slice())
-                    } else {
-                        T::parse(parser, ()).ok()
-                    }
-                } else {
-                    None
+            ease
+        })
+        .collect::<Vec<_>>();
+
+    if has_spatial {
+        let flags = (0..eases.len() * 2)
+            .map(|_| bits.next())
+            .collect::<Vec<_>>();
+        let num_bits = bits.next_num_bits();
+        for (ease, flags) in eases.iter_mut().zip(flags.chunks(2)) {
+            if flags[0] {
+                ease.spatial_in = next_point(bits, num_bits, SPATIAL_PRECISION);
+            }
+            if flags[1] {
+                ease.spatial_out = next_point(bits, num_bits, SPATIAL_PRECISION);
+            }
+        }
+    }
+
+    eases
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{KeyframeInterpolationType, Point, Property};
+
+    use crate::parse::ParseErrorKind;
+
+    use super::{AttributeBlock, AttributeType};
+
+    /// 按 LSB 优先的顺序拼接比特位
+    fn pack_bits(fields: &[(i64, u8)]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut index = 0;
+        for &(value, num_bits) in fields {
+            for i in 0..num_bits {
+                if index % 8 == 0 {
+                    bytes.push(0u8);
+                }
+                if (value >> i) & 1 != 0 {
+                    *bytes.last_mut().unwrap() |= 1 << (index % 8);
                 }
+                index += 1;
             }
         }
+        bytes
+    }
+
+    #[test]
+    fn test_read_simple_property_keyframes() {
+        let mut input = pack_bits(&[(1, 1), (1, 1)]);
+        input.extend([1]); // numFrames
+        input.extend(pack_bits(&[(1, 2)])); // Linear
+        input.extend([0, 10]); // timeList
+        input.extend(0f32.to_le_bytes());
+        input.extend(100f32.to_le_bytes());
+        input.extend(pack_bits(&[(0, 5)])); // timeEaseNumBits
+
+        let mut block = AttributeBlock::new(&input);
+        let flag = block.flag(AttributeType::SimpleProperty);
+        let property = block
+            .read_property::<f32>("opacity", flag)
+            .unwrap()
+            .unwrap();
+
+        let keyframes = property.keyframes();
+        assert_eq!(keyframes.len(), 1);
+        assert_eq!(
+            keyframes[0].interpolation_type,
+            KeyframeInterpolationType::Linear
+        );
+        assert_eq!(keyframes[0].start_time, 0);
+        assert_eq!(keyframes[0].end_time, 10);
+        assert_eq!(keyframes[0].start_value, 0.);
+        assert_eq!(keyframes[0].end_value, 100.);
+    }
+
+    #[test]
+    fn test_read_spatial_property_keyframes() {
+        let mut input = pack_bits(&[(1, 1), (1, 1), (1, 1)]);
+        input.extend([1]); // numFrames
+        input.extend(pack_bits(&[(2, 2)])); // Bezier
+        input.extend([0, 20]); // timeList
+        input.extend(pack_bits(&[
+            // valueList: (0, 0) -> (10, 5)
+            (8, 5),
+            (0, 9),
+            (0, 9),
+            (200, 9),
+            (100, 9),
+            // timeEaseList: out (0.5, 0), in (0.5, 1)
+            (8, 5),
+            (100, 9),
+            (0, 9),
+            (100, 9),
+            (200, 9),
+            // spatialFlagList + spatialEaseList: in (1, -2)
+            (1, 1),
+            (0, 1),
+            (6, 5),
+            (20, 7),
+            (-40, 7),
+        ]));
+
+        let mut block = AttributeBlock::new(&input);
+        let flag = block.flag(AttributeType::SpatialProperty);
+        let property = block
+            .read_property::<Point>("position", flag)
+            .unwrap()
+            .unwrap();
+
+        let Property::Animated(keyframes) = property else {
+            panic!("property should be animated");
+        };
+        let keyframe = &keyframes[0];
+        assert_eq!(
+            keyframe.interpolation_type,
+            KeyframeInterpolationType::Bezier
+        );
+        assert_eq!(keyframe.end_time, 20);
+        assert_eq!(keyframe.start_value, Point::zero());
+        assert_eq!(keyframe.end_value, Point::new(10., 5.));
+        assert_eq!(keyframe.bezier_out, vec![Point::new(0.5, 0.)]);
+        assert_eq!(keyframe.bezier_in, vec![Point::new(0.5, 1.)]);
+        assert_eq!(keyframe.spatial_in, Point::new(1., -2.));
+        assert_eq!(keyframe.spatial_out, Point::zero());
+    }
+
+    #[test]
+    fn test_read_broken_keyframes() {
+        // 关键帧数据不完整
+        let mut input = pack_bits(&[(1, 1), (1, 1)]);
+        input.extend([1]); // numFrames
+        input.extend(pack_bits(&[(1, 2)])); // Linear
+        input.extend([0]); // timeList
+
+        let mut block = AttributeBlock::new(&input);
+        let flag = block.flag(AttributeType::SimpleProperty);
+        let error = block.read_property::<f32>("opacity", flag).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Eof));
+        assert_eq!(error.field, Some("opacity"));
+
+        // 含有关键帧的属性，关键帧数量不能为 0
+        let mut input = pack_bits(&[(1, 1), (1, 1)]);
+        input.extend([0]); // numFrames
+        input.extend([0; 16]);
+
+        let mut block = AttributeBlock::new(&input);
+        let flag = block.flag(AttributeType::SimpleProperty);
+        let error = block.read_property::<f32>("opacity", flag).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Message(_)));
+        assert_eq!(error.offset, Some(1));
+        assert_eq!(error.field, Some("opacity"));
     }
 }
@@ -1,11 +1,13 @@
 use std::{cmp::min, fmt::Debug, num::NonZeroUsize};
 
-use super::{parser::StreamParser, ParseError};
+use super::{error::ParseErrorKind, parser::StreamParser, ParseError};
 
 #[derive(Clone)]
 pub struct Bits<'a> {
     buffer: &'a [u8],
     index: usize,
+    /// buffer 起始位置在完整输入中的绝对偏移量
+    offset: usize,
 }
 
 impl<'a> Debug for Bits<'a> {
@@ -21,10 +23,23 @@ impl<'a> Debug for Bits<'a> {
 }
 
 impl<'a> Bits<'a> {
+    /// 连续数据编码中，头部区域存储 numBits 所占的比特位数
+    const LENGTH_FOR_STORE_NUM_BITS: u8 = 5;
+
     pub fn new(buffer: &'a [u8]) -> Self {
-        Self { buffer, index: 0 }
+        Self {
+            buffer,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    /// 指定 buffer 起始位置在完整输入中的绝对偏移量
+    pub fn with_offset(self, offset: usize) -> Self {
+        Self { offset, ..self }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> bool {
         let index = self.index;
         self.index += 1;
@@ -41,17 +56,64 @@ impl<'a> Bits<'a> {
         }
     }
 
+    /// 读取 UB[num_bits]
+    pub fn next_ubits(&mut self, num_bits: u8) -> u32 {
+        let mut value = 0u32;
+        for i in 0..num_bits {
+            if self.next() {
+                value |= 1 << i;
+            }
+        }
+        value
+    }
+
+    /// 读取 SB[num_bits]，最高位为符号位
+    pub fn next_sbits(&mut self, num_bits: u8) -> i32 {
+        if num_bits == 0 {
+            return 0;
+        }
+        let shift = 32 - num_bits as u32;
+        ((self.next_ubits(num_bits) << shift) as i32) >> shift
+    }
+
+    /// 读取连续数据编码的头部区域：UB[5] + 1
+    pub fn next_num_bits(&mut self) -> u8 {
+        self.next_ubits(Self::LENGTH_FOR_STORE_NUM_BITS) as u8 + 1
+    }
+
+    /// 读取连续无符号整数编码的列表
+    pub fn next_uint32_list(&mut self, count: usize) -> Vec<u32> {
+        let num_bits = self.next_num_bits();
+        (0..count).map(|_| self.next_ubits(num_bits)).collect()
+    }
+
+    /// 读取连续浮点数编码的列表，每个值为 SB[numBits] * precision
+    pub fn next_float_list(&mut self, count: usize, precision: f32) -> Vec<f32> {
+        let num_bits = self.next_num_bits();
+        (0..count)
+            .map(|_| self.next_sbits(num_bits) as f32 * precision)
+            .collect()
+    }
+
+    /// 已读取的比特位按字节对齐后所占的字节数
+    pub fn byte_length(&self) -> Result<usize, ParseError> {
+        let offset = self.index.div_ceil(8);
+        if offset > self.buffer.len() {
+            let needed = NonZeroUsize::new(offset - self.buffer.len()).unwrap();
+            return Err(
+                ParseError::from(ParseErrorKind::Incomplete(nom::Needed::Size(needed)))
+                    .with_offset(self.offset + self.buffer.len()),
+            );
+        }
+        Ok(offset)
+    }
+
     pub fn finish<'b>(self) -> Result<StreamParser<'b>, ParseError>
     where
         'a: 'b,
     {
-        let offset = (self.index + 7) / 8;
-        if offset > self.buffer.len() {
-            return Err(ParseError::Incomplete(nom::Needed::Size(
-                NonZeroUsize::new(offset - self.buffer.len()).unwrap(),
-            )));
-        }
+        let offset = self.byte_length()?;
         let buffer = &self.buffer[offset..];
-        Ok(StreamParser::new(buffer))
+        Ok(StreamParser::new(buffer).with_offset(self.offset + offset))
     }
 }
@@ -2,6 +2,17 @@ use std::fmt::Debug;
 
 use crate::format::{LayerType, TagCode};
 
+/// 解析选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// 宽松模式：标签内容解析失败时，保留原始数据及错误信息，并继续解析后续标签
+    pub lenient: bool,
+    /// 严格模式：标签内容解析完成后仍有未读取的数据时报错
+    pub strict: bool,
+    /// 无损模式：记录标签的原始内容，未修改的标签以无损模式写出时保持原有编码
+    pub lossless: bool,
+}
+
 pub trait ParseContext: Clone {
     fn with_tag_code(&self, tag_code: TagCode) -> DefaultParseContext;
     fn with_layer_type(&self, layer_type: LayerType) -> DefaultParseContext;
@@ -10,19 +21,40 @@ pub trait ParseContext: Clone {
     fn parent_code(&self) -> Option<TagCode>;
     fn layer_type(&self) -> Option<LayerType>;
     fn has_alpha(&self) -> bool;
+
+    /// 外层正在解析的标签，由外层到内层排列
+    fn tag_stack(&self) -> &[TagCode] {
+        &[]
+    }
+
+    fn options(&self) -> ParseOptions {
+        ParseOptions::default()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DefaultParseContext {
-    parent_code: Option<TagCode>,
+    tag_stack: Vec<TagCode>,
     layer_type: Option<LayerType>,
     has_alpha: bool,
+    options: ParseOptions,
+}
+
+impl DefaultParseContext {
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
 }
 
 impl ParseContext for DefaultParseContext {
     fn with_tag_code(&self, tag_code: TagCode) -> DefaultParseContext {
+        let mut tag_stack = self.tag_stack.clone();
+        tag_stack.push(tag_code);
         Self {
-            parent_code: Some(tag_code),
+            tag_stack,
             ..self.clone()
         }
     }
@@ -42,7 +74,7 @@ impl ParseContext for DefaultParseContext {
     }
 
     fn parent_code(&self) -> Option<TagCode> {
-        self.parent_code
+        self.tag_stack.last().copied()
     }
 
     fn layer_type(&self) -> Option<LayerType> {
@@ -52,30 +84,35 @@ impl ParseContext for DefaultParseContext {
     fn has_alpha(&self) -> bool {
         self.has_alpha
     }
+
+    fn tag_stack(&self) -> &[TagCode] {
+        &self.tag_stack
+    }
+
+    fn options(&self) -> ParseOptions {
+        self.options
+    }
 }
 
 impl ParseContext for () {
     fn with_tag_code(&self, tag_code: TagCode) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: Some(tag_code),
-            layer_type: None,
-            has_alpha: false,
+            tag_stack: vec![tag_code],
+            ..DefaultParseContext::default()
         }
     }
 
     fn with_layer_type(&self, layer_type: LayerType) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: None,
             layer_type: Some(layer_type),
-            has_alpha: false,
+            ..DefaultParseContext::default()
         }
     }
 
     fn with_alpha(&self, has_alpha: bool) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: None,
-            layer_type: None,
             has_alpha,
+            ..DefaultParseContext::default()
         }
     }
 
@@ -95,25 +132,22 @@ impl ParseContext for () {
 impl ParseContext for bool {
     fn with_tag_code(&self, tag_code: TagCode) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: Some(tag_code),
-            layer_type: None,
-            has_alpha: false,
+            tag_stack: vec![tag_code],
+            ..DefaultParseContext::default()
         }
     }
 
     fn with_layer_type(&self, layer_type: LayerType) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: None,
             layer_type: Some(layer_type),
-            has_alpha: false,
+            ..DefaultParseContext::default()
         }
     }
 
     fn with_alpha(&self, has_alpha: bool) -> DefaultParseContext {
         DefaultParseContext {
-            parent_code: None,
-            layer_type: None,
             has_alpha,
+            ..DefaultParseContext::default()
         }
     }
 
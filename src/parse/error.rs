@@ -1,25 +1,170 @@
+use std::fmt;
+
 use thiserror::Error;
 
+use crate::format::TagCode;
+
+/// 解析错误的类型
 #[derive(Debug, Error)]
-pub enum ParseError {
-    #[error("")]
+pub enum ParseErrorKind {
+    #[error("unsupported PAG version {0}")]
     UnsupportPagVersion(u8),
-    #[error("")]
+    #[error("incomplete data, {}", needed_message(.0))]
     Incomplete(nom::Needed),
-    #[error("")]
+    #[error("bad frame ({0:?})")]
     BadFrame(nom::error::ErrorKind),
-    #[error("")]
+    #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
-    #[error("")]
+    #[error("unexpected end of input")]
     Eof,
+    #[error("{remain} unconsumed bytes left in {code:?} body")]
+    UnconsumedBytes { code: TagCode, remain: usize },
+    /// 反序列化得到的错误，只保留错误信息
+    #[error("{0}")]
+    Message(String),
+}
+
+fn needed_message(needed: &nom::Needed) -> String {
+    match needed {
+        nom::Needed::Unknown => "more bytes needed".to_string(),
+        nom::Needed::Size(size) => format!("{} more bytes needed", size),
+    }
+}
+
+/// 解析错误，包含出错位置的上下文信息
+#[derive(Debug)]
+pub struct ParseError {
+    /// 错误类型
+    pub kind: ParseErrorKind,
+    /// 出错位置在完整输入中的绝对偏移量
+    pub offset: Option<usize>,
+    /// 出错时正在解析的标签，由外层到内层排列
+    pub tag_stack: Vec<TagCode>,
+    /// 出错时正在解析的字段名
+    pub field: Option<&'static str>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind) -> Self {
+        Self {
+            kind,
+            offset: None,
+            tag_stack: vec![],
+            field: None,
+        }
+    }
+
+    /// 记录出错位置，已记录更内层的位置时保持不变
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset.get_or_insert(offset);
+        self
+    }
+
+    /// 记录外层正在解析的标签，按解析栈展开的顺序依次插入到最前面
+    pub fn with_tag(mut self, code: TagCode) -> Self {
+        self.tag_stack.insert(0, code);
+        self
+    }
+
+    /// 记录出错的字段名，已记录更内层的字段时保持不变
+    pub fn with_field(mut self, field: &'static str) -> Self {
+        self.field.get_or_insert(field);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(field) = self.field {
+            write!(f, " while reading `{}`", field)?;
+        }
+        if !self.tag_stack.is_empty() {
+            let path = self
+                .tag_stack
+                .iter()
+                .map(|code| format!("{:?}", code))
+                .collect::<Vec<_>>();
+            write!(f, " in {}", path.join("/"))?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {:#x}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// 序列化为错误信息、偏移量及标签栈，反序列化时错误类型统一为 [`ParseErrorKind::Message`]
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParseError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ParseError", 4)?;
+        state.serialize_field("message", &self.kind.to_string())?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("tag_stack", &self.tag_stack)?;
+        state.serialize_field("field", &self.field)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ParseError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            message: String,
+            offset: Option<usize>,
+            tag_stack: Vec<TagCode>,
+            field: Option<String>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        // 字段名为 &'static str，合并到错误信息中
+        let message = match fields.field {
+            Some(field) => format!("{} while reading `{}`", fields.message, field),
+            None => fields.message,
+        };
+        Ok(Self {
+            kind: ParseErrorKind::Message(message),
+            offset: fields.offset,
+            tag_stack: fields.tag_stack,
+            field: None,
+        })
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(ParseErrorKind::IoError(error))
+    }
 }
 
 impl<'a> From<nom::Err<nom::error::Error<&'a [u8]>>> for ParseError {
     fn from(error: nom::Err<nom::error::Error<&'a [u8]>>) -> Self {
-        match error {
-            nom::Err::Incomplete(needed) => ParseError::Incomplete(needed),
-            nom::Err::Error(e) => ParseError::BadFrame(e.code),
-            nom::Err::Failure(e) => ParseError::BadFrame(e.code),
-        }
+        let kind = match error {
+            nom::Err::Incomplete(needed) => ParseErrorKind::Incomplete(needed),
+            nom::Err::Error(e) | nom::Err::Failure(e) => match e.code {
+                nom::error::ErrorKind::Eof => ParseErrorKind::Eof,
+                code => ParseErrorKind::BadFrame(code),
+            },
+        };
+        Self::new(kind)
     }
 }
@@ -4,16 +4,19 @@ mod context;
 mod error;
 mod parsable;
 mod parser;
+mod stream;
 mod types;
 
-pub use attr::{AttributeConfig, AttributeType};
-pub use context::ParseContext;
-pub use error::ParseError;
+pub use attr::{AttributeBlock, AttributeConfig, AttributeType};
+pub use bits::Bits;
+pub use context::{DefaultParseContext, ParseContext, ParseOptions};
+pub use error::{ParseError, ParseErrorKind};
 pub use parsable::Parsable;
 pub use parser::{Parser, StreamParser};
+pub use stream::AsyncPagParser;
 pub use types::*;
 
-use crate::{FileHeader, Pag, Tag};
+use crate::{FileHeader, Pag, PagRef, Tag};
 
 /// PAG File Parser
 #[derive(Debug)]
@@ -22,35 +25,57 @@ pub struct PagParser<'a> {
     header: FileHeader,
     /// The internal bytes based parser
     inner: StreamParser<'a>,
+    /// The options of parsing
+    options: ParseOptions,
 }
 
 impl<'a> PagParser<'a> {
     const DEFAULT_PAG_VERSION: u8 = 1;
+    /// Offset of the version in file header, right after "PAG"
+    const VERSION_OFFSET: usize = 3;
 
     /// Create new instance
     /// - input: the content of PAG file
     pub fn new(input: &'a [u8]) -> Result<Self, ParseError> {
-        let mut parser = StreamParser::new(input);
+        Self::with_options(input, ParseOptions::default())
+    }
 
-        // Parse and check file header
-        let header = FileHeader::parse(&mut parser, ())?;
-        if header.version != Self::DEFAULT_PAG_VERSION {
-            return Err(ParseError::UnsupportPagVersion(header.version));
-        }
+    /// Create new instance with options
+    /// - input: the content of PAG file
+    /// - options: e.g. lenient mode to keep broken tags and continue parsing,
+    ///   or strict mode to reject tag bodies with unconsumed bytes
+    pub fn with_options(input: &'a [u8], options: ParseOptions) -> Result<Self, ParseError> {
+        let mut parser = StreamParser::new(input);
+        let header = Self::parse_header(&mut parser)?;
 
         // Return parser
         Ok(Self {
             header,
             inner: parser,
+            options,
         })
     }
 
-    /// Parser next tag section
-    pub fn next_tag(&mut self) -> Option<Result<Tag, ParseError>> {
+    /// Parse and check file header
+    fn parse_header<'b>(parser: &mut impl Parser<'b>) -> Result<FileHeader, ParseError> {
+        let header = FileHeader::parse(parser, ())?;
+        if header.version != Self::DEFAULT_PAG_VERSION {
+            return Err(
+                ParseError::from(ParseErrorKind::UnsupportPagVersion(header.version))
+                    .with_offset(Self::VERSION_OFFSET)
+                    .with_field("version"),
+            );
+        }
+        Ok(header)
+    }
+
+    /// Parser next tag section, byte payloads of the tag borrow from the input
+    pub fn next_tag(&mut self) -> Option<Result<Tag<'a>, ParseError>> {
         if self.inner.is_empty() {
             None
         } else {
-            Some(Tag::parse(&mut self.inner, ()))
+            let ctx = DefaultParseContext::new(self.options);
+            Some(Tag::parse(&mut self.inner, ctx))
         }
     }
 }
@@ -58,8 +83,27 @@ impl<'a> PagParser<'a> {
 impl<'a> PagParser<'a> {
     /// Parse whole input to a Pag object
     pub fn parse_all(input: &'a [u8]) -> Result<Pag, ParseError> {
-        let mut parser = Self::new(input)?;
-        let mut pag = Pag::new(parser.header.clone());
+        Self::parse_all_with(input, ParseOptions::default())
+    }
+
+    /// Parse whole input to a Pag object with options
+    pub fn parse_all_with(input: &'a [u8], options: ParseOptions) -> Result<Pag, ParseError> {
+        Ok(Self::parse_ref_with(input, options)?.into_owned())
+    }
+
+    /// Parse whole input to a PagRef object without copying byte payloads,
+    /// images, videos and other binary data borrow from the input
+    pub fn parse_ref(input: &'a [u8]) -> Result<PagRef<'a>, ParseError> {
+        Self::parse_ref_with(input, ParseOptions::default())
+    }
+
+    /// Parse whole input to a PagRef object with options
+    pub fn parse_ref_with(
+        input: &'a [u8],
+        options: ParseOptions,
+    ) -> Result<PagRef<'a>, ParseError> {
+        let mut parser = Self::with_options(input, options)?;
+        let mut pag = PagRef::new(parser.header.clone());
         while let Some(tag) = parser.next_tag() {
             pag.push_tag(tag?);
         }
@@ -70,11 +114,21 @@ impl<'a> PagParser<'a> {
 #[cfg(test)]
 mod tests {
     use std::{
+        borrow::Cow,
         fs::{self},
         path::Path,
     };
 
-    use super::{PagParser, ParseError};
+    use crate::{
+        encode::{Encodable, PagWriter, StreamWriter, Writer},
+        ByteData, Color, CompositionAttributes, FileHeader, Pag, Tag, TagBlock, TagBody, TagCode,
+        VectorCompositionBlock,
+    };
+
+    use super::{
+        EncodedInt32, EncodedUint32, EncodedUint64, PagParser, ParseError, ParseErrorKind,
+        ParseOptions,
+    };
 
     #[test]
     fn test_parse_all_pag() -> Result<(), ParseError> {
@@ -107,9 +161,10 @@ mod tests {
             .try_init();
 
         // let name = "libpag/resources/apitest/ImageLayerBounds.pag";
-        let mut names = vec![];
-        // names.push("tests/12767246.pag");
-        names.push("tests/pags/12767270.pag");
+        let names = vec![
+            // "tests/12767246.pag",
+            "tests/pags/12767270.pag",
+        ];
         for name in names {
             match parse_single(Path::new(name)) {
                 Ok(_) => {}
@@ -145,4 +200,208 @@ mod tests {
 
         Ok(())
     }
+
+    /// 生成只包含一个 VectorCompositionBlock 的文件
+    fn new_pag(tags: Vec<Tag<'static>>) -> Pag {
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock { tags },
+        };
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        pag
+    }
+
+    fn new_attributes() -> Tag<'static> {
+        let attributes = CompositionAttributes {
+            width: EncodedInt32::from(100),
+            height: EncodedInt32::from(100),
+            duration: EncodedUint64::from(30),
+            frame_rate: 30.,
+            background_color: Color::WHITE,
+        };
+        Tag::new(
+            TagCode::CompositionAttributes,
+            TagBody::CompositionAttributes(attributes),
+        )
+    }
+
+    #[test]
+    fn test_parse_error_context() {
+        let mut input = PagWriter::write_all(&new_pag(vec![new_attributes()]));
+
+        // CompositionAttributes 的 TagHeader 位于文件头、VectorCompositionBlock 的 TagHeader 与 id 之后，
+        // 将其长度改为超出 VectorCompositionBlock 的内容范围
+        let (header, body) = (12, 14);
+        input[header] |= 0b0011_1110;
+
+        let error = PagParser::parse_all(&input).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Eof));
+        assert_eq!(error.offset, Some(body));
+        assert_eq!(
+            error.tag_stack,
+            [
+                TagCode::VectorCompositionBlock,
+                TagCode::CompositionAttributes
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of input in VectorCompositionBlock/CompositionAttributes at offset 0xe"
+        );
+
+        // 不支持的版本号
+        input[3] = 2;
+        let error = PagParser::parse_all(&input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unsupported PAG version 2 while reading `version` at offset 0x3"
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        // 内容不完整的 CompositionAttributes，解析 frame_rate 时数据不足
+        let broken = Tag::new(
+            TagCode::CompositionAttributes,
+            TagBody::Raw(ByteData::from(&[0xc8, 0x01, 0xc8, 0x01, 0x1e, 0x00])),
+        );
+        let input = PagWriter::write_all(&new_pag(vec![broken, new_attributes()]));
+        assert!(PagParser::parse_all(&input).is_err());
+
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let pag = PagParser::parse_all_with(&input, options).unwrap();
+        let diagnostics = pag.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].tag_stack,
+            [
+                TagCode::VectorCompositionBlock,
+                TagCode::CompositionAttributes
+            ]
+        );
+
+        let TagBody::VectorCompositionBlock(composition) = &pag.tag_block.tags[0].body else {
+            panic!("expect VectorCompositionBlock");
+        };
+        let tags = &composition.tag_block.tags;
+        assert_eq!(tags.len(), 2);
+        assert!(matches!(&tags[0].body, TagBody::Invalid { bytes, .. } if bytes.data.len() == 6));
+        assert!(matches!(tags[1].body, TagBody::CompositionAttributes(_)));
+
+        // 解析失败的标签按原始内容写出
+        assert_eq!(PagWriter::write_all(&pag), input);
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        // CompositionAttributes 的内容末尾多出 2 个字节
+        let mut writer = StreamWriter::new();
+        new_attributes().body.encode(&mut writer, ());
+        writer.write_u16(0);
+        let body = TagBody::Raw(ByteData::from_vec(writer.into_bytes()));
+        let input = PagWriter::write_all(&new_pag(vec![Tag::new(
+            TagCode::CompositionAttributes,
+            body,
+        )]));
+        assert!(PagParser::parse_all(&input).is_ok());
+
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = PagParser::parse_all_with(&input, options).unwrap_err();
+        assert!(matches!(
+            error.kind,
+            ParseErrorKind::UnconsumedBytes {
+                code: TagCode::CompositionAttributes,
+                remain: 2
+            }
+        ));
+
+        // 同时开启宽松模式时，记录为诊断信息并继续解析
+        let options = ParseOptions {
+            lenient: true,
+            strict: true,
+            ..Default::default()
+        };
+        let pag = PagParser::parse_all_with(&input, options).unwrap();
+        assert_eq!(pag.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_ref() {
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(Tag::new(
+            TagCode::Performance,
+            TagBody::Raw(ByteData::from_vec(vec![0x5a; 64])),
+        ));
+        pag.push_tag(Tag::EMPTY);
+        let input = PagWriter::write_all(&pag);
+
+        // 二进制数据直接引用输入数据
+        let pag = PagParser::parse_ref(&input).unwrap();
+        let TagBody::Raw(raw) = &pag.tag_block.tags[0].body else {
+            panic!("expect Raw, got {:?}", pag.tag_block.tags[0].body);
+        };
+        assert!(matches!(raw.data, Cow::Borrowed(_)));
+        assert!(input.as_ptr_range().contains(&raw.data.as_ptr()));
+
+        // 转换后不再依赖输入数据
+        let pag = pag.into_owned();
+        let output = PagWriter::write_all(&pag);
+        drop(input);
+        let TagBody::Raw(raw) = &pag.tag_block.tags[0].body else {
+            panic!("expect Raw");
+        };
+        assert!(matches!(raw.data, Cow::Owned(_)));
+        assert_eq!(*raw.data, [0x5a; 64]);
+        assert_eq!(
+            PagParser::parse_all(&output).unwrap().tag_block.tags.len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json() {
+        use crate::{ByteDataMode, WithByteDataMode};
+
+        let broken = Tag::new(
+            TagCode::CompositionAttributes,
+            TagBody::Raw(ByteData::from(&[0xc8, 0x01, 0xc8, 0x01, 0x1e, 0x00])),
+        );
+        let input = PagWriter::write_all(&new_pag(vec![broken, new_attributes()]));
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let pag = PagParser::parse_ref_with(&input, options).unwrap();
+
+        // 反序列化后写出的内容与原文件一致
+        let json = serde_json::to_string(&pag).unwrap();
+        assert!(json.contains(r#""data":"yAHIAR4A""#));
+        let loaded: Pag = serde_json::from_str(&json).unwrap();
+        assert_eq!(PagWriter::write_all(&loaded), input);
+        assert_eq!(
+            loaded.diagnostics()[0].tag_stack,
+            [
+                TagCode::VectorCompositionBlock,
+                TagCode::CompositionAttributes
+            ]
+        );
+
+        // 省略二进制数据时无法反序列化
+        let json = serde_json::to_string(&WithByteDataMode(&pag, ByteDataMode::Elided)).unwrap();
+        assert!(json.contains(r#"{"length":6}"#));
+        assert!(serde_json::from_str::<Pag>(&json).is_err());
+        assert!(serde_json::to_string(&pag).unwrap().contains(r#""data":"#));
+    }
 }
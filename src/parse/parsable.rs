@@ -1,66 +1,84 @@
-use super::{context::ParseContext, parser::Parser, ParseError};
+use super::{attr::AttributeType, bits::Bits, context::ParseContext, parser::Parser, ParseError};
 
 /// 可解析对象
-pub trait Parsable
+pub trait Parsable<'a>
 where
     Self: Sized,
 {
     /// 解析
-    fn parse(parser: &mut impl Parser, ctx: impl ParseContext) -> Result<Self, ParseError>;
+    fn parse(parser: &mut impl Parser<'a>, ctx: impl ParseContext) -> Result<Self, ParseError>;
 
     /// 直接从布尔值中解析
     fn from_bool(_value: bool) -> Option<Self> {
         None
     }
 
-    /// TODO:
-    fn from_key_frames(_value: &[String]) -> Option<Self> {
+    /// 关键帧的属性值列表按比特位存储时，直接从比特流中解析；否则返回 None，按字节依次解析
+    fn parse_value_bits(
+        _bits: &mut Bits,
+        _count: usize,
+        _type: AttributeType,
+    ) -> Option<Vec<Self>> {
         None
     }
+
+    /// 多维时间缓动参数的维度
+    fn dimensionality() -> usize {
+        1
+    }
 }
 
-impl Parsable for f32 {
+impl<'a> Parsable<'a> for f32 {
     #[inline(always)]
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         parser.next_f32()
     }
 }
 
-impl Parsable for u8 {
+impl<'a> Parsable<'a> for u8 {
     #[inline(always)]
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         parser.next_u8()
     }
+
+    fn parse_value_bits(bits: &mut Bits, count: usize, _type: AttributeType) -> Option<Vec<Self>> {
+        let list = bits.next_uint32_list(count);
+        Some(list.into_iter().map(|value| value as u8).collect())
+    }
 }
 
 // impl Parsable for u32 {
 //     #[inline(always)]
-//     fn parse(parser: &mut impl Parser, _ctx: impl ParserContext) -> Result<Self, ParseError> {
+//     fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParserContext) -> Result<Self, ParseError> {
 //         parser.next_encoded_u32()
 //     }
 // }
 
 // impl Parsable for u64 {
 //     #[inline(always)]
-//     fn parse(parser: &mut impl Parser, _ctx: impl ParserContext) -> Result<Self, ParseError> {
+//     fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParserContext) -> Result<Self, ParseError> {
 //         parser.next_encoded_u64()
 //     }
 // }
 
-impl Parsable for bool {
+impl<'a> Parsable<'a> for bool {
     #[inline(always)]
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         parser.next_bool()
     }
 
-    fn from_bool(_value: bool) -> Option<Self> {
-        Some(true)
+    fn from_bool(value: bool) -> Option<Self> {
+        Some(value)
+    }
+
+    fn parse_value_bits(bits: &mut Bits, count: usize, _type: AttributeType) -> Option<Vec<Self>> {
+        Some((0..count).map(|_| bits.next()).collect())
     }
 }
 
-impl Parsable for String {
+impl<'a> Parsable<'a> for String {
     #[inline(always)]
-    fn parse(parser: &mut impl Parser, _ctx: impl ParseContext) -> Result<Self, ParseError> {
+    fn parse(parser: &mut impl Parser<'a>, _ctx: impl ParseContext) -> Result<Self, ParseError> {
         parser.next_string()
     }
 }
@@ -4,6 +4,7 @@ use std::fmt::Debug;
 use nom::{
     bytes::complete::{tag, take, take_until},
     number::complete::{le_f32, le_i32, le_i64, le_i8, le_u16, le_u32, le_u64, le_u8},
+    IResult,
 };
 
 use super::{
@@ -13,8 +14,11 @@ use super::{
     Parsable, ParseError,
 };
 
-pub trait Parser {
-    fn buffer(&self) -> &[u8];
+pub trait Parser<'a> {
+    fn buffer(&self) -> &'a [u8];
+
+    /// 当前位置在完整输入中的绝对偏移量
+    fn offset(&self) -> usize;
 
     #[inline(always)]
     fn remain(&self) -> usize {
@@ -27,26 +31,38 @@ pub trait Parser {
     }
 
     #[inline(always)]
-    fn peek(&self, max_length: usize) -> &[u8] {
+    fn peek(&self, max_length: usize) -> &'a [u8] {
         let buffer = self.buffer();
         &buffer[0..min(max_length, buffer.len())]
     }
 
     fn advance(&mut self, count: usize);
 
-    fn new_slice<'b>(&'b mut self, length: usize) -> Result<StreamParser<'b>, ParseError>;
+    fn new_slice(&mut self, length: usize) -> Result<StreamParser<'a>, ParseError>;
 
     #[inline(always)]
-    fn new_attribute_block(&self) -> AttributeBlock {
-        AttributeBlock::new(self.buffer())
+    fn new_attribute_block(&self) -> AttributeBlock<'a> {
+        AttributeBlock::new(self.buffer()).with_offset(self.offset())
     }
 
     #[inline(always)]
-    fn new_bits(&mut self) -> Bits {
-        Bits::new(self.buffer())
+    fn new_bits(&mut self) -> Bits<'a> {
+        Bits::new(self.buffer()).with_offset(self.offset())
+    }
+
+    /// 读取一段 Bit 类型数据，结束后按字节对齐
+    fn next_bits<T>(&mut self, f: impl FnOnce(&mut Bits<'a>) -> T) -> Result<T, ParseError>
+    where
+        Self: Sized,
+    {
+        let mut bits = self.new_bits();
+        let value = f(&mut bits);
+        let length = bits.byte_length()?;
+        self.advance(length);
+        Ok(value)
     }
 
-    fn next_term<'b>(&'b mut self, tag: &str) -> Result<&'b [u8], ParseError>;
+    fn next_term(&mut self, tag: &str) -> Result<&'a [u8], ParseError>;
 
     fn next_u8(&mut self) -> Result<u8, ParseError>;
     fn next_i8(&mut self) -> Result<i8, ParseError>;
@@ -67,7 +83,7 @@ pub trait Parser {
     where
         T: From<u8> + Debug;
     fn next_string(&mut self) -> Result<String, ParseError>;
-    fn next_bytes(&mut self, count: usize) -> Result<&[u8], ParseError>;
+    fn next_bytes(&mut self, count: usize) -> Result<&'a [u8], ParseError>;
 
     #[inline(always)]
     fn next_id(&mut self) -> Result<EncodedUint32, ParseError> {
@@ -81,111 +97,123 @@ pub trait Parser {
     #[inline(always)]
     fn next<T>(&mut self) -> Result<T, ParseError>
     where
-        T: Parsable,
+        T: Parsable<'a>,
         Self: Sized,
     {
         T::parse(self, ())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StreamParser<'a> {
     input: &'a [u8],
+    /// input 起始位置在完整输入中的绝对偏移量
+    offset: usize,
 }
 
 impl<'a> StreamParser<'a> {
     pub fn new(input: &'a [u8]) -> Self {
-        Self { input }
+        Self { input, offset: 0 }
+    }
+
+    /// 指定 input 起始位置在完整输入中的绝对偏移量
+    pub fn with_offset(self, offset: usize) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// 执行 nom 解析函数，成功时前进到剩余数据处，失败时记录当前位置
+    fn run<T>(
+        &mut self,
+        f: impl FnOnce(&'a [u8]) -> IResult<&'a [u8], T>,
+    ) -> Result<T, ParseError> {
+        match f(self.input) {
+            Ok((input, value)) => {
+                self.offset += self.input.len() - input.len();
+                self.input = input;
+                Ok(value)
+            }
+            Err(e) => Err(ParseError::from(e).with_offset(self.offset)),
+        }
     }
 }
 
-impl<'a> Parser for StreamParser<'a> {
-    fn buffer(&self) -> &[u8] {
+impl<'a> Parser<'a> for StreamParser<'a> {
+    fn buffer(&self) -> &'a [u8] {
         self.input
     }
 
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
     fn advance(&mut self, count: usize) {
         self.input = &self.input[count..];
+        self.offset += count;
     }
 
     fn new_slice(&mut self, length: usize) -> Result<StreamParser<'a>, ParseError> {
-        let (input, slice) = take(length)(self.input)?;
-        self.input = input;
-        Ok(StreamParser { input: slice })
+        let offset = self.offset;
+        let slice = self.run(take(length))?;
+        Ok(StreamParser {
+            input: slice,
+            offset,
+        })
     }
 
-    fn next_term<'b>(&'b mut self, term: &str) -> Result<&'b [u8], ParseError> {
-        let (input, term) = tag(term)(self.input)?;
-        self.input = input;
-        Ok(term)
+    fn next_term(&mut self, term: &str) -> Result<&'a [u8], ParseError> {
+        self.run(tag(term))
     }
 
     fn next_u8(&mut self) -> Result<u8, ParseError> {
-        let (input, value) = le_u8(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_u8)
     }
 
     fn next_i8(&mut self) -> Result<i8, ParseError> {
-        let (input, value) = le_i8(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_i8)
     }
 
     fn next_u16(&mut self) -> Result<u16, ParseError> {
-        let (input, value) = le_u16(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_u16)
     }
 
     fn next_u32(&mut self) -> Result<u32, ParseError> {
-        let (input, value) = le_u32(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_u32)
     }
 
     fn next_i32(&mut self) -> Result<i32, ParseError> {
-        let (input, value) = le_i32(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_i32)
     }
 
     fn next_u64(&mut self) -> Result<u64, ParseError> {
-        let (input, value) = le_u64(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_u64)
     }
 
     fn next_i64(&mut self) -> Result<i64, ParseError> {
-        let (input, value) = le_i64(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_i64)
     }
 
     fn next_f32(&mut self) -> Result<f32, ParseError> {
-        let (input, value) = le_f32(self.input)?;
-        self.input = input;
-        Ok(value)
+        self.run(le_f32)
     }
 
     fn next_bool(&mut self) -> Result<bool, ParseError> {
-        let (input, value) = le_u8(self.input)?;
-        self.input = input;
+        let value = self.run(le_u8)?;
         Ok(value > 0)
     }
 
     fn next_encoded_u32(&mut self) -> Result<EncodedUint32, ParseError> {
-        let mut input = self.input;
-        let mut value = 0u32;
-        for i in (0..32).step_by(7) {
-            let (next, byte) = le_u8(input)?;
-            input = next;
-            value |= ((byte & 0x7f) as u32) << i;
-            if (byte & 0x80) == 0 {
-                break;
+        let value = self.run(|mut input| {
+            let mut value = 0u32;
+            for i in (0..32).step_by(7) {
+                let (next, byte) = le_u8(input)?;
+                input = next;
+                value |= ((byte & 0x7f) as u32) << i;
+                if (byte & 0x80) == 0 {
+                    break;
+                }
             }
-        }
-        self.input = input;
+            Ok((input, value))
+        })?;
         Ok(EncodedUint32::from(value))
     }
 
@@ -200,17 +228,18 @@ impl<'a> Parser for StreamParser<'a> {
     }
 
     fn next_encoded_u64(&mut self) -> Result<EncodedUint64, ParseError> {
-        let mut input = self.input;
-        let mut value = 0u64;
-        for i in (0..64).step_by(7) {
-            let (next, byte) = le_u8(input)?;
-            input = next;
-            value |= ((byte & 0x7f) as u64) << i;
-            if (byte & 0x80) == 0 {
-                break;
+        let value = self.run(|mut input| {
+            let mut value = 0u64;
+            for i in (0..64).step_by(7) {
+                let (next, byte) = le_u8(input)?;
+                input = next;
+                value |= ((byte & 0x7f) as u64) << i;
+                if (byte & 0x80) == 0 {
+                    break;
+                }
             }
-        }
-        self.input = input;
+            Ok((input, value))
+        })?;
         Ok(EncodedUint64::from(value))
     }
 
@@ -228,21 +257,22 @@ impl<'a> Parser for StreamParser<'a> {
     where
         T: From<u8> + Debug,
     {
-        let (input, value) = le_u8(self.input)?;
-        self.input = input;
+        let value = self.run(le_u8)?;
         Ok(T::from(value))
     }
 
     fn next_string(&mut self) -> Result<String, ParseError> {
-        let (input, value) = take_until("\0")(self.input)?;
-        self.input = input;
+        // 跳过结尾的 '\0'
+        let value = self.run(|input| {
+            let (input, value) = take_until("\0")(input)?;
+            let (input, _) = tag("\0")(input)?;
+            Ok((input, value))
+        })?;
         Ok(String::from_utf8_lossy(value).to_string())
     }
 
-    fn next_bytes(&mut self, count: usize) -> Result<&[u8], ParseError> {
-        let (input, data) = take(count)(self.input)?;
-        self.input = input;
-        Ok(data)
+    fn next_bytes(&mut self, count: usize) -> Result<&'a [u8], ParseError> {
+        self.run(take(count))
     }
 
     // fn next_tag_block(&mut self, ctx: impl ParserContext) -> Result<TagBlock, ParseError> {
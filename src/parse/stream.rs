@@ -0,0 +1,229 @@
+use std::io::ErrorKind;
+
+use futures::{stream, Stream};
+use nom::Needed;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{FileHeader, Tag};
+
+use super::{
+    DefaultParseContext, PagParser, Parsable, ParseError, ParseErrorKind, ParseOptions,
+    StreamParser,
+};
+
+/// Asynchronous PAG File Parser, which reads tags incrementally from an `AsyncRead`
+#[derive(Debug)]
+pub struct AsyncPagParser<R> {
+    /// The header of file
+    header: FileHeader,
+    /// The underlying reader
+    reader: R,
+    /// The absolute offset of the next byte to read
+    offset: usize,
+    /// The options of parsing
+    options: ParseOptions,
+}
+
+impl<R> AsyncPagParser<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Length of the file header: "PAG" + version + length + compress method
+    const FILE_HEADER_LENGTH: usize = 9;
+    /// Mask of the length in short-form tag header
+    const TAG_LENGTH_MASK: u16 = 0b0011_1111;
+
+    /// Create new instance, the file header is read immediately
+    /// - reader: the source of PAG file
+    pub async fn new(reader: R) -> Result<Self, ParseError> {
+        Self::with_options(reader, ParseOptions::default()).await
+    }
+
+    /// Create new instance with options
+    pub async fn with_options(reader: R, options: ParseOptions) -> Result<Self, ParseError> {
+        let mut parser = Self {
+            header: FileHeader {
+                version: 0,
+                length: 0,
+                compress_method: 0,
+            },
+            reader,
+            offset: 0,
+            options,
+        };
+
+        let mut buffer = vec![0; Self::FILE_HEADER_LENGTH];
+        parser.read_exact(&mut buffer).await?;
+        parser.header = PagParser::parse_header(&mut StreamParser::new(&buffer))?;
+        Ok(parser)
+    }
+
+    /// The header of file
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+
+    /// Parse next top-level tag section, only the bytes of this tag are read from the reader.
+    /// Return `None` when the reader reaches the end at a tag boundary.
+    pub async fn next_tag(&mut self) -> Option<Result<Tag<'static>, ParseError>> {
+        let start = self.offset;
+        let mut buffer = vec![0; 2];
+        match self.reader.read_u8().await {
+            Ok(value) => buffer[0] = value,
+            // Reader ends at a tag boundary
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(ParseError::from(e).with_offset(start))),
+        }
+        self.offset += 1;
+        if let Err(e) = self.read_exact(&mut buffer[1..]).await {
+            return Some(Err(e));
+        }
+
+        let code_and_length = u16::from_le_bytes([buffer[0], buffer[1]]);
+        let mut length = (code_and_length & Self::TAG_LENGTH_MASK) as usize;
+        if length == Self::TAG_LENGTH_MASK as usize {
+            let mut long_length = [0; 4];
+            if let Err(e) = self.read_exact(&mut long_length).await {
+                return Some(Err(e));
+            }
+            buffer.extend_from_slice(&long_length);
+            length = u32::from_le_bytes(long_length) as usize;
+        }
+
+        // 标签长度来自未校验的输入，不能超过文件头记录的剩余长度
+        let remain = (self.header.length as usize).saturating_sub(self.offset);
+        if length > remain {
+            let error = ParseError::from(ParseErrorKind::Incomplete(Needed::new(length - remain)));
+            return Some(Err(error.with_offset(start).with_field("length")));
+        }
+        if let Err(e) = self.read_body(&mut buffer, length).await {
+            return Some(Err(e));
+        }
+
+        let ctx = DefaultParseContext::new(self.options);
+        let mut parser = StreamParser::new(&buffer).with_offset(start);
+        // 读取的数据只在本次调用中有效，因此转换为持有数据的标签
+        Some(Tag::parse(&mut parser, ctx).map(Tag::into_owned))
+    }
+
+    /// Convert into a stream of top-level tags.
+    /// Bytes are only read when the stream is polled, and the stream ends after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Tag<'static>, ParseError>> {
+        stream::unfold(Some(self), |parser| async move {
+            let mut parser = parser?;
+            match parser.next_tag().await? {
+                Ok(tag) => Some((Ok(tag), Some(parser))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// 读取 length 字节追加到 buffer 中，缓冲区随实际读到的数据增长
+    async fn read_body(&mut self, buffer: &mut Vec<u8>, length: usize) -> Result<(), ParseError> {
+        let start = self.offset;
+        let read = (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(buffer)
+            .await
+            .map_err(|e| ParseError::from(e).with_offset(start))?;
+        self.offset += read;
+        match read < length {
+            true => Err(ParseError::from(ParseErrorKind::Eof).with_offset(start)),
+            false => Ok(()),
+        }
+    }
+
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), ParseError> {
+        match self.reader.read_exact(buffer).await {
+            Ok(_) => {
+                self.offset += buffer.len();
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                Err(ParseError::from(ParseErrorKind::Eof).with_offset(self.offset))
+            }
+            Err(e) => Err(ParseError::from(e).with_offset(self.offset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    use crate::{
+        encode::PagWriter, parse::ParseErrorKind, ByteData, FileHeader, Pag, Tag, TagBody, TagCode,
+    };
+
+    use super::AsyncPagParser;
+
+    fn new_pag() -> Pag {
+        let mut pag = Pag::new(FileHeader::default());
+        for (code, length) in [(TagCode::CachePolicy, 16), (TagCode::Performance, 100)] {
+            pag.push_tag(Tag::new(
+                code,
+                TagBody::Raw(ByteData::from_vec(vec![length as u8; length])),
+            ));
+        }
+        pag.push_tag(Tag::EMPTY);
+        pag
+    }
+
+    #[tokio::test]
+    async fn test_async_parse_stream() {
+        let input = PagWriter::write_all(&new_pag());
+
+        // 以较小的缓冲区分段写入，模拟数据逐步到达
+        let (mut writer, reader) = tokio::io::duplex(8);
+        let task = tokio::spawn(async move {
+            for chunk in input.chunks(5) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+
+        let parser = AsyncPagParser::new(reader).await.unwrap();
+        assert_eq!(parser.header().version, 1);
+        let tags = parser
+            .into_stream()
+            .map(|tag| tag.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        task.await.unwrap();
+
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[0].header.code, TagCode::CachePolicy);
+        assert!(tags[1].header.is_long);
+        assert!(matches!(&tags[1].body, TagBody::Raw(raw) if raw.data.len() == 100));
+        assert!(tags[2].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_async_parse_truncated() {
+        let input = PagWriter::write_all(&new_pag());
+        let input = &input[..input.len() - 10];
+
+        let parser = AsyncPagParser::new(input).await.unwrap();
+        let tags = parser.into_stream().collect::<Vec<_>>().await;
+        assert_eq!(tags.len(), 2);
+        let error = tags[1].as_ref().unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Eof));
+        assert_eq!(error.offset, Some(9 + 2 + 16 + 6));
+    }
+
+    #[tokio::test]
+    async fn test_async_parse_huge_length() {
+        // 文件头之后的标签声明了约 4GB 的长度，但数据在此处截断
+        let mut input = PagWriter::write_all(&new_pag())[..9].to_vec();
+        input.extend_from_slice(&u16::to_le_bytes(
+            u16::from(u8::from(TagCode::CachePolicy)) << 6 | 0x3f,
+        ));
+        input.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut parser = AsyncPagParser::new(&input[..]).await.unwrap();
+        let error = parser.next_tag().await.unwrap().unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Incomplete(_)));
+        assert_eq!(error.offset, Some(9));
+        assert_eq!(error.field, Some("length"));
+    }
+}
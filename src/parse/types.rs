@@ -7,7 +7,12 @@ use super::{Parsable, ParseContext, ParseError, Parser};
 
 macro_rules! define_encoded_num {
     ($t:ident, $r:ident) => {
-        #[derive(Clone, Copy)]
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
         pub struct $t($r);
 
         impl $t {
@@ -70,9 +75,9 @@ macro_rules! define_encoded_num {
             }
         }
 
-        impl Parsable for $t {
+        impl<'a> Parsable<'a> for $t {
             fn parse(
-                parser: &mut impl Parser,
+                parser: &mut impl Parser<'a>,
                 _ctx: impl ParseContext,
             ) -> Result<Self, ParseError> {
                 concat_idents!(fn_name = next_encoded_, $r {
@@ -89,3 +94,10 @@ define_encoded_num!(EncodedUint64, u64);
 define_encoded_num!(EncodedInt64, i64);
 
 pub type Time = EncodedUint64;
+
+/// 浮点数表示空间上的坐标点时的精度
+pub const SPATIAL_PRECISION: f32 = 0.05;
+/// 浮点数表示贝塞尔曲线缓动参数时的精度
+pub const BEZIER_PRECISION: f32 = 0.005;
+/// 浮点数表示渐变插值位置参数时的精度
+pub const GRADIENT_PRECISION: f32 = 0.00002;
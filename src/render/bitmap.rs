@@ -0,0 +1,235 @@
+use thiserror::Error;
+
+use crate::{BitmapRect, BitmapSequence};
+
+/// 位图尺寸过大，无法分配像素数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("bitmap size {width}x{height} is too large")]
+pub struct ImageSizeError {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// RGBA 格式的位图，每个像素 4 字节，按行从上到下存储
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// 全透明的位图，像素数据的长度溢出或无法分配时返回错误
+    pub fn new(width: u32, height: u32) -> Result<Self, ImageSizeError> {
+        let error = ImageSizeError { width, height };
+        let length = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or(error)?;
+        let mut data = vec![];
+        data.try_reserve_exact(length).map_err(|_| error)?;
+        data.resize(length, 0);
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    fn clear(&mut self) {
+        self.data.fill(0);
+    }
+
+    /// 将图片的像素复制到 (x, y) 处，超出画布的部分被裁剪
+    fn copy_from(&mut self, image: &RgbaImage, x: i32, y: i32) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let left = (x as i64).clamp(0, width);
+        let right = (x as i64 + image.width as i64).clamp(0, width);
+        if left >= right {
+            return;
+        }
+        let row_length = image.width as usize * 4;
+        let rows = image
+            .data
+            .chunks_exact(row_length)
+            .take(image.height as usize);
+        for (i, row) in rows.enumerate() {
+            let target_y = y as i64 + i as i64;
+            if target_y < 0 || target_y >= height {
+                continue;
+            }
+            let source = &row[(left - x as i64) as usize * 4..(right - x as i64) as usize * 4];
+            let start = (target_y * width + left) as usize * 4;
+            self.data[start..start + source.len()].copy_from_slice(source);
+        }
+    }
+}
+
+/// 逐帧还原位图序列帧的完整画面
+///
+/// 关键帧从全透明的画布开始绘制，其余帧在前一帧的基础上覆盖变化的矩形区域，
+/// 矩形区域的图片由 decoder 解码为 RGBA 位图
+#[derive(Debug)]
+pub struct BitmapFrames<'s, 'a, D> {
+    sequence: &'s BitmapSequence<'a>,
+    decoder: D,
+    canvas: RgbaImage,
+    index: usize,
+}
+
+impl<'s, 'a, D, E> BitmapFrames<'s, 'a, D>
+where
+    D: FnMut(&[u8]) -> Result<RgbaImage, E>,
+{
+    /// 序列帧尺寸过大，无法分配画布时返回错误
+    pub fn new(sequence: &'s BitmapSequence<'a>, decoder: D) -> Result<Self, ImageSizeError> {
+        let width = sequence.width.to_i32().max(0) as u32;
+        let height = sequence.height.to_i32().max(0) as u32;
+        Ok(Self {
+            sequence,
+            decoder,
+            canvas: RgbaImage::new(width, height)?,
+            index: 0,
+        })
+    }
+
+    fn draw(&mut self, bitmaps: &[BitmapRect]) -> Result<(), E> {
+        for bitmap in bitmaps {
+            let image = (self.decoder)(&bitmap.file_bytes.data)?;
+            self.canvas
+                .copy_from(&image, bitmap.x.to_i32(), bitmap.y.to_i32());
+        }
+        Ok(())
+    }
+}
+
+impl<D, E> Iterator for BitmapFrames<'_, '_, D>
+where
+    D: FnMut(&[u8]) -> Result<RgbaImage, E>,
+{
+    type Item = Result<RgbaImage, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.sequence.bitmap_frames.get(self.index)?;
+        if frame.is_key_frame || self.index == 0 {
+            self.canvas.clear();
+        }
+        self.index += 1;
+        Some(self.draw(&frame.bitmaps).map(|_| self.canvas.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remain = self.sequence.bitmap_frames.len() - self.index;
+        (remain, Some(remain))
+    }
+}
+
+impl<'a> BitmapSequence<'a> {
+    /// 按顺序还原每一帧的完整画面，decoder 将 PNG、WebP 等压缩图片解码为 RGBA 位图
+    pub fn frames<'s, D, E>(&'s self, decoder: D) -> Result<BitmapFrames<'s, 'a, D>, ImageSizeError>
+    where
+        D: FnMut(&[u8]) -> Result<RgbaImage, E>,
+    {
+        BitmapFrames::new(self, decoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32},
+        BitmapFrame, BitmapRect, BitmapSequence, ByteData,
+    };
+
+    use super::{ImageSizeError, RgbaImage};
+
+    fn new_rect(x: i32, y: i32, data: &'static [u8]) -> BitmapRect<'static> {
+        BitmapRect {
+            x: EncodedInt32::from(x),
+            y: EncodedInt32::from(y),
+            file_bytes: ByteData::from(data),
+        }
+    }
+
+    /// 测试用的解码器，图片数据为 [宽, 高, 颜色]
+    fn decode(data: &[u8]) -> Result<RgbaImage, String> {
+        let [width, height, color] = *data else {
+            return Err(format!("bad image {:?}", data));
+        };
+        let mut image = RgbaImage::new(width as u32, height as u32).map_err(|e| e.to_string())?;
+        image.data.fill(color);
+        Ok(image)
+    }
+
+    /// 每个像素的红色分量
+    fn pixels(image: &RgbaImage) -> Vec<u8> {
+        image.data.iter().step_by(4).copied().collect()
+    }
+
+    #[test]
+    fn test_bitmap_frames() {
+        let frames = [
+            (true, vec![new_rect(0, 0, &[3, 2, 1])]),
+            (false, vec![]),
+            // 超出画布的部分被裁剪
+            (false, vec![new_rect(2, 1, &[2, 2, 2])]),
+            (true, vec![new_rect(-1, 0, &[2, 1, 3])]),
+        ];
+        let sequence = BitmapSequence {
+            width: EncodedInt32::from(3),
+            height: EncodedInt32::from(2),
+            frame_rate: 24.,
+            frame_count: EncodedUint32::from(frames.len() as u32),
+            is_key_frame_flag: frames.iter().map(|(key, _)| *key).collect(),
+            bitmap_frames: frames
+                .into_iter()
+                .map(|(is_key_frame, bitmaps)| BitmapFrame {
+                    is_key_frame,
+                    bitmaps,
+                })
+                .collect(),
+        };
+
+        let images = sequence
+            .frames(decode)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            images.iter().map(pixels).collect::<Vec<_>>(),
+            [
+                [1, 1, 1, 1, 1, 1],
+                [1, 1, 1, 1, 1, 1],
+                [1, 1, 1, 1, 1, 2],
+                // 关键帧从全透明的画布开始绘制
+                [3, 0, 0, 0, 0, 0],
+            ]
+        );
+        assert_eq!((images[0].width, images[0].height), (3, 2));
+
+        let broken = BitmapSequence {
+            bitmap_frames: vec![BitmapFrame {
+                is_key_frame: true,
+                bitmaps: vec![new_rect(0, 0, &[0])],
+            }],
+            ..sequence
+        };
+        let mut frames = broken.frames(decode).unwrap();
+        assert_eq!(frames.next(), Some(Err("bad image [0]".to_string())));
+        assert_eq!(frames.next(), None);
+
+        // 画布的像素数据长度溢出
+        let huge = BitmapSequence {
+            width: EncodedInt32::from(i32::MAX),
+            height: EncodedInt32::from(i32::MAX),
+            ..broken
+        };
+        assert_eq!(
+            huge.frames(decode).err(),
+            Some(ImageSizeError {
+                width: i32::MAX as u32,
+                height: i32::MAX as u32,
+            })
+        );
+    }
+}
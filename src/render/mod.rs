@@ -0,0 +1,3 @@
+mod bitmap;
+
+pub use bitmap::{BitmapFrames, ImageSizeError, RgbaImage};
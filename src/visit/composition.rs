@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use super::LayerHierarchy;
+
+use crate::{
+    BitmapCompositionBlock, BitmapSequence, CompositionAttributes, CompositionReference,
+    LayerBlock, LayerType, TagBlock, TagBody, VectorCompositionBlock, VideoCompositionBlock,
+    VideoSequence,
+};
+
+/// A composition of any kind
+#[derive(Debug, Clone, Copy)]
+pub enum Composition<'b> {
+    Vector(&'b VectorCompositionBlock<'b>),
+    Video(&'b VideoCompositionBlock<'b>),
+    Bitmap(&'b BitmapCompositionBlock<'b>),
+}
+
+impl<'b> Composition<'b> {
+    /// Unique ID of the composition
+    pub fn id(&self) -> u32 {
+        match self {
+            Composition::Vector(block) => block.id.to_u32(),
+            Composition::Video(block) => block.id.to_u32(),
+            Composition::Bitmap(block) => block.id.to_u32(),
+        }
+    }
+
+    /// Child tags of the composition
+    pub fn tag_block(&self) -> &'b TagBlock<'b> {
+        match self {
+            Composition::Vector(block) => &block.tag_block,
+            Composition::Video(block) => &block.tag_block,
+            Composition::Bitmap(block) => &block.tag_block,
+        }
+    }
+
+    /// Basic attributes of the composition, e.g. size and duration
+    pub fn attributes(&self) -> Option<&'b CompositionAttributes> {
+        self.tag_block()
+            .tags
+            .iter()
+            .find_map(|tag| match &tag.body {
+                TagBody::CompositionAttributes(attributes) => Some(attributes),
+                _ => None,
+            })
+    }
+
+    /// Layers of the composition, only vector compositions have layers
+    pub fn layers(&self) -> impl Iterator<Item = &'b LayerBlock<'b>> {
+        let tags = match self {
+            Composition::Vector(block) => block.tag_block.tags.as_slice(),
+            _ => &[],
+        };
+        tags.iter().filter_map(|tag| match &tag.body {
+            TagBody::LayerBlock(layer) => Some(layer),
+            _ => None,
+        })
+    }
+
+    /// Video sequences of different sizes, only video compositions have sequences
+    pub fn video_sequences(&self) -> impl Iterator<Item = &'b VideoSequence<'b>> {
+        let tags = match self {
+            Composition::Video(block) => block.tag_block.tags.as_slice(),
+            _ => &[],
+        };
+        tags.iter().filter_map(|tag| match &tag.body {
+            TagBody::VideoSequence(sequence) => Some(sequence),
+            _ => None,
+        })
+    }
+
+    /// Bitmap sequences of different sizes, only bitmap compositions have sequences
+    pub fn bitmap_sequences(&self) -> impl Iterator<Item = &'b BitmapSequence<'b>> {
+        let tags = match self {
+            Composition::Bitmap(block) => block.tag_block.tags.as_slice(),
+            _ => &[],
+        };
+        tags.iter().filter_map(|tag| match &tag.body {
+            TagBody::BitmapSequence(sequence) => Some(sequence),
+            _ => None,
+        })
+    }
+
+    /// Audio of the composition, an MP4 file
+    pub fn audio_bytes(&self) -> Option<&'b [u8]> {
+        self.tag_block()
+            .tags
+            .iter()
+            .find_map(|tag| match &tag.body {
+                TagBody::AudioBytes(bytes) => Some(&*bytes.data),
+                _ => None,
+            })
+    }
+
+    /// Parent/child hierarchy of the layers
+    pub fn layer_hierarchy(&self) -> LayerHierarchy<'b> {
+        LayerHierarchy::new(self.layers())
+    }
+
+    fn from_body(body: &'b TagBody<'b>) -> Option<Self> {
+        match body {
+            TagBody::VectorCompositionBlock(block) => Some(Composition::Vector(block)),
+            TagBody::VideoCompositionBlock(block) => Some(Composition::Video(block)),
+            TagBody::BitmapCompositionBlock(block) => Some(Composition::Bitmap(block)),
+            _ => None,
+        }
+    }
+}
+
+/// A PreCompose layer and the composition it refers to
+#[derive(Debug, Clone, Copy)]
+pub struct Precompose<'b> {
+    /// The PreCompose layer
+    pub layer: &'b LayerBlock<'b>,
+    /// The reference tag of the layer
+    pub reference: &'b CompositionReference,
+    /// Index of the referred composition in the graph, `None` if the reference is dangling
+    target: Option<usize>,
+}
+
+/// A PreCompose layer referring to a composition that does not exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DanglingReference {
+    /// ID of the composition containing the layer
+    pub composition_id: u32,
+    /// ID of the PreCompose layer
+    pub layer_id: u32,
+    /// ID of the missing composition
+    pub reference_id: u32,
+}
+
+/// A composition visited by [`CompositionGraph::walk`]
+#[derive(Debug, Clone, Copy)]
+pub struct CompositionNode<'b> {
+    /// The composition
+    pub composition: Composition<'b>,
+    /// The PreCompose layer through which the composition is reached, `None` for the main composition
+    pub layer: Option<&'b LayerBlock<'b>>,
+    /// Nesting depth, 0 for the main composition
+    pub depth: usize,
+}
+
+#[derive(Debug)]
+struct Node<'b> {
+    composition: Composition<'b>,
+    children: Vec<Precompose<'b>>,
+}
+
+/// Nesting of compositions, linked by the `CompositionReference` of PreCompose layers
+#[derive(Debug)]
+pub struct CompositionGraph<'b> {
+    nodes: Vec<Node<'b>>,
+    index: HashMap<u32, usize>,
+    main: Option<usize>,
+    dangling_references: Vec<DanglingReference>,
+    cycles: Vec<Vec<u32>>,
+}
+
+impl<'b> CompositionGraph<'b> {
+    /// Build the graph from the top-level tags of a file.
+    /// The main composition is the last top-level composition.
+    pub fn new(tag_block: &'b TagBlock<'b>) -> Self {
+        let mut graph = Self {
+            nodes: vec![],
+            index: HashMap::new(),
+            main: None,
+            dangling_references: vec![],
+            cycles: vec![],
+        };
+        for tag in &tag_block.tags {
+            if let Some(composition) = Composition::from_body(&tag.body) {
+                graph.main = Some(graph.nodes.len());
+                graph.collect(composition);
+            }
+        }
+        graph.link();
+        graph.cycles = graph.find_cycles();
+        graph
+    }
+
+    /// Collect the composition and the compositions nested in its tags
+    fn collect(&mut self, composition: Composition<'b>) {
+        if self.index.contains_key(&composition.id()) {
+            log::warn!("duplicated composition id {}", composition.id());
+        } else {
+            self.index.insert(composition.id(), self.nodes.len());
+        }
+        self.nodes.push(Node {
+            composition,
+            children: vec![],
+        });
+        for tag in &composition.tag_block().tags {
+            if let Some(composition) = Composition::from_body(&tag.body) {
+                self.collect(composition);
+            }
+        }
+    }
+
+    /// Resolve the references of PreCompose layers
+    fn link(&mut self) {
+        for i in 0..self.nodes.len() {
+            let composition = self.nodes[i].composition;
+            for layer in composition.layers() {
+                if layer.r#type != LayerType::PreCompose {
+                    continue;
+                }
+                let Some(reference) = composition_reference(layer) else {
+                    continue;
+                };
+
+                let target = self.index.get(&reference.id.to_u32()).copied();
+                if target.is_none() {
+                    self.dangling_references.push(DanglingReference {
+                        composition_id: composition.id(),
+                        layer_id: layer.id.to_u32(),
+                        reference_id: reference.id.to_u32(),
+                    });
+                }
+                self.nodes[i].children.push(Precompose {
+                    layer,
+                    reference,
+                    target,
+                });
+            }
+        }
+    }
+
+    /// Find all reference cycles by depth-first search, each cycle is reported once
+    fn find_cycles(&self) -> Vec<Vec<u32>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            Visiting,
+            Visited,
+        }
+
+        fn visit(
+            graph: &CompositionGraph,
+            node: usize,
+            states: &mut [State],
+            stack: &mut Vec<usize>,
+            cycles: &mut Vec<Vec<u32>>,
+        ) {
+            states[node] = State::Visiting;
+            stack.push(node);
+            for child in graph.nodes[node]
+                .children
+                .iter()
+                .filter_map(|link| link.target)
+            {
+                match states[child] {
+                    State::Unvisited => visit(graph, child, states, stack, cycles),
+                    State::Visiting => {
+                        let start = stack.iter().position(|&i| i == child).unwrap_or(0);
+                        let cycle = stack[start..]
+                            .iter()
+                            .map(|&i| graph.nodes[i].composition.id())
+                            .collect();
+                        cycles.push(cycle);
+                    }
+                    State::Visited => {}
+                }
+            }
+            stack.pop();
+            states[node] = State::Visited;
+        }
+
+        let mut states = vec![State::Unvisited; self.nodes.len()];
+        let mut cycles = vec![];
+        for node in 0..self.nodes.len() {
+            if states[node] == State::Unvisited {
+                visit(self, node, &mut states, &mut vec![], &mut cycles);
+            }
+        }
+        cycles
+    }
+
+    /// The main composition, i.e. the last top-level composition
+    pub fn main(&self) -> Option<Composition<'b>> {
+        self.main.map(|i| self.nodes[i].composition)
+    }
+
+    /// Find composition by ID
+    pub fn get(&self, id: u32) -> Option<Composition<'b>> {
+        self.index.get(&id).map(|&i| self.nodes[i].composition)
+    }
+
+    /// All compositions in file order
+    pub fn compositions(&self) -> impl Iterator<Item = Composition<'b>> + '_ {
+        self.nodes.iter().map(|node| node.composition)
+    }
+
+    /// PreCompose layers of a composition
+    pub fn precomposes(&self, id: u32) -> &[Precompose<'b>] {
+        match self.index.get(&id) {
+            Some(&i) => &self.nodes[i].children,
+            None => &[],
+        }
+    }
+
+    /// The composition referred by a PreCompose layer, `None` if the reference is dangling
+    pub fn resolve(&self, precompose: &Precompose<'b>) -> Option<Composition<'b>> {
+        precompose.target.map(|i| self.nodes[i].composition)
+    }
+
+    /// PreCompose layers referring to compositions that do not exist
+    pub fn dangling_references(&self) -> &[DanglingReference] {
+        &self.dangling_references
+    }
+
+    /// Reference cycles, each listed as composition IDs in reference order,
+    /// e.g. `[1, 2]` for 1 → 2 → 1
+    pub fn cycles(&self) -> &[Vec<u32>] {
+        &self.cycles
+    }
+
+    /// Walk the composition tree from the main composition in depth-first order.
+    /// A composition is visited once per PreCompose layer that reaches it;
+    /// dangling references and references closing a cycle are skipped.
+    pub fn walk<F>(&self, mut visitor: F)
+    where
+        F: FnMut(&CompositionNode<'b>),
+    {
+        if let Some(main) = self.main {
+            self.walk_node(main, None, &mut vec![], &mut visitor);
+        }
+    }
+
+    fn walk_node<F>(
+        &self,
+        node: usize,
+        layer: Option<&'b LayerBlock<'b>>,
+        stack: &mut Vec<usize>,
+        visitor: &mut F,
+    ) where
+        F: FnMut(&CompositionNode<'b>),
+    {
+        visitor(&CompositionNode {
+            composition: self.nodes[node].composition,
+            layer,
+            depth: stack.len(),
+        });
+        stack.push(node);
+        for link in &self.nodes[node].children {
+            match link.target {
+                Some(child) if !stack.contains(&child) => {
+                    self.walk_node(child, Some(link.layer), stack, visitor)
+                }
+                _ => {}
+            }
+        }
+        stack.pop();
+    }
+}
+
+/// The `CompositionReference` tag of a PreCompose layer
+pub fn composition_reference<'b>(layer: &'b LayerBlock<'_>) -> Option<&'b CompositionReference> {
+    layer.tag_block.tags.iter().find_map(|tag| match &tag.body {
+        TagBody::CompositionReference(reference) => Some(reference),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedUint32, Time},
+        CompositionReference, FileHeader, LayerBlock, LayerType, Pag, Tag, TagBlock, TagBody,
+        TagCode, VectorCompositionBlock,
+    };
+
+    /// Create a VectorCompositionBlock with a PreCompose layer for each reference
+    fn new_composition(id: u32, references: &[u32]) -> Tag<'static> {
+        let tags = references
+            .iter()
+            .enumerate()
+            .map(|(i, &reference)| {
+                let reference = CompositionReference {
+                    id: EncodedUint32::from(reference),
+                    composition_start_time: Time::from(0),
+                };
+                let layer = LayerBlock {
+                    r#type: LayerType::PreCompose,
+                    id: EncodedUint32::from(id * 100 + i as u32),
+                    tag_block: TagBlock {
+                        tags: vec![Tag::new(
+                            TagCode::CompositionReference,
+                            TagBody::CompositionReference(reference),
+                        )],
+                    },
+                };
+                Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer))
+            })
+            .collect();
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(id),
+            tag_block: TagBlock { tags },
+        };
+        Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        )
+    }
+
+    fn new_pag(compositions: Vec<Tag<'static>>) -> Pag {
+        let mut pag = Pag::new(FileHeader::default());
+        for composition in compositions {
+            pag.push_tag(composition);
+        }
+        pag
+    }
+
+    #[test]
+    fn test_composition_tree() {
+        let pag = new_pag(vec![
+            new_composition(3, &[]),
+            new_composition(2, &[3, 4]),
+            new_composition(1, &[2, 3]),
+        ]);
+        let graph = pag.composition_graph();
+        assert_eq!(graph.main().unwrap().id(), 1);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(graph.dangling_references().len(), 1);
+        assert_eq!(graph.dangling_references()[0].reference_id, 4);
+        assert_eq!(graph.dangling_references()[0].layer_id, 201);
+
+        let mut nodes = vec![];
+        graph.walk(|node| {
+            nodes.push((
+                node.composition.id(),
+                node.layer.map(|layer| layer.id.to_u32()),
+                node.depth,
+            ))
+        });
+        assert_eq!(
+            nodes,
+            [
+                (1, None, 0),
+                (2, Some(100), 1),
+                (3, Some(200), 2),
+                (3, Some(101), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_composition_cycle() {
+        let pag = new_pag(vec![
+            new_composition(3, &[2]),
+            new_composition(2, &[3]),
+            new_composition(1, &[2, 1]),
+        ]);
+        let graph = pag.composition_graph();
+        assert_eq!(graph.cycles(), [vec![3, 2], vec![1]]);
+
+        // References closing a cycle are not expanded
+        let mut count = 0;
+        graph.walk(|_| count += 1);
+        assert_eq!(count, 3);
+    }
+}
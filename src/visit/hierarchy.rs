@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use crate::LayerBlock;
+
+/// A layer whose parent does not exist in the same composition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DanglingParent {
+    /// ID of the layer
+    pub layer_id: u32,
+    /// ID of the missing parent layer
+    pub parent_id: u32,
+}
+
+/// Parent/child hierarchy of the layers in a composition, linked by `LayerAttributes::parent`
+#[derive(Debug)]
+pub struct LayerHierarchy<'b> {
+    layers: Vec<&'b LayerBlock<'b>>,
+    index: HashMap<u32, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    dangling_parents: Vec<DanglingParent>,
+    cycles: Vec<Vec<u32>>,
+}
+
+impl<'b> LayerHierarchy<'b> {
+    /// Build the hierarchy from the layers of one composition.
+    /// Links closing a parent cycle are dropped, so every ancestor walk terminates.
+    pub fn new(layers: impl IntoIterator<Item = &'b LayerBlock<'b>>) -> Self {
+        let layers = layers.into_iter().collect::<Vec<_>>();
+        let mut index = HashMap::new();
+        for (i, layer) in layers.iter().enumerate() {
+            index.entry(layer.id.to_u32()).or_insert(i);
+        }
+
+        let mut dangling_parents = vec![];
+        let mut parents = layers
+            .iter()
+            .map(|layer| {
+                let parent_id = layer.attributes()?.parent.to_u32();
+                if parent_id == 0 {
+                    return None;
+                }
+                let parent = index.get(&parent_id).copied();
+                if parent.is_none() {
+                    dangling_parents.push(DanglingParent {
+                        layer_id: layer.id.to_u32(),
+                        parent_id,
+                    });
+                }
+                parent
+            })
+            .collect::<Vec<_>>();
+        let cycles = Self::break_cycles(&layers, &mut parents);
+
+        let mut children = vec![vec![]; layers.len()];
+        for (i, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children[*parent].push(i);
+            }
+        }
+
+        Self {
+            layers,
+            index,
+            parents,
+            children,
+            dangling_parents,
+            cycles,
+        }
+    }
+
+    /// Follow the parent chain of every layer, drop the link closing each cycle
+    fn break_cycles(layers: &[&LayerBlock], parents: &mut [Option<usize>]) -> Vec<Vec<u32>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            Visiting,
+            Visited,
+        }
+
+        let mut states = vec![State::Unvisited; layers.len()];
+        let mut cycles = vec![];
+        for start in 0..layers.len() {
+            let mut path = vec![];
+            let mut current = Some(start);
+            while let Some(i) = current.filter(|&i| states[i] == State::Unvisited) {
+                states[i] = State::Visiting;
+                path.push(i);
+                current = parents[i];
+            }
+            if let Some(i) = current.filter(|&i| states[i] == State::Visiting) {
+                let position = path.iter().position(|&j| j == i).unwrap_or(0);
+                let cycle = path[position..]
+                    .iter()
+                    .map(|&j| layers[j].id.to_u32())
+                    .collect::<Vec<_>>();
+                log::warn!("layer parent cycle {:?}", cycle);
+                cycles.push(cycle);
+                if let Some(&last) = path.last() {
+                    parents[last] = None;
+                }
+            }
+            for i in path {
+                states[i] = State::Visited;
+            }
+        }
+        cycles
+    }
+
+    /// Find layer by ID
+    pub fn layer(&self, id: u32) -> Option<&'b LayerBlock<'b>> {
+        self.index.get(&id).map(|&i| self.layers[i])
+    }
+
+    /// Parent of a layer
+    pub fn parent(&self, id: u32) -> Option<&'b LayerBlock<'b>> {
+        let parent = self.parents[*self.index.get(&id)?]?;
+        Some(self.layers[parent])
+    }
+
+    /// Direct children of a layer, i.e. the layers following it
+    pub fn children(&self, id: u32) -> impl Iterator<Item = &'b LayerBlock<'b>> + '_ {
+        let children = match self.index.get(&id) {
+            Some(&i) => self.children[i].as_slice(),
+            None => &[],
+        };
+        children.iter().map(|&i| self.layers[i])
+    }
+
+    /// Ancestors of a layer from its parent up to the root, e.g. for concatenating transforms
+    pub fn ancestors(&self, id: u32) -> impl Iterator<Item = &'b LayerBlock<'b>> + '_ {
+        let mut current = self.index.get(&id).copied();
+        std::iter::from_fn(move || {
+            current = self.parents[current?];
+            current.map(|i| self.layers[i])
+        })
+    }
+
+    /// Layers without a parent, in composition order
+    pub fn roots(&self) -> impl Iterator<Item = &'b LayerBlock<'b>> + '_ {
+        self.layers
+            .iter()
+            .zip(&self.parents)
+            .filter(|(_, parent)| parent.is_none())
+            .map(|(layer, _)| *layer)
+    }
+
+    /// Layers whose parent does not exist
+    pub fn dangling_parents(&self) -> &[DanglingParent] {
+        &self.dangling_parents
+    }
+
+    /// Parent cycles, each listed as layer IDs from child to parent,
+    /// e.g. `[1, 2]` when the parent of 1 is 2 and the parent of 2 is 1
+    pub fn cycles(&self) -> &[Vec<u32>] {
+        &self.cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedUint32, Time},
+        BlendMode, LayerAttributes, LayerBlock, LayerType, Property, Ratio, Tag, TagBlock, TagBody,
+        TagCode, TrackMatteType,
+    };
+
+    use super::LayerHierarchy;
+
+    fn new_layer(id: u32, parent: u32) -> LayerBlock<'static> {
+        let attributes = LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(parent),
+            stretch: Ratio::one(),
+            start_time: Time::from(0),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::Value(0.),
+            duration: Time::from(1),
+            name: String::new(),
+        };
+        LayerBlock {
+            r#type: LayerType::Null,
+            id: EncodedUint32::from(id),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::LayerAttributes,
+                    TagBody::LayerAttributes(attributes),
+                )],
+            },
+        }
+    }
+
+    fn ids<'b>(layers: impl Iterator<Item = &'b LayerBlock<'b>>) -> Vec<u32> {
+        layers.map(|layer| layer.id.to_u32()).collect()
+    }
+
+    #[test]
+    fn test_layer_hierarchy() {
+        let layers = [
+            new_layer(1, 0),
+            new_layer(2, 1),
+            new_layer(3, 2),
+            new_layer(4, 1),
+            new_layer(5, 9),
+        ];
+        let hierarchy = LayerHierarchy::new(&layers);
+        assert_eq!(ids(hierarchy.ancestors(3)), [2, 1]);
+        assert_eq!(ids(hierarchy.children(1)), [2, 4]);
+        assert_eq!(ids(hierarchy.roots()), [1, 5]);
+        assert_eq!(hierarchy.parent(4).unwrap().id.to_u32(), 1);
+        assert!(hierarchy.parent(1).is_none());
+        assert_eq!(hierarchy.dangling_parents().len(), 1);
+        assert_eq!(hierarchy.dangling_parents()[0].parent_id, 9);
+        assert!(hierarchy.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_layer_hierarchy_cycle() {
+        let layers = [
+            new_layer(1, 3),
+            new_layer(2, 1),
+            new_layer(3, 2),
+            new_layer(4, 3),
+            new_layer(5, 5),
+        ];
+        let hierarchy = LayerHierarchy::new(&layers);
+        assert_eq!(hierarchy.cycles(), [vec![1, 3, 2], vec![5]]);
+        // The link closing the cycle is dropped
+        assert_eq!(ids(hierarchy.ancestors(4)), [3, 2]);
+        assert_eq!(ids(hierarchy.ancestors(5)), Vec::<u32>::new());
+    }
+}
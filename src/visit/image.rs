@@ -0,0 +1,211 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{ImageBytes, ImageBytes2, ImageBytes3, ImageReference, LayerBlock, TagBlock, TagBody};
+
+/// Transparent border trimmed from an image, recorded by `ImageBytes3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageTrim {
+    /// Width of the original image before trimming
+    pub width: i32,
+    /// Height of the original image before trimming
+    pub height: i32,
+    /// X offset of the stored image inside the original image
+    pub anchor_x: i32,
+    /// Y offset of the stored image inside the original image
+    pub anchor_y: i32,
+}
+
+/// Image payload resolved by ID
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageAsset<'b> {
+    /// Unique ID of the image
+    pub id: u32,
+    /// Encoded image file, e.g. WebP, PNG or JPEG
+    pub file_bytes: &'b [u8],
+    /// Scale factor of the stored image relative to its original size, 1.0 if not recorded
+    pub scale_factor: f32,
+    /// Trim info, only recorded by `ImageBytes3`
+    pub trim: Option<ImageTrim>,
+}
+
+impl<'b> From<&'b ImageBytes<'_>> for ImageAsset<'b> {
+    fn from(image: &'b ImageBytes<'_>) -> Self {
+        Self {
+            id: image.id.to_u32(),
+            file_bytes: &image.file_bytes.data,
+            scale_factor: 1.0,
+            trim: None,
+        }
+    }
+}
+
+impl<'b> From<&'b ImageBytes2<'_>> for ImageAsset<'b> {
+    fn from(image: &'b ImageBytes2<'_>) -> Self {
+        Self {
+            id: image.id.to_u32(),
+            file_bytes: &image.file_bytes.data,
+            scale_factor: image.scale_factor,
+            trim: None,
+        }
+    }
+}
+
+impl<'b> From<&'b ImageBytes3<'_>> for ImageAsset<'b> {
+    fn from(image: &'b ImageBytes3<'_>) -> Self {
+        Self {
+            id: image.id.to_u32(),
+            file_bytes: &image.file_bytes.data,
+            scale_factor: image.scale_factor,
+            trim: Some(ImageTrim {
+                width: image.width.to_i32(),
+                height: image.height.to_i32(),
+                anchor_x: image.anchor_x.to_i32(),
+                anchor_y: image.anchor_y.to_i32(),
+            }),
+        }
+    }
+}
+
+/// Index from image IDs to image payloads of the whole document
+#[derive(Debug, Default)]
+pub struct ImageIndex<'b> {
+    images: HashMap<u32, ImageAsset<'b>>,
+}
+
+impl<'b> ImageIndex<'b> {
+    /// Build the index from all `ImageTables` and `ImageBytes{,2,3}` tags in the tag tree.
+    /// If an ID appears more than once, the first image wins.
+    pub fn new(tag_block: &'b TagBlock<'_>) -> Self {
+        let mut index = Self::default();
+        index.collect(tag_block);
+        index
+    }
+
+    fn collect(&mut self, tag_block: &'b TagBlock<'_>) {
+        for tag in &tag_block.tags {
+            match &tag.body {
+                TagBody::ImageTables(tables) => {
+                    for image in &tables.images {
+                        self.insert(image.into());
+                    }
+                }
+                TagBody::ImageBytes(image) => self.insert(image.into()),
+                TagBody::ImageBytes2(image) => self.insert(image.into()),
+                TagBody::ImageBytes3(image) => self.insert(image.into()),
+                body => {
+                    if let Some(tag_block) = body.tag_block() {
+                        self.collect(tag_block);
+                    }
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, image: ImageAsset<'b>) {
+        match self.images.entry(image.id) {
+            Entry::Occupied(_) => log::warn!("duplicated image id {}", image.id),
+            Entry::Vacant(entry) => {
+                entry.insert(image);
+            }
+        }
+    }
+
+    /// Find image by ID
+    pub fn get(&self, id: u32) -> Option<&ImageAsset<'b>> {
+        self.images.get(&id)
+    }
+
+    /// Find the image referenced by an image layer
+    pub fn for_layer(&self, layer: &LayerBlock<'_>) -> Option<&ImageAsset<'b>> {
+        self.get(image_reference(layer)?.id.to_u32())
+    }
+
+    /// Number of images
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether there is no image
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Iterate over all images in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = &ImageAsset<'b>> {
+        self.images.values()
+    }
+}
+
+/// The `ImageReference` tag of an image layer
+pub fn image_reference<'b>(layer: &'b LayerBlock<'_>) -> Option<&'b ImageReference> {
+    layer.tag_block.tags.iter().find_map(|tag| match &tag.body {
+        TagBody::ImageReference(reference) => Some(reference),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encode::PagWriter,
+        parse::{EncodedInt32, EncodedUint32, PagParser},
+        ByteData, FileHeader, ImageBytes, ImageBytes3, ImageReference, ImageTables, LayerBlock,
+        LayerType, Pag, Tag, TagBlock, TagBody, TagCode,
+    };
+
+    fn new_layer(id: u32, image_id: u32) -> LayerBlock<'static> {
+        let reference = ImageReference {
+            id: EncodedUint32::from(image_id),
+        };
+        LayerBlock {
+            r#type: LayerType::Image,
+            id: EncodedUint32::from(id),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::ImageReference,
+                    TagBody::ImageReference(reference),
+                )],
+            },
+        }
+    }
+
+    #[test]
+    fn test_image_for_layer() {
+        let mut pag = Pag::new(FileHeader::default());
+        let tables = ImageTables {
+            count: EncodedInt32::from(1),
+            images: vec![ImageBytes {
+                id: EncodedUint32::from(1),
+                file_bytes: ByteData::from_vec(vec![1; 4]),
+            }],
+        };
+        pag.push_tag(Tag::new(TagCode::ImageTables, TagBody::ImageTables(tables)));
+        let image = ImageBytes3 {
+            id: EncodedUint32::from(2),
+            file_bytes: ByteData::from_vec(vec![2; 8]),
+            scale_factor: 0.5,
+            width: EncodedInt32::from(100),
+            height: EncodedInt32::from(80),
+            anchor_x: EncodedInt32::from(10),
+            anchor_y: EncodedInt32::from(20),
+        };
+        pag.push_tag(Tag::new(TagCode::ImageBytes3, TagBody::ImageBytes3(image)));
+        pag.push_tag(Tag::EMPTY);
+        let input = PagWriter::write_all(&pag);
+        let pag = PagParser::parse_ref(&input).unwrap();
+
+        let index = pag.image_index();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get(1).unwrap().file_bytes, [1; 4]);
+        assert_eq!(index.get(1).unwrap().scale_factor, 1.0);
+
+        let image = pag.image_for_layer(&new_layer(1, 2)).unwrap();
+        assert_eq!(image.file_bytes, [2; 8]);
+        assert_eq!(image.scale_factor, 0.5);
+        let trim = image.trim.unwrap();
+        assert_eq!((trim.width, trim.height), (100, 80));
+        assert_eq!((trim.anchor_x, trim.anchor_y), (10, 20));
+
+        assert!(pag.image_for_layer(&new_layer(2, 3)).is_none());
+    }
+}
@@ -1,4 +1,23 @@
-use crate::LayerType;
+mod composition;
+mod hierarchy;
+mod image;
+mod time;
+mod transform;
+mod video;
+
+pub use composition::{
+    composition_reference, Composition, CompositionGraph, CompositionNode, DanglingReference,
+    Precompose,
+};
+pub use hierarchy::{DanglingParent, LayerHierarchy};
+pub use image::{image_reference, ImageAsset, ImageIndex, ImageTrim};
+pub use time::{precompose_frame, LayerTime};
+pub use transform::WorldTransform;
+pub use video::{StaticFrameStats, VideoLayout, VideoLayoutError, VideoRect};
+
+use std::ops::Range;
+
+use crate::{Frame, LayerType};
 
 /// Layer info trait
 pub trait LayerInfo {
@@ -6,6 +25,10 @@ pub trait LayerInfo {
     fn get_layer_name(&self) -> Option<&str>;
     /// Get layer type
     fn get_layer_type(&self) -> LayerType;
+    /// Get layer ID
+    fn get_layer_id(&self) -> u32;
+    /// Get the frames in which the layer is shown, from its in point to its out point
+    fn get_layer_time_span(&self) -> Option<Range<Frame>>;
 }
 
 /// Traversable trait
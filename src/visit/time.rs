@@ -0,0 +1,218 @@
+use crate::{CompositionReference, Frame, LayerBlock, LayerType};
+
+use super::{composition_reference, Composition, CompositionGraph};
+
+/// Timing of a layer at a frame of the main composition
+#[derive(Debug, Clone, Copy)]
+pub struct LayerTime<'b> {
+    /// The composition containing the layer
+    pub composition: Composition<'b>,
+    /// The layer
+    pub layer: &'b LayerBlock<'b>,
+    /// Frame on the timeline of the composition containing the layer, used to evaluate the
+    /// properties of the layer
+    pub frame: Frame,
+    /// Frame of the layer content, i.e. the frame of the referenced composition for a
+    /// PreCompose layer, or `frame` for other layers
+    pub local_frame: Frame,
+    /// Whether the layer is visible at `frame`, together with all enclosing PreCompose layers
+    pub active: bool,
+    /// Nesting depth of the composition, 0 for the main composition
+    pub depth: usize,
+}
+
+/// Frame rate of a composition, `None` if the composition has no attributes
+fn frame_rate(composition: &Composition) -> Option<f32> {
+    composition
+        .attributes()
+        .map(|attributes| attributes.frame_rate)
+        .filter(|rate| *rate > 0.)
+}
+
+/// Frame of the referenced composition shown by a PreCompose layer at a frame of the
+/// containing composition.
+///
+/// - With a time remap that is animated or differs from the default 0, the remapped time in
+///   seconds is converted by `child_rate`; a static time remap freezes the content at one frame.
+/// - Otherwise the frame is offset by `composition_start_time`, slowed down by the stretch of
+///   the layer, and converted from `parent_rate` to `child_rate`.
+pub fn precompose_frame(
+    layer: &LayerBlock,
+    reference: &CompositionReference,
+    frame: Frame,
+    parent_rate: f32,
+    child_rate: f32,
+) -> Frame {
+    let attributes = layer.attributes();
+    let time_remap = attributes
+        .filter(|attributes| {
+            let time_remap = &attributes.time_remap;
+            time_remap.is_animated() || time_remap.value().is_some_and(|value| *value != 0.)
+        })
+        .and_then(|attributes| attributes.time_remap.value_at(frame));
+    if let Some(seconds) = time_remap {
+        return (seconds as f64 * child_rate as f64).round() as Frame;
+    }
+
+    let stretch = attributes.map_or(1., |attributes| attributes.stretch_factor());
+    let time_scale = match (parent_rate > 0., child_rate > 0.) {
+        (true, true) => child_rate as f64 / parent_rate as f64,
+        _ => 1.,
+    };
+    let offset = (frame - reference.composition_start_time.to_i64()) as f64;
+    (offset / stretch * time_scale).round() as Frame
+}
+
+impl<'b> CompositionGraph<'b> {
+    /// Timing of all layers reachable from the main composition at a frame, in depth-first order.
+    /// Layers in a PreCompose are only active if the PreCompose layer is active.
+    pub fn layer_times(&self, frame: Frame) -> Vec<LayerTime<'b>> {
+        let mut times = vec![];
+        if let Some(main) = self.main() {
+            self.collect_times(main, frame, true, &mut vec![], &mut times);
+        }
+        times
+    }
+
+    fn collect_times(
+        &self,
+        composition: Composition<'b>,
+        frame: Frame,
+        parent_active: bool,
+        stack: &mut Vec<u32>,
+        times: &mut Vec<LayerTime<'b>>,
+    ) {
+        stack.push(composition.id());
+        for layer in composition.layers() {
+            let active = parent_active
+                && layer
+                    .attributes()
+                    .is_none_or(|attributes| attributes.is_active_at(frame));
+            let child = self.precompose_child(&composition, layer, frame);
+            times.push(LayerTime {
+                composition,
+                layer,
+                frame,
+                local_frame: child.map_or(frame, |(_, child_frame)| child_frame),
+                active,
+                depth: stack.len() - 1,
+            });
+            match child {
+                Some((child, child_frame)) if !stack.contains(&child.id()) => {
+                    self.collect_times(child, child_frame, active, stack, times);
+                }
+                _ => {}
+            }
+        }
+        stack.pop();
+    }
+
+    /// The composition referred by a PreCompose layer and its frame at `frame`
+    pub(super) fn precompose_child(
+        &self,
+        composition: &Composition<'b>,
+        layer: &LayerBlock,
+        frame: Frame,
+    ) -> Option<(Composition<'b>, Frame)> {
+        if layer.r#type != LayerType::PreCompose {
+            return None;
+        }
+        let reference = composition_reference(layer)?;
+        let child = self.get(reference.id.to_u32())?;
+        let parent_rate = frame_rate(composition).unwrap_or(0.);
+        let child_rate = frame_rate(&child).unwrap_or(parent_rate);
+        let child_frame = precompose_frame(layer, reference, frame, parent_rate, child_rate);
+        Some((child, child_frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedUint32, Time},
+        BlendMode, CompositionReference, Keyframe, KeyframeInterpolationType, LayerAttributes,
+        LayerBlock, LayerType, Point, Property, Ratio, Tag, TagBlock, TagBody, TagCode,
+        TrackMatteType,
+    };
+
+    use super::precompose_frame;
+
+    fn new_attributes(start_time: u64, duration: u64, stretch: Ratio) -> LayerAttributes {
+        LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(0),
+            stretch,
+            start_time: Time::from(start_time),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::Value(0.),
+            duration: Time::from(duration),
+            name: String::new(),
+        }
+    }
+
+    fn new_layer(attributes: LayerAttributes) -> LayerBlock<'static> {
+        LayerBlock {
+            r#type: LayerType::PreCompose,
+            id: EncodedUint32::from(1),
+            tag_block: TagBlock {
+                tags: vec![Tag::new(
+                    TagCode::LayerAttributes,
+                    TagBody::LayerAttributes(attributes),
+                )],
+            },
+        }
+    }
+
+    #[test]
+    fn test_layer_active() {
+        let attributes = new_attributes(10, 20, Ratio::one());
+        assert!(!attributes.is_active_at(9));
+        assert!(attributes.is_active_at(10));
+        assert!(attributes.is_active_at(29));
+        assert!(!attributes.is_active_at(30));
+    }
+
+    #[test]
+    fn test_precompose_frame() {
+        let reference = CompositionReference {
+            id: EncodedUint32::from(2),
+            composition_start_time: Time::from(10),
+        };
+
+        let layer = new_layer(new_attributes(10, 20, Ratio::one()));
+        assert_eq!(precompose_frame(&layer, &reference, 25, 30., 30.), 15);
+        // 60fps child composition in a 30fps parent
+        assert_eq!(precompose_frame(&layer, &reference, 25, 30., 60.), 30);
+
+        // Stretched to 200%, the content plays at half speed
+        let layer = new_layer(new_attributes(10, 40, Ratio::new(2, 1)));
+        assert_eq!(precompose_frame(&layer, &reference, 30, 30., 30.), 10);
+
+        // Time remap from 1s to 0s over frames 10 ~ 40
+        let mut attributes = new_attributes(10, 30, Ratio::one());
+        attributes.time_remap = Property::Animated(vec![Keyframe {
+            start_value: 1.,
+            end_value: 0.,
+            start_time: Time::from(10),
+            end_time: Time::from(40),
+            interpolation_type: KeyframeInterpolationType::Linear,
+            bezier_out: vec![],
+            bezier_in: vec![],
+            spatial_out: Point::zero(),
+            spatial_in: Point::zero(),
+        }]);
+        let layer = new_layer(attributes);
+        assert_eq!(precompose_frame(&layer, &reference, 25, 30., 30.), 15);
+        assert_eq!(precompose_frame(&layer, &reference, 40, 30., 30.), 0);
+
+        // Static time remap of 0.5s freezes the content at frame 15
+        let mut attributes = new_attributes(10, 30, Ratio::one());
+        attributes.time_remap = Property::Value(0.5);
+        let layer = new_layer(attributes);
+        assert_eq!(precompose_frame(&layer, &reference, 10, 30., 30.), 15);
+        assert_eq!(precompose_frame(&layer, &reference, 35, 30., 30.), 15);
+    }
+}
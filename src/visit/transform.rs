@@ -0,0 +1,211 @@
+use crate::{Frame, LayerBlock, Matrix2D};
+
+use super::{Composition, CompositionGraph, LayerHierarchy};
+
+/// Transform of a layer in the coordinate space of the main composition
+#[derive(Debug, Clone, Copy)]
+pub struct WorldTransform<'b> {
+    /// The composition containing the layer
+    pub composition: Composition<'b>,
+    /// The layer
+    pub layer: &'b LayerBlock<'b>,
+    /// Frame on the timeline of the composition containing the layer
+    pub frame: Frame,
+    /// Matrix mapping layer coordinates to main composition coordinates
+    pub matrix: Matrix2D,
+    /// Effective opacity in 0 ~ 1, multiplied by the opacity of enclosing PreCompose layers
+    pub opacity: f32,
+    /// Nesting depth of the composition, 0 for the main composition
+    pub depth: usize,
+}
+
+/// Matrix and opacity of a single layer, without parents
+fn local_transform(layer: &LayerBlock, frame: Frame) -> (Matrix2D, f32) {
+    match layer.transform() {
+        Some(transform) => (transform.matrix_at(frame), transform.opacity_at(frame)),
+        None => (Matrix2D::IDENTITY, 1.),
+    }
+}
+
+impl<'b> CompositionGraph<'b> {
+    /// World transforms of all layers reachable from the main composition at a frame, in
+    /// depth-first order. Parent layer matrices are concatenated (parents do not affect opacity),
+    /// and layers in a PreCompose are mapped through the PreCompose layer at the frame given by
+    /// [`precompose_frame`](super::precompose_frame).
+    pub fn world_transforms(&self, frame: Frame) -> Vec<WorldTransform<'b>> {
+        let mut transforms = vec![];
+        if let Some(main) = self.main() {
+            self.collect_transforms(
+                main,
+                frame,
+                (Matrix2D::IDENTITY, 1.),
+                &mut vec![],
+                &mut transforms,
+            );
+        }
+        transforms
+    }
+
+    fn collect_transforms(
+        &self,
+        composition: Composition<'b>,
+        frame: Frame,
+        (parent_matrix, parent_opacity): (Matrix2D, f32),
+        stack: &mut Vec<u32>,
+        transforms: &mut Vec<WorldTransform<'b>>,
+    ) {
+        stack.push(composition.id());
+        let hierarchy = LayerHierarchy::new(composition.layers());
+        for layer in composition.layers() {
+            let (mut matrix, opacity) = local_transform(layer, frame);
+            for ancestor in hierarchy.ancestors(layer.id.to_u32()) {
+                matrix = matrix.then(&local_transform(ancestor, frame).0);
+            }
+            let transform = WorldTransform {
+                composition,
+                layer,
+                frame,
+                matrix: matrix.then(&parent_matrix),
+                opacity: opacity * parent_opacity,
+                depth: stack.len() - 1,
+            };
+            transforms.push(transform);
+
+            match self.precompose_child(&composition, layer, frame) {
+                Some((child, child_frame)) if !stack.contains(&child.id()) => {
+                    self.collect_transforms(
+                        child,
+                        child_frame,
+                        (transform.matrix, transform.opacity),
+                        stack,
+                        transforms,
+                    );
+                }
+                _ => {}
+            }
+        }
+        stack.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedUint32, Time},
+        BlendMode, CompositionReference, FileHeader, LayerAttributes, LayerBlock, LayerType, Pag,
+        Point, Property, Ratio, Tag, TagBlock, TagBody, TagCode, TrackMatteType, Transform2D,
+        VectorCompositionBlock,
+    };
+
+    fn new_transform(position: Point, scale: Point, opacity: u8) -> Tag<'static> {
+        let transform = Transform2D {
+            anchor_point: Property::Value(Point::zero()),
+            position: Property::Value(position),
+            x_position: Property::Value(0.),
+            y_position: Property::Value(0.),
+            scale: Property::Value(scale),
+            rotation: Property::Value(0.),
+            opacity: Property::Value(opacity),
+        };
+        Tag::new(TagCode::Transform2D, TagBody::Transform2D(transform))
+    }
+
+    fn new_attributes(parent: u32) -> Tag<'static> {
+        let attributes = LayerAttributes {
+            is_active: true,
+            auto_orientation: false,
+            motion_blur: false,
+            parent: EncodedUint32::from(parent),
+            stretch: Ratio::one(),
+            start_time: Time::from(0),
+            blend_mode: BlendMode::Normal,
+            track_matte_type: TrackMatteType::None,
+            time_remap: Property::Value(0.),
+            duration: Time::from(100),
+            name: String::new(),
+        };
+        Tag::new(
+            TagCode::LayerAttributes,
+            TagBody::LayerAttributes(attributes),
+        )
+    }
+
+    fn new_layer(r#type: LayerType, id: u32, tags: Vec<Tag<'static>>) -> Tag<'static> {
+        let layer = LayerBlock {
+            r#type,
+            id: EncodedUint32::from(id),
+            tag_block: TagBlock { tags },
+        };
+        Tag::new(TagCode::LayerBlock, TagBody::LayerBlock(layer))
+    }
+
+    fn new_composition(id: u32, layers: Vec<Tag<'static>>) -> Tag<'static> {
+        let composition = VectorCompositionBlock {
+            id: EncodedUint32::from(id),
+            tag_block: TagBlock { tags: layers },
+        };
+        Tag::new(
+            TagCode::VectorCompositionBlock,
+            TagBody::VectorCompositionBlock(composition),
+        )
+    }
+
+    #[test]
+    fn test_world_transforms() {
+        let child = new_composition(
+            2,
+            vec![
+                new_layer(
+                    LayerType::Null,
+                    20,
+                    vec![
+                        new_attributes(0),
+                        new_transform(Point::new(5., 0.), Point::one(), 255),
+                    ],
+                ),
+                new_layer(
+                    LayerType::Solid,
+                    21,
+                    vec![
+                        new_attributes(20),
+                        new_transform(Point::new(10., 10.), Point::one(), 51),
+                    ],
+                ),
+            ],
+        );
+        let reference = CompositionReference {
+            id: EncodedUint32::from(2),
+            composition_start_time: Time::from(10),
+        };
+        let main = new_composition(
+            1,
+            vec![new_layer(
+                LayerType::PreCompose,
+                10,
+                vec![
+                    new_attributes(0),
+                    new_transform(Point::new(100., 50.), Point::new(2., 2.), 128),
+                    Tag::new(
+                        TagCode::CompositionReference,
+                        TagBody::CompositionReference(reference),
+                    ),
+                ],
+            )],
+        );
+
+        let mut pag = Pag::new(FileHeader::default());
+        pag.push_tag(child);
+        pag.push_tag(main);
+        let graph = pag.composition_graph();
+        let transforms = graph.world_transforms(25);
+        assert_eq!(transforms.len(), 3);
+
+        let layer = transforms[2];
+        assert_eq!(layer.layer.id.to_u32(), 21);
+        assert_eq!(layer.depth, 1);
+        assert_eq!(layer.frame, 15);
+        // (0, 0) → (10, 10) → parent (15, 10) → precompose (130, 70)
+        assert_eq!(layer.matrix.map_point(Point::zero()), Point::new(130., 70.));
+        assert!((layer.opacity - 0.2 * 128. / 255.).abs() < 1e-6);
+    }
+}
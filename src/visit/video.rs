@@ -0,0 +1,389 @@
+use thiserror::Error;
+
+use crate::{Frame, TimeRange, VideoSequence};
+
+/// A rectangular region of the decoded video frames, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl VideoRect {
+    /// Right edge, fails if it exceeds `i32::MAX`
+    fn right(&self) -> Result<i32, VideoLayoutError> {
+        self.x
+            .checked_add(self.width)
+            .ok_or(VideoLayoutError::Overflow { region: *self })
+    }
+
+    /// Bottom edge, fails if it exceeds `i32::MAX`
+    fn bottom(&self) -> Result<i32, VideoLayoutError> {
+        self.y
+            .checked_add(self.height)
+            .ok_or(VideoLayoutError::Overflow { region: *self })
+    }
+
+    fn intersects(&self, other: &VideoRect) -> Result<bool, VideoLayoutError> {
+        Ok(self.x < other.right()?
+            && other.x < self.right()?
+            && self.y < other.bottom()?
+            && other.y < self.bottom()?)
+    }
+}
+
+/// Invalid layout of a video sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum VideoLayoutError {
+    #[error("empty video size {width}x{height}")]
+    EmptySize { width: i32, height: i32 },
+    #[error("negative alpha start ({x}, {y})")]
+    NegativeAlphaStart { x: i32, y: i32 },
+    #[error("alpha region {alpha:?} overlaps color region {color:?}")]
+    Overlap { color: VideoRect, alpha: VideoRect },
+    #[error("region {region:?} exceeds the range of i32")]
+    Overflow { region: VideoRect },
+    #[error("region {region:?} exceeds video frame {width}x{height}")]
+    OutOfBounds {
+        region: VideoRect,
+        width: i32,
+        height: i32,
+    },
+}
+
+/// Layout of the decoded frames of a video sequence.
+///
+/// Videos with alpha store the color and the alpha channel side by side in one frame: the color
+/// region at the top left, and the alpha region of the same size starting at
+/// `alpha_start_x`/`alpha_start_y`, where the red channel holds the alpha value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoLayout {
+    /// Width of the decoded frames, padded to an even number for H.264
+    pub video_width: i32,
+    /// Height of the decoded frames, padded to an even number for H.264
+    pub video_height: i32,
+    /// Region of the color channels
+    pub color: VideoRect,
+    /// Region of the alpha channel, `None` if the video has no alpha
+    pub alpha: Option<VideoRect>,
+}
+
+impl VideoLayout {
+    /// Size of the recombined RGBA output, i.e. the size of the color region
+    pub fn output_size(&self) -> (i32, i32) {
+        (self.color.width, self.color.height)
+    }
+
+    /// Check that both regions fit within decoded frames of the given size,
+    /// e.g. the size reported by the decoder
+    pub fn check_frame_size(&self, width: i32, height: i32) -> Result<(), VideoLayoutError> {
+        for region in [Some(self.color), self.alpha].into_iter().flatten() {
+            if region.right()? > width || region.bottom()? > height {
+                return Err(VideoLayoutError::OutOfBounds {
+                    region,
+                    width,
+                    height,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VideoSequence<'_> {
+    /// Layout of the color and alpha regions of the decoded frames.
+    /// Fails if the size is empty, or if the alpha region overlaps the color region.
+    pub fn layout(&self) -> Result<VideoLayout, VideoLayoutError> {
+        let (width, height) = (self.width.to_i32(), self.height.to_i32());
+        if width <= 0 || height <= 0 {
+            return Err(VideoLayoutError::EmptySize { width, height });
+        }
+        let color = VideoRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+
+        let alpha = match (self.alpha_start_x, self.alpha_start_y) {
+            (Some(x), Some(y)) => {
+                let (x, y) = (x.to_i32(), y.to_i32());
+                if x < 0 || y < 0 {
+                    return Err(VideoLayoutError::NegativeAlphaStart { x, y });
+                }
+                let alpha = VideoRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                };
+                if alpha.intersects(&color)? {
+                    return Err(VideoLayoutError::Overlap { color, alpha });
+                }
+                Some(alpha)
+            }
+            _ => None,
+        };
+
+        let (right, bottom) = match alpha {
+            Some(alpha) => (alpha.right()?.max(width), alpha.bottom()?.max(height)),
+            None => (width, height),
+        };
+        // H.264 pads the decoded frames to an even size
+        let frame = VideoRect {
+            x: 0,
+            y: 0,
+            width: right,
+            height: bottom,
+        };
+        let overflow = VideoLayoutError::Overflow { region: frame };
+        Ok(VideoLayout {
+            video_width: right.checked_add(right % 2).ok_or(overflow)?,
+            video_height: bottom.checked_add(bottom % 2).ok_or(overflow)?,
+            color,
+            alpha,
+        })
+    }
+}
+
+/// Statistics of the static frames of a video sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticFrameStats {
+    /// Total number of frames, including the frames inside static ranges that are not stored
+    pub total_frames: u64,
+    /// Number of frames that reuse an earlier frame and need no decoding
+    pub static_frames: u64,
+}
+
+impl StaticFrameStats {
+    /// Percentage of static frames among all frames
+    pub fn static_percentage(&self) -> f64 {
+        match self.total_frames {
+            0 => 0.,
+            total => self.static_frames as f64 * 100. / total as f64,
+        }
+    }
+}
+
+impl VideoSequence<'_> {
+    /// The decoded frame to show instead of `frame`, `None` if `frame` is not static.
+    ///
+    /// A frame inside a static time range shows the start frame of the range. Overlapping or
+    /// chained ranges are followed until a frame that is not static, e.g. with the ranges
+    /// `1 ~ 5` and `4 ~ 8`, frame 7 shows frame 1.
+    pub fn reused_frame(&self, frame: Frame) -> Option<Frame> {
+        let is_reused = |range: &&TimeRange, frame| {
+            range.contains(frame) && range.start.to_u64() as Frame != frame
+        };
+        let mut reused = frame;
+        // The start of a matching range is always before the current frame
+        while let Some(range) = self
+            .static_time_ranges
+            .iter()
+            .find(|range| is_reused(range, reused))
+        {
+            reused = range.start.to_u64() as Frame;
+        }
+        (reused != frame).then_some(reused)
+    }
+
+    /// Whether the frame is static, i.e. shows an earlier frame and needs no decoding
+    pub fn is_static_frame(&self, frame: Frame) -> bool {
+        self.reused_frame(frame).is_some()
+    }
+
+    /// Count the static frames, frames inside overlapping ranges are counted once
+    pub fn static_frame_stats(&self) -> StaticFrameStats {
+        let last_frame = self
+            .video_frames
+            .iter()
+            .map(|frame| frame.frame.to_u64())
+            .chain(
+                self.static_time_ranges
+                    .iter()
+                    .map(|range| range.end.to_u64()),
+            )
+            .max();
+        let total_frames = last_frame.map_or(0, |frame| frame + 1);
+
+        let mut ranges = self
+            .static_time_ranges
+            .iter()
+            .map(|range| (range.start.to_u64(), range.end.to_u64()))
+            .filter(|(start, end)| start < end)
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+        let mut static_frames = 0;
+        // The last frame counted so far
+        let mut counted = None;
+        for (start, end) in ranges {
+            let first = match counted {
+                Some(counted) if counted >= start => counted + 1,
+                _ => start + 1,
+            };
+            if first <= end {
+                static_frames += end - first + 1;
+                counted = Some(end);
+            }
+        }
+
+        StaticFrameStats {
+            total_frames,
+            static_frames,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{EncodedInt32, EncodedUint32, Time},
+        ByteData, TimeRange, VideoFrame, VideoSequence,
+    };
+
+    use super::{StaticFrameStats, VideoLayoutError, VideoRect};
+
+    fn new_sequence(
+        width: i32,
+        height: i32,
+        alpha_start: Option<(i32, i32)>,
+    ) -> VideoSequence<'static> {
+        VideoSequence {
+            width: EncodedInt32::from(width),
+            height: EncodedInt32::from(height),
+            frame_rate: 30.,
+            alpha_start_x: alpha_start.map(|(x, _)| EncodedInt32::from(x)),
+            alpha_start_y: alpha_start.map(|(_, y)| EncodedInt32::from(y)),
+            sps_data: ByteData::from_vec(vec![]),
+            pps_data: ByteData::from_vec(vec![]),
+            frame_count: EncodedUint32::from(0),
+            is_key_frame_flag: vec![],
+            video_frames: vec![],
+            static_time_ranges: vec![],
+        }
+    }
+
+    #[test]
+    fn test_video_layout() {
+        let layout = new_sequence(101, 50, None).layout().unwrap();
+        assert_eq!((layout.video_width, layout.video_height), (102, 50));
+        assert_eq!(layout.output_size(), (101, 50));
+        assert_eq!(layout.alpha, None);
+
+        // Side by side with a 2 pixel gap
+        let layout = new_sequence(100, 50, Some((102, 0))).layout().unwrap();
+        assert_eq!((layout.video_width, layout.video_height), (202, 50));
+        assert_eq!(
+            layout.alpha,
+            Some(VideoRect {
+                x: 102,
+                y: 0,
+                width: 100,
+                height: 50
+            })
+        );
+        assert_eq!(layout.output_size(), (100, 50));
+        assert!(layout.check_frame_size(202, 50).is_ok());
+        assert!(matches!(
+            layout.check_frame_size(200, 50),
+            Err(VideoLayoutError::OutOfBounds { .. })
+        ));
+
+        assert!(matches!(
+            new_sequence(100, 50, Some((50, 0))).layout(),
+            Err(VideoLayoutError::Overlap { .. })
+        ));
+        assert_eq!(
+            new_sequence(100, 50, Some((-1, 0))).layout(),
+            Err(VideoLayoutError::NegativeAlphaStart { x: -1, y: 0 })
+        );
+        assert_eq!(
+            new_sequence(0, 50, None).layout(),
+            Err(VideoLayoutError::EmptySize {
+                width: 0,
+                height: 50
+            })
+        );
+    }
+
+    #[test]
+    fn test_video_layout_overflow() {
+        assert_eq!(
+            new_sequence(100, 50, Some((i32::MAX, 0))).layout(),
+            Err(VideoLayoutError::Overflow {
+                region: VideoRect {
+                    x: i32::MAX,
+                    y: 0,
+                    width: 100,
+                    height: 50
+                }
+            })
+        );
+        // The padding of an odd size exceeds i32
+        assert!(matches!(
+            new_sequence(i32::MAX, 50, None).layout(),
+            Err(VideoLayoutError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_static_frames() {
+        let range = |start, end| TimeRange {
+            start: Time::from(start),
+            end: Time::from(end),
+        };
+        // Frames 2 ~ 5 show frame 1, frames 8 ~ 9 show frame 7
+        let frames = [0, 1, 6, 7];
+        let sequence = VideoSequence {
+            frame_count: EncodedUint32::from(frames.len() as u32),
+            video_frames: frames
+                .iter()
+                .map(|&frame| VideoFrame {
+                    is_key_frame: frame == 0,
+                    frame: Time::from(frame),
+                    file_bytes: ByteData::from_vec(vec![]),
+                })
+                .collect(),
+            static_time_ranges: vec![range(1, 5), range(3, 4), range(7, 9)],
+            ..new_sequence(16, 16, None)
+        };
+
+        assert_eq!(sequence.reused_frame(0), None);
+        assert_eq!(sequence.reused_frame(1), None);
+        assert_eq!(sequence.reused_frame(3), Some(1));
+        assert_eq!(sequence.reused_frame(4), Some(1));
+        assert_eq!(sequence.reused_frame(5), Some(1));
+        assert_eq!(sequence.reused_frame(6), None);
+        assert!(sequence.is_static_frame(9));
+        assert!(!sequence.is_static_frame(10));
+
+        let stats = sequence.static_frame_stats();
+        assert_eq!(
+            stats,
+            StaticFrameStats {
+                total_frames: 10,
+                static_frames: 6,
+            }
+        );
+        assert_eq!(stats.static_percentage(), 60.);
+    }
+
+    #[test]
+    fn test_chained_static_frames() {
+        let range = |start, end| TimeRange {
+            start: Time::from(start),
+            end: Time::from(end),
+        };
+        // Frame 4 shows frame 1, so frames 5 ~ 8 show frame 1 as well
+        let sequence = VideoSequence {
+            static_time_ranges: vec![range(4, 8), range(1, 5)],
+            ..new_sequence(16, 16, None)
+        };
+        assert_eq!(sequence.reused_frame(4), Some(1));
+        assert_eq!(sequence.reused_frame(7), Some(1));
+        assert_eq!(sequence.reused_frame(8), Some(1));
+        assert_eq!(sequence.reused_frame(9), None);
+    }
+}
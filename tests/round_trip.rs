@@ -0,0 +1,58 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use pag_parser::encode::check_round_trip;
+
+/// 参与往返测试的样本目录，样本不随仓库提供
+const SAMPLE_DIRS: [&str; 2] = ["tests/pags", "libpag/resources"];
+
+fn collect_pags(dir: &Path, pags: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_pags(&path, pags)?;
+        } else if path.extension().is_some_and(|ext| ext == "pag") {
+            pags.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires sample files under tests/pags or libpag/resources"]
+fn test_round_trip_all_pag() -> io::Result<()> {
+    let mut pags = vec![];
+    for dir in SAMPLE_DIRS {
+        let dir = Path::new(dir);
+        if dir.is_dir() {
+            collect_pags(dir, &mut pags)?;
+        }
+    }
+    pags.sort();
+    assert!(
+        !pags.is_empty(),
+        "no sample files found under {:?}",
+        SAMPLE_DIRS
+    );
+
+    let mut failures = vec![];
+    for path in &pags {
+        let input = fs::read(path)?;
+        match check_round_trip(&input) {
+            Ok(None) => {}
+            Ok(Some(mismatch)) => failures.push(format!("{}: {}", path.display(), mismatch)),
+            Err(e) => failures.push(format!("{}: parse error: {:?}", path.display(), e)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} files failed to round-trip:\n{}",
+        failures.len(),
+        pags.len(),
+        failures.join("\n")
+    );
+    Ok(())
+}